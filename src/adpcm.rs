@@ -0,0 +1,179 @@
+// Decodes 4-bit IMA ADPCM, (wFormatTag 0x0011,) into 16-bit samples.
+// See https://wiki.multimedia.cx/index.php/IMA_ADPCM and https://wiki.multimedia.cx/index.php/Microsoft_IMA_ADPCM
+
+use std::io::{Error, ErrorKind, Read, Result};
+
+const STEP_TABLE: [i32; 89] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66,
+    73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371, 408, 449,
+    494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272,
+    2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630, 9493,
+    10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794, 32767,
+];
+
+const INDEX_TABLE: [i32; 16] = [-1, -1, -1, -1, 2, 4, 6, 8, -1, -1, -1, -1, 2, 4, 6, 8];
+
+// Each channel's block header is a 16-bit sample, the step index, and a reserved byte
+const BLOCK_HEADER_SIZE: usize = 4;
+
+// The samples in each channel are interleaved in groups of 4 bytes, (8 samples)
+const INTERLEAVE_SIZE: usize = 4;
+
+/// The number of samples, per channel, in a block of 'block_len' bytes. Returns 0 if the block is too short to
+/// hold its headers
+pub(crate) fn samples_in_block(block_len: usize, num_channels: usize) -> usize {
+    let header_len = BLOCK_HEADER_SIZE * num_channels;
+    if num_channels == 0 || block_len < header_len {
+        return 0;
+    }
+
+    let data_len = (block_len - header_len) / (INTERLEAVE_SIZE * num_channels) * INTERLEAVE_SIZE;
+    1 + data_len * 2
+}
+
+/// Decodes a block into interleaved 16-bit samples
+pub(crate) fn decode_block(block: &[u8], num_channels: usize) -> Result<Vec<i16>> {
+    let samples_per_channel = samples_in_block(block.len(), num_channels);
+    if samples_per_channel == 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "The IMA ADPCM block is too short",
+        ));
+    }
+
+    let mut samples = vec![0i16; samples_per_channel * num_channels];
+
+    for channel in 0..num_channels {
+        let header = &block[channel * BLOCK_HEADER_SIZE..(channel + 1) * BLOCK_HEADER_SIZE];
+        let mut predictor = i16::from_le_bytes([header[0], header[1]]) as i32;
+        let mut step_index = (header[2] as i32).clamp(0, 88);
+        samples[channel] = predictor as i16;
+
+        let mut sample = 1;
+        let mut offset = num_channels * BLOCK_HEADER_SIZE + channel * INTERLEAVE_SIZE;
+        while sample < samples_per_channel {
+            for byte in &block[offset..offset + INTERLEAVE_SIZE] {
+                for nibble in [byte & 0x0F, byte >> 4] {
+                    let step = STEP_TABLE[step_index as usize];
+
+                    let mut diff = step >> 3;
+                    if nibble & 1 == 1 {
+                        diff += step >> 2;
+                    }
+                    if nibble & 2 == 2 {
+                        diff += step >> 1;
+                    }
+                    if nibble & 4 == 4 {
+                        diff += step;
+                    }
+                    if nibble & 8 == 8 {
+                        diff = -diff;
+                    }
+
+                    predictor = (predictor + diff).clamp(i16::MIN as i32, i16::MAX as i32);
+                    step_index = (step_index + INDEX_TABLE[nibble as usize]).clamp(0, 88);
+
+                    samples[sample * num_channels + channel] = predictor as i16;
+                    sample += 1;
+                }
+            }
+
+            offset += INTERLEAVE_SIZE * num_channels;
+        }
+    }
+
+    Ok(samples)
+}
+
+/// Decodes the data chunk a block at a time. The decoded samples are read as little-endian 16-bit samples
+pub(crate) struct AdpcmDecoder {
+    num_channels: usize,
+    block_align: usize,
+    data_length: usize,
+    // The number of bytes in the data chunk that haven't been decoded
+    bytes_remaining: usize,
+    decoded: Vec<u8>,
+    position: usize,
+}
+
+impl AdpcmDecoder {
+    pub(crate) fn new(num_channels: usize, block_align: usize, data_length: usize) -> AdpcmDecoder {
+        AdpcmDecoder {
+            num_channels,
+            block_align,
+            data_length,
+            bytes_remaining: data_length,
+            decoded: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Starts decoding from the first block again
+    pub(crate) fn restart(&mut self) {
+        self.bytes_remaining = self.data_length;
+        self.decoded.clear();
+        self.position = 0;
+    }
+
+    /// Reads decoded samples. 'reader' must be positioned at the next undecoded block
+    pub(crate) fn read(&mut self, reader: &mut impl Read, buf: &mut [u8]) -> Result<usize> {
+        if self.position == self.decoded.len() {
+            let block_len = self.block_align.min(self.bytes_remaining);
+            if samples_in_block(block_len, self.num_channels) == 0 {
+                return Ok(0);
+            }
+
+            let mut block = vec![0u8; block_len];
+            reader.read_exact(&mut block)?;
+            self.bytes_remaining -= block_len;
+
+            self.decoded = decode_block(&block, self.num_channels)?
+                .iter()
+                .flat_map(|sample| sample.to_le_bytes())
+                .collect();
+            self.position = 0;
+        }
+
+        let len = buf.len().min(self.decoded.len() - self.position);
+        buf[..len].copy_from_slice(&self.decoded[self.position..self.position + len]);
+        self.position += len;
+
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_mono_block() {
+        // Predictor 100, step index 0
+        let block = [100, 0, 0, 0, 0x74, 0x98, 0x00, 0x00];
+        assert_eq!(9, samples_in_block(block.len(), 1));
+
+        let samples = decode_block(&block, 1).unwrap();
+        assert_eq!(vec![100, 107, 123, 121, 115, 117, 118, 119, 120], samples);
+    }
+
+    #[test]
+    fn decode_stereo_block() {
+        // Left: predictor 100, step index 0. Right: predictor -100, step index 0
+        let mut block = vec![100, 0, 0, 0, 0x9C, 0xFF, 0, 0];
+        block.extend([0x74, 0x98, 0x00, 0x00]);
+        block.extend([0x00, 0x00, 0x00, 0x00]);
+
+        let samples = decode_block(&block, 2).unwrap();
+        let left: Vec<i16> = samples.iter().step_by(2).copied().collect();
+        let right: Vec<i16> = samples.iter().skip(1).step_by(2).copied().collect();
+        assert_eq!(vec![100, 107, 123, 121, 115, 117, 118, 119, 120], left);
+        // With a step index of 0, a nibble of 0 doesn't change the sample
+        assert_eq!(vec![-100; 9], right);
+    }
+
+    #[test]
+    fn decode_short_block() {
+        let err = decode_block(&[0, 0, 0], 1).expect_err("The block is too short");
+        assert_eq!(ErrorKind::InvalidData, err.kind());
+    }
+}
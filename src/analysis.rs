@@ -0,0 +1,752 @@
+//! Quality-control tools that read a wav and report on its contents
+
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::io::{Error, ErrorKind, Result};
+
+use crate::samples_by_channel::{SamplesByChannel, DBFS_FLOOR};
+use crate::wave_header::{ChannelPosition, Channels, SampleFormat};
+use crate::wave_reader::StreamWavReader;
+
+/// The result of checking how a stereo wav folds down to mono
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MonoCompatReport {
+    /// The correlation coefficient between the left and right channels. 1.0 is identical, 0.0 is unrelated, and
+    /// -1.0 is fully out of phase. (And will cancel when folded down to mono)
+    pub correlation: f32,
+    /// The peak level of the mono fold-down, (L+R)/2
+    pub mono_peak: f32,
+}
+
+/// Checks a stereo wav for phase cancellation when it's folded down to mono
+///
+/// # Arguments
+///
+/// * 'reader' - The wav to check. It must be stereo
+pub fn mono_compatibility(reader: StreamWavReader<f32>) -> Result<MonoCompatReport> {
+    if *reader.info().channels() != Channels::new().front_left().front_right() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Mono compatibility can only be checked on stereo wavs",
+        ));
+    }
+
+    let mut sum_left_right = 0f64;
+    let mut sum_left_squared = 0f64;
+    let mut sum_right_squared = 0f64;
+    let mut mono_peak = 0f32;
+
+    for samples_result in reader.into_iter() {
+        let samples = samples_result?;
+        let left = samples.front_left.expect("Left channel missing");
+        let right = samples.front_right.expect("Right channel missing");
+
+        sum_left_right += (left as f64) * (right as f64);
+        sum_left_squared += (left as f64) * (left as f64);
+        sum_right_squared += (right as f64) * (right as f64);
+
+        mono_peak = mono_peak.max(((left + right) / 2.0).abs());
+    }
+
+    let denominator = (sum_left_squared * sum_right_squared).sqrt();
+    let correlation = if denominator > 0.0 {
+        (sum_left_right / denominator) as f32
+    } else {
+        0.0
+    };
+
+    Ok(MonoCompatReport {
+        correlation,
+        mono_peak,
+    })
+}
+
+/// The samples that would clip if a floating point wav was converted to an integer sample format
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ClipPreview {
+    /// The number of samples, counting each channel separately, that would clip
+    pub clipped_samples: usize,
+    /// The largest absolute value of the samples that would clip, or 0.0 if no samples would clip
+    pub peak: f32,
+}
+
+/// Counts the samples that would clip if the wav is converted to 'target'. Integer sample formats clip samples
+/// outside of -1.0 to 1.0. Floating point never clips
+///
+/// # Arguments
+///
+/// * 'reader' - The wav to check
+/// * 'target' - The sample format that the wav would be converted to
+pub fn clipping_preview(reader: StreamWavReader<f32>, target: SampleFormat) -> Result<ClipPreview> {
+    let mut clip_preview = ClipPreview {
+        clipped_samples: 0,
+        peak: 0.0,
+    };
+
+    for samples_result in reader.into_iter() {
+        if target == SampleFormat::Float {
+            // Errors are still returned, even though nothing can clip
+            samples_result?;
+            continue;
+        }
+
+        for sample in samples_result?.to_vec() {
+            let magnitude = sample.abs();
+            if magnitude > 1.0 {
+                clip_preview.clipped_samples += 1;
+                clip_preview.peak = clip_preview.peak.max(magnitude);
+            }
+        }
+    }
+
+    Ok(clip_preview)
+}
+
+/// Checks if every sample in the wav is within 'threshold'. Stops reading at the first sample that exceeds
+/// 'threshold', so non-silent wavs are usually detected quickly
+///
+/// # Arguments
+///
+/// * 'reader' - The wav to check
+/// * 'threshold' - The largest magnitude that is considered silent. 0.0 only allows digital silence
+pub fn is_silent(reader: StreamWavReader<f32>, threshold: f32) -> Result<bool> {
+    for samples_result in reader.into_iter() {
+        for sample in samples_result?.to_vec() {
+            if sample.abs() > threshold {
+                return Ok(false);
+            }
+        }
+    }
+
+    Ok(true)
+}
+
+/// Finds clicks and pops: Samples that differ from the previous sample, in the same channel, by more than
+/// 'threshold'. Returns the index of each sample, and the channel. A click that lasts for one sample is usually
+/// found twice: Once where it starts, and once where it ends
+///
+/// # Arguments
+///
+/// * 'reader' - The wav to check
+/// * 'threshold' - The largest change between two samples that isn't a click. (IE, 0.5) Full scale is 1.0, so
+///   changes can be as large as 2.0
+pub fn detect_clicks(
+    reader: StreamWavReader<f32>,
+    threshold: f32,
+) -> Result<Vec<(usize, ChannelPosition)>> {
+    let mut clicks = Vec::new();
+    let mut previous: Option<SamplesByChannel<f32>> = None;
+
+    for (sample, samples_result) in reader.into_iter().enumerate() {
+        let samples = samples_result?;
+
+        if let Some(previous) = previous {
+            for position in Channels::all_positions() {
+                if let (Some(value), Some(previous_value)) =
+                    (samples.get(position), previous.get(position))
+                {
+                    if (value - previous_value).abs() > threshold {
+                        clicks.push((sample, position));
+                    }
+                }
+            }
+        }
+
+        previous = Some(samples);
+    }
+
+    Ok(clicks)
+}
+
+/// Counts how many samples in each channel fall into each of 'bins' equal-width bins between -1.0 and 1.0. Samples
+/// outside of -1.0 to 1.0 are counted in the first or last bin. Gaps in the histogram can reveal quantization to a
+/// lower bit depth
+///
+/// # Arguments
+///
+/// * 'reader' - The wav to analyze
+/// * 'bins' - The number of bins
+pub fn histogram(reader: StreamWavReader<f32>, bins: usize) -> Result<SamplesByChannel<Vec<u64>>> {
+    if bins == 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "The histogram must have at least 1 bin",
+        ));
+    }
+
+    let mut channels: Vec<(ChannelPosition, Vec<u64>)> = reader
+        .info()
+        .channels()
+        .positions()
+        .map(|position| (position, vec![0; bins]))
+        .collect();
+
+    for samples_result in reader.into_iter() {
+        let samples = samples_result?;
+        for (position, counts) in channels.iter_mut() {
+            if let Some(sample) = samples.get(*position) {
+                let bin = ((sample + 1.0) / 2.0 * bins as f32).floor();
+                counts[(bin.max(0.0) as usize).min(bins - 1)] += 1;
+            }
+        }
+    }
+
+    let mut histogram = SamplesByChannel::new();
+    for (position, counts) in channels {
+        histogram.set(position, counts);
+    }
+
+    Ok(histogram)
+}
+
+/// Infers the resolution that a wav's samples actually use, by finding the low bits that are unused in every
+/// sample. (IE, a 24-bit wav that was upconverted from 16-bit only uses 16 bits.) Low bits that are always zero are
+/// unused. For positive samples, low bits that are always one are also unused, because that's how wave_stream
+/// upconverts. Returns 0 for digital silence
+///
+/// # Arguments
+///
+/// * 'reader' - The wav to analyze, read as 24-bit samples. (get_stream_i24_reader())
+pub fn effective_bit_depth(reader: StreamWavReader<i32>) -> Result<u8> {
+    let mut zero_padded = 0i32;
+    let mut one_padded = 0i32;
+
+    for samples_result in reader.into_iter() {
+        for sample in samples_result?.to_vec() {
+            zero_padded |= sample;
+            one_padded |= if sample > 0 { sample + 1 } else { sample };
+        }
+    }
+
+    let unused_bits = zero_padded
+        .trailing_zeros()
+        .max(one_padded.trailing_zeros())
+        .min(24);
+
+    Ok((24 - unused_bits) as u8)
+}
+
+/// The levels of a channel, (or all channels,) that are checked when mastering
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LevelStats {
+    /// The largest absolute value of any sample
+    pub peak: f32,
+    /// The root mean square of the samples
+    pub rms: f32,
+    /// The ratio of the peak to the RMS, in decibels. A sine wave is about 3.01 dB. 0.0 for digital silence
+    pub crest_factor_db: f32,
+    /// How far the peak is below 0 dBFS, in decibels. Negative if the peak is above full scale. Digital silence
+    /// has -DBFS_FLOOR of headroom
+    pub headroom_db: f32,
+}
+
+/// The levels of a wav, for each channel and for all channels combined
+#[derive(Debug, Clone, PartialEq)]
+pub struct MasteringReport {
+    pub channels: SamplesByChannel<LevelStats>,
+    pub overall: LevelStats,
+}
+
+// The running totals that LevelStats is calculated from
+#[derive(Default)]
+struct LevelAccumulator {
+    peak: f32,
+    sum_squared: f64,
+    count: u64,
+}
+
+impl LevelAccumulator {
+    fn add(&mut self, sample: f32) {
+        self.peak = self.peak.max(sample.abs());
+        self.sum_squared += (sample as f64) * (sample as f64);
+        self.count += 1;
+    }
+
+    fn stats(&self) -> LevelStats {
+        let rms = if self.count > 0 {
+            (self.sum_squared / self.count as f64).sqrt() as f32
+        } else {
+            0.0
+        };
+
+        let crest_factor_db = if rms > 0.0 {
+            20.0 * (self.peak / rms).log10()
+        } else {
+            0.0
+        };
+
+        LevelStats {
+            peak: self.peak,
+            rms,
+            crest_factor_db,
+            headroom_db: -(20.0 * self.peak.log10()).max(DBFS_FLOOR),
+        }
+    }
+}
+
+/// Measures the peak, RMS, crest factor, and headroom of each channel, and of all channels combined, in a single
+/// pass through the wav
+///
+/// # Arguments
+///
+/// * 'reader' - The wav to measure
+pub fn mastering_stats(reader: StreamWavReader<f32>) -> Result<MasteringReport> {
+    let mut channels: Vec<(ChannelPosition, LevelAccumulator)> = reader
+        .info()
+        .channels()
+        .positions()
+        .map(|position| (position, LevelAccumulator::default()))
+        .collect();
+    let mut overall = LevelAccumulator::default();
+
+    for samples_result in reader.into_iter() {
+        let samples = samples_result?;
+        for (position, accumulator) in channels.iter_mut() {
+            if let Some(sample) = samples.get(*position) {
+                accumulator.add(*sample);
+                overall.add(*sample);
+            }
+        }
+    }
+
+    let mut report = MasteringReport {
+        channels: SamplesByChannel::new(),
+        overall: overall.stats(),
+    };
+    for (position, accumulator) in channels {
+        report.channels.set(position, accumulator.stats());
+    }
+
+    Ok(report)
+}
+
+// The number of samples on each side of an interpolated point that true_peak uses
+const TRUE_PEAK_TAPS_PER_SIDE: usize = 16;
+
+/// Measures the true (inter-sample) peak of each channel, by oversampling with a windowed sinc interpolator. The
+/// true peak can be higher than the highest sample, and is what a true-peak (dBTP) meter reports
+///
+/// # Arguments
+///
+/// * 'reader' - The wav to measure
+/// * 'oversample' - The oversampling factor. 4 is typical for true-peak metering
+pub fn true_peak(reader: StreamWavReader<f32>, oversample: usize) -> Result<SamplesByChannel<f32>> {
+    if oversample == 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "The oversampling factor must be at least 1",
+        ));
+    }
+
+    // The coefficients for each point that is interpolated between two samples
+    let phases: Vec<Vec<f32>> = (1..oversample)
+        .map(|phase| {
+            let fraction = phase as f32 / oversample as f32;
+            (0..TRUE_PEAK_TAPS_PER_SIDE * 2)
+                .map(|tap| {
+                    let x = (TRUE_PEAK_TAPS_PER_SIDE - 1) as f32 + fraction - tap as f32;
+                    let sinc = (PI * x).sin() / (PI * x);
+                    let window = 0.5 * (1.0 + (PI * x / TRUE_PEAK_TAPS_PER_SIDE as f32).cos());
+                    sinc * window
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut channels: Vec<(ChannelPosition, VecDeque<f32>, f32)> = reader
+        .info()
+        .channels()
+        .positions()
+        .map(|position| {
+            (
+                position,
+                VecDeque::from(vec![0.0; TRUE_PEAK_TAPS_PER_SIDE * 2]),
+                0.0,
+            )
+        })
+        .collect();
+
+    let push_sample = |history: &mut VecDeque<f32>, peak: &mut f32, sample: f32| {
+        *peak = peak.max(sample.abs());
+
+        history.pop_front();
+        history.push_back(sample);
+
+        for coefficients in phases.iter() {
+            let interpolated: f32 = history
+                .iter()
+                .zip(coefficients)
+                .map(|(sample, coefficient)| sample * coefficient)
+                .sum();
+            *peak = peak.max(interpolated.abs());
+        }
+    };
+
+    for samples_result in reader.into_iter() {
+        let samples = samples_result?;
+        for (position, history, peak) in channels.iter_mut() {
+            let sample = match samples.get(*position) {
+                Some(sample) => *sample,
+                None => 0.0,
+            };
+            push_sample(history, peak, sample);
+        }
+    }
+
+    // Interpolate between the last samples
+    for (_, history, peak) in channels.iter_mut() {
+        for _ in 0..TRUE_PEAK_TAPS_PER_SIDE {
+            push_sample(history, peak, 0.0);
+        }
+    }
+
+    let mut true_peaks = SamplesByChannel::new();
+    for (position, _, peak) in channels {
+        true_peaks.set(position, peak);
+    }
+
+    Ok(true_peaks)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_1_SQRT_2, TAU};
+    use std::path::Path;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::open_wav::OpenWav;
+    use crate::wave_header::WavHeader;
+    use crate::wave_reader::StreamOpenWavReader;
+    use crate::{read_wav_from_file_path, write_wav_to_file_path};
+
+    #[test]
+    fn mono_compatibility_out_of_phase() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("out_of_phase.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Float,
+            channels: Channels::new().front_left().front_right(),
+            sample_rate: 48000,
+        };
+
+        let open_wav = write_wav_to_file_path(&path, header).unwrap();
+        open_wav
+            .write_all_f32((0..48000).map(|sample| {
+                let value = (TAU * 440.0 * sample as f32 / 48000.0).sin() * 0.5;
+                Ok(SamplesByChannel::new()
+                    .front_left(value)
+                    .front_right(-value))
+            }))
+            .unwrap();
+
+        let reader = read_wav_from_file_path(&path)
+            .unwrap()
+            .get_stream_f32_reader()
+            .unwrap();
+        let report = mono_compatibility(reader).unwrap();
+
+        assert!(
+            (report.correlation + 1.0).abs() < 0.0001,
+            "Correlation should be -1, was {0}",
+            report.correlation
+        );
+        assert!(
+            report.mono_peak < 0.0001,
+            "Mono fold-down should cancel, peak was {0}",
+            report.mono_peak
+        );
+    }
+
+    #[test]
+    fn clipping_preview_counts_clipped_samples() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("hot.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Float,
+            channels: Channels::new().front_left().front_right(),
+            sample_rate: 48000,
+        };
+
+        let open_wav = write_wav_to_file_path(&path, header).unwrap();
+        open_wav
+            .write_all_f32((0..100).map(|sample| {
+                let left = match sample {
+                    10 => 1.5,
+                    20 => -1.25,
+                    _ => 0.5,
+                };
+                let right = if sample == 30 { 1.0001 } else { 1.0 };
+                Ok(SamplesByChannel::new().front_left(left).front_right(right))
+            }))
+            .unwrap();
+
+        let reader = read_wav_from_file_path(&path)
+            .unwrap()
+            .get_stream_f32_reader()
+            .unwrap();
+        let clip_preview = clipping_preview(reader, SampleFormat::Int16).unwrap();
+        assert_eq!(3, clip_preview.clipped_samples);
+        assert_eq!(1.5, clip_preview.peak);
+
+        let reader = read_wav_from_file_path(&path)
+            .unwrap()
+            .get_stream_f32_reader()
+            .unwrap();
+        let clip_preview = clipping_preview(reader, SampleFormat::Float).unwrap();
+        assert_eq!(0, clip_preview.clipped_samples);
+    }
+
+    fn is_silent_mono(samples: impl Iterator<Item = f32> + 'static, threshold: f32) -> bool {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("is_silent.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Float,
+            channels: Channels::new().front_left(),
+            sample_rate: 48000,
+        };
+
+        let open_wav = write_wav_to_file_path(&path, header).unwrap();
+        open_wav
+            .write_all_f32(samples.map(|sample| Ok(SamplesByChannel::new().front_left(sample))))
+            .unwrap();
+
+        let reader = read_wav_from_file_path(&path)
+            .unwrap()
+            .get_stream_f32_reader()
+            .unwrap();
+        is_silent(reader, threshold).unwrap()
+    }
+
+    #[test]
+    fn is_silent_digital_silence() {
+        assert!(is_silent_mono((0..48000).map(|_| 0.0), 0.0));
+    }
+
+    #[test]
+    fn is_silent_spike() {
+        assert!(!is_silent_mono(
+            (0..48000).map(|sample| if sample == 30000 { -0.5 } else { 0.0 }),
+            0.001
+        ));
+    }
+
+    #[test]
+    fn is_silent_below_threshold() {
+        assert!(is_silent_mono(
+            (0..48000).map(|sample| if sample % 2 == 0 { 0.0009 } else { -0.0009 }),
+            0.001
+        ));
+    }
+
+    #[test]
+    fn detect_clicks_spike() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("click.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Float,
+            channels: Channels::new().front_left().front_right(),
+            sample_rate: 48000,
+        };
+
+        // A quiet sine in both channels, with a spike in the right channel
+        let open_wav = write_wav_to_file_path(&path, header).unwrap();
+        open_wav
+            .write_all_f32((0..48000).map(|sample| {
+                let value = (TAU * 440.0 * sample as f32 / 48000.0).sin() * 0.1;
+                let spike = if sample == 30000 { 0.8 } else { 0.0 };
+                Ok(SamplesByChannel::new()
+                    .front_left(value)
+                    .front_right(value + spike))
+            }))
+            .unwrap();
+
+        let reader = read_wav_from_file_path(&path)
+            .unwrap()
+            .get_stream_f32_reader()
+            .unwrap();
+        assert_eq!(
+            vec![
+                (30000, ChannelPosition::FrontRight),
+                (30001, ChannelPosition::FrontRight)
+            ],
+            detect_clicks(reader, 0.5).unwrap()
+        );
+    }
+
+    #[test]
+    fn histogram_counts() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("histogram.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Float,
+            channels: Channels::new().front_left().front_right(),
+            sample_rate: 48000,
+        };
+
+        // 1.0 and 1.5 are clamped into the last bin, -2.0 into the first
+        let right_values = [-1.0, -0.75, -0.25, 0.25, 1.0, 1.5, -2.0, 0.75];
+
+        let open_wav = write_wav_to_file_path(&path, header).unwrap();
+        open_wav
+            .write_all_f32((0..800).map(move |sample| {
+                Ok(SamplesByChannel::new()
+                    .front_left(0.0)
+                    .front_right(right_values[sample % right_values.len()]))
+            }))
+            .unwrap();
+
+        let open_wav = read_wav_from_file_path(&path).unwrap();
+        let len_samples = open_wav.len_samples() as u64;
+        let histogram = histogram(open_wav.get_stream_f32_reader().unwrap(), 4).unwrap();
+
+        let left = histogram.front_left.unwrap();
+        let right = histogram.front_right.unwrap();
+        assert_eq!(vec![0, 0, 800, 0], left);
+        assert_eq!(vec![300, 100, 100, 300], right);
+        assert_eq!(len_samples, left.iter().sum::<u64>());
+        assert_eq!(len_samples, right.iter().sum::<u64>());
+    }
+
+    #[test]
+    fn mastering_stats_sine() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("mastering.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Float,
+            channels: Channels::new().front_left().front_right(),
+            sample_rate: 48000,
+        };
+
+        // 480hz, so the wav is a whole number of cycles. The right channel is silent
+        let open_wav = write_wav_to_file_path(&path, header).unwrap();
+        open_wav
+            .write_all_f32((0..48000).map(|sample| {
+                Ok(SamplesByChannel::new()
+                    .front_left((sample as f32 / 100.0 * TAU).sin() * 0.5)
+                    .front_right(0.0))
+            }))
+            .unwrap();
+
+        let open_wav = read_wav_from_file_path(&path).unwrap();
+        let report = mastering_stats(open_wav.get_stream_f32_reader().unwrap()).unwrap();
+
+        let left = report.channels.front_left.unwrap();
+        assert!((left.peak - 0.5).abs() < 0.0001);
+        assert!((left.rms - 0.5 * FRAC_1_SQRT_2).abs() < 0.0001);
+        assert!((left.crest_factor_db - 3.01).abs() < 0.01);
+        assert!((left.headroom_db - 6.02).abs() < 0.01);
+
+        let right = report.channels.front_right.unwrap();
+        assert_eq!(0.0, right.peak);
+        assert_eq!(0.0, right.crest_factor_db);
+        assert_eq!(-DBFS_FLOOR, right.headroom_db);
+
+        // Half of the samples are silent, so the overall RMS is lower
+        assert_eq!(left.peak, report.overall.peak);
+        assert!((report.overall.rms - 0.25).abs() < 0.0001);
+        assert!((report.overall.crest_factor_db - 6.02).abs() < 0.01);
+    }
+
+    fn effective_bit_depth_24(write: impl FnOnce(&Path, WavHeader)) -> u8 {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("effective_bit_depth.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Int24,
+            channels: Channels::new().front_left(),
+            sample_rate: 48000,
+        };
+        write(&path, header);
+
+        let reader = read_wav_from_file_path(&path)
+            .unwrap()
+            .get_stream_i24_reader()
+            .unwrap();
+        effective_bit_depth(reader).unwrap()
+    }
+
+    #[test]
+    fn effective_bit_depth_shifted_16() {
+        let bits = effective_bit_depth_24(|path, header| {
+            write_wav_to_file_path(path, header)
+                .unwrap()
+                .write_all_i24(
+                    (-1000i32..1000)
+                        .map(|sample| Ok(SamplesByChannel::new().front_left((sample * 31) << 8))),
+                )
+                .unwrap()
+        });
+        assert_eq!(16, bits);
+    }
+
+    #[test]
+    fn effective_bit_depth_upconverted_16() {
+        let bits = effective_bit_depth_24(|path, header| {
+            write_wav_to_file_path(path, header)
+                .unwrap()
+                .write_all_i16(
+                    (-1000i16..1000)
+                        .map(|sample| Ok(SamplesByChannel::new().front_left(sample * 31))),
+                )
+                .unwrap()
+        });
+        assert_eq!(16, bits);
+    }
+
+    #[test]
+    fn effective_bit_depth_full_24() {
+        let bits = effective_bit_depth_24(|path, header| {
+            write_wav_to_file_path(path, header)
+                .unwrap()
+                .write_all_i24(
+                    (-1000i32..1000)
+                        .map(|sample| Ok(SamplesByChannel::new().front_left(sample * 4099))),
+                )
+                .unwrap()
+        });
+        assert_eq!(24, bits);
+    }
+
+    #[test]
+    fn true_peak_between_samples() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("quarter_rate.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Float,
+            channels: Channels::new().front_left(),
+            sample_rate: 48000,
+        };
+
+        // A sine wave at a quarter of the sample rate, sampled 45 degrees away from its peaks
+        let open_wav = write_wav_to_file_path(&path, header).unwrap();
+        open_wav
+            .write_all_f32((0..4800).map(|sample| {
+                let phase = TAU * sample as f32 / 4.0 + TAU / 8.0;
+                Ok(SamplesByChannel::new().front_left(phase.sin()))
+            }))
+            .unwrap();
+
+        let reader = read_wav_from_file_path(&path)
+            .unwrap()
+            .get_stream_f32_reader()
+            .unwrap();
+        let sample_peak = true_peak(reader, 1).unwrap().front_left.unwrap();
+        assert!((sample_peak - FRAC_1_SQRT_2).abs() < 0.001, "{sample_peak}");
+
+        let reader = read_wav_from_file_path(&path)
+            .unwrap()
+            .get_stream_f32_reader()
+            .unwrap();
+        let peak = true_peak(reader, 4).unwrap().front_left.unwrap();
+        assert!((peak - 1.0).abs() < 0.02, "{peak}");
+    }
+}
@@ -0,0 +1,46 @@
+// Counts the bytes that are read from, or written to, a stream. (IE, for progress UIs that show bytes transferred)
+
+use std::io::{Read, Result, Seek, SeekFrom, Write};
+
+pub(crate) struct ByteCounter<T> {
+    inner: T,
+    count: usize,
+}
+
+impl<T> ByteCounter<T> {
+    // 'count' is the number of bytes that were already read or written, (IE, the headers,) before counting started
+    pub(crate) fn new(inner: T, count: usize) -> ByteCounter<T> {
+        ByteCounter { inner, count }
+    }
+
+    pub(crate) fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl<T: Read> Read for ByteCounter<T> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let bytes_read = self.inner.read(buf)?;
+        self.count += bytes_read;
+        Ok(bytes_read)
+    }
+}
+
+impl<T: Write> Write for ByteCounter<T> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let bytes_written = self.inner.write(buf)?;
+        self.count += bytes_written;
+        Ok(bytes_written)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+// Seeking doesn't change the count, because no bytes are transferred
+impl<T: Seek> Seek for ByteCounter<T> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        self.inner.seek(pos)
+    }
+}
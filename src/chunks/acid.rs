@@ -0,0 +1,167 @@
+use std::io::{Read, Result, Write};
+
+use crate::{ReadEx, WriteEx};
+
+/// Loop and tempo metadata that music production tools (IE, ACIDized loops) store in an "acid" chunk
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AcidChunk {
+    /// Flags. (IE, 0x01 is a one-shot, 0x02 is a root note, 0x04 is stretch)
+    pub file_type: u32,
+    /// The MIDI note of the root, (IE, 60 is middle C,) only used if the root note flag is set
+    pub root_note: u16,
+    /// The number of beats in the loop
+    pub beats: u32,
+    /// The tempo in beats per minute
+    pub tempo: f32,
+}
+
+/// The size of an acid chunk, excluding the chunk's name and length
+pub(crate) const ACID_CHUNK_SIZE: usize = 24;
+
+impl AcidChunk {
+    /// Reads an acid chunk, or None if the chunk is too short to be an acid chunk. (A malformed acid chunk doesn't
+    /// prevent reading the samples.) The reader must be positioned after the chunk's name and length
+    ///
+    /// # Arguments
+    ///
+    /// * 'reader' - The reader to read the chunk from
+    /// * 'chunk_size' - The length of the chunk, as written in the wav
+    pub(crate) fn from_reader(
+        reader: &mut impl Read,
+        chunk_size: usize,
+    ) -> Result<Option<AcidChunk>> {
+        if chunk_size < ACID_CHUNK_SIZE {
+            reader.skip(chunk_size)?;
+            return Ok(None);
+        }
+
+        let file_type = reader.read_u32()?;
+        let root_note = reader.read_u16()?;
+        // Unknown, usually 0x8000
+        reader.skip(2)?;
+        // Unknown, usually 0.0
+        reader.skip(4)?;
+        let beats = reader.read_u32()?;
+        // Meter denominator and numerator
+        reader.skip(4)?;
+        let tempo = reader.read_f32()?;
+
+        reader.skip(chunk_size - ACID_CHUNK_SIZE)?;
+
+        Ok(Some(AcidChunk {
+            file_type,
+            root_note,
+            beats,
+            tempo,
+        }))
+    }
+
+    /// Writes the acid chunk's contents, excluding the chunk's name and length
+    ///
+    /// # Arguments
+    ///
+    /// * 'writer' - The writer to write the chunk into
+    pub(crate) fn to_writer(self, writer: &mut impl Write) -> Result<()> {
+        writer.write_u32(self.file_type)?;
+        writer.write_u16(self.root_note)?;
+        writer.write_u16(0x8000)?;
+        writer.write_f32(0.0)?;
+        writer.write_u32(self.beats)?;
+        // 4/4 time
+        writer.write_u16(4)?;
+        writer.write_u16(4)?;
+        writer.write_f32(self.tempo)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::open_wav::OpenWav;
+    use crate::samples_by_channel::SamplesByChannel;
+    use crate::wave_header::{Channels, SampleFormat, WavHeader};
+    use crate::{read_wav_from_file_path, write_wav_to_file_path};
+
+    #[test]
+    fn acid_chunk_round_trip() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("loop.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Int16,
+            channels: Channels::new().front_left(),
+            sample_rate: 44100,
+        };
+
+        // 4 beats at 120 BPM is 2 seconds
+        let acid_chunk = AcidChunk {
+            file_type: 0x02,
+            root_note: 60,
+            beats: 4,
+            tempo: 120.0,
+        };
+
+        let mut open_wav = write_wav_to_file_path(&path, header).unwrap();
+        open_wav.set_acid_chunk(acid_chunk).unwrap();
+        open_wav
+            .write_all_i16((0..88200).map(|_| Ok(SamplesByChannel::new().front_left(0))))
+            .unwrap();
+
+        let open_wav = read_wav_from_file_path(&path).unwrap();
+        assert_eq!(Some(&acid_chunk), open_wav.acid_chunk());
+        assert_eq!(88200, open_wav.len_samples());
+    }
+
+    #[test]
+    fn acid_chunk_after_data_started() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("loop.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Int16,
+            channels: Channels::new().front_left(),
+            sample_rate: 44100,
+        };
+
+        let mut open_wav = write_wav_to_file_path(&path, header).unwrap();
+        open_wav.flush().unwrap();
+
+        let err = open_wav
+            .set_acid_chunk(AcidChunk {
+                file_type: 0,
+                root_note: 60,
+                beats: 4,
+                tempo: 120.0,
+            })
+            .expect_err("The data chunk was already started");
+        assert_eq!(ErrorKind::Unsupported, err.kind());
+    }
+
+    #[test]
+    fn short_acid_chunk_is_ignored() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("loop.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Int16,
+            channels: Channels::new().front_left(),
+            sample_rate: 44100,
+        };
+
+        let mut open_wav = write_wav_to_file_path(&path, header).unwrap();
+        open_wav.set_chunk(b"acid", vec![0x02, 0, 0, 0]).unwrap();
+        open_wav
+            .write_all_i16((0..100).map(|_| Ok(SamplesByChannel::new().front_left(0))))
+            .unwrap();
+
+        let open_wav = read_wav_from_file_path(&path).unwrap();
+        assert_eq!(None, open_wav.acid_chunk());
+        assert_eq!(100, open_wav.len_samples());
+    }
+}
@@ -0,0 +1,271 @@
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+use super::{read_fixed_string, write_fixed_string};
+use crate::{ReadEx, WriteEx};
+
+/// The size of the fixed part of a bext chunk, excluding the chunk's name and length
+pub(crate) const BEXT_FIXED_SIZE: usize = 602;
+
+/// The size of a UMID, (SMPTE 330M Unique Material Identifier,) in a bext chunk
+pub const UMID_SIZE: usize = 64;
+
+/// The broadcast extension, (bext chunk,) from the EBU's Broadcast Wave Format
+#[derive(Debug, Clone, PartialEq)]
+pub struct BroadcastExtension {
+    /// A description of the sound, up to 256 characters
+    pub description: String,
+    /// The name of the originator, up to 32 characters
+    pub originator: String,
+    /// The originator's reference for the sound, up to 32 characters
+    pub originator_reference: String,
+    /// yyyy-mm-dd
+    pub origination_date: String,
+    /// hh:mm:ss
+    pub origination_time: String,
+    /// The position of the first sample, in samples since midnight
+    pub time_reference: u64,
+    /// The version of the bext chunk's layout
+    pub version: u16,
+    /// The SMPTE 330M Unique Material Identifier. All zeros when the wav doesn't have a UMID
+    pub umid: [u8; UMID_SIZE],
+    /// The processing steps that the audio went through, as ASCII text with one step per line. (IE,
+    /// "A=PCM,F=48000,W=24,M=stereo,T=original\r\n")
+    pub coding_history: String,
+}
+
+impl Default for BroadcastExtension {
+    fn default() -> Self {
+        BroadcastExtension {
+            description: String::new(),
+            originator: String::new(),
+            originator_reference: String::new(),
+            origination_date: String::new(),
+            origination_time: String::new(),
+            time_reference: 0,
+            version: 0,
+            umid: [0u8; UMID_SIZE],
+            coding_history: String::new(),
+        }
+    }
+}
+
+impl BroadcastExtension {
+    /// Reads a bext chunk, or None if the chunk is shorter than the fixed-size fields. (A malformed bext chunk doesn't
+    /// prevent reading the samples.) The reader must be positioned after the chunk's name and length
+    ///
+    /// # Arguments
+    ///
+    /// * 'reader' - The reader to read the chunk from
+    /// * 'chunk_size' - The length of the chunk, as written in the wav
+    pub(crate) fn from_reader(
+        reader: &mut impl Read,
+        chunk_size: usize,
+    ) -> Result<Option<BroadcastExtension>> {
+        if chunk_size < BEXT_FIXED_SIZE {
+            reader.skip(chunk_size)?;
+            return Ok(None);
+        }
+
+        let description = read_fixed_string(reader, 256)?;
+        let originator = read_fixed_string(reader, 32)?;
+        let originator_reference = read_fixed_string(reader, 32)?;
+        let origination_date = read_fixed_string(reader, 10)?;
+        let origination_time = read_fixed_string(reader, 8)?;
+        let time_reference_low = reader.read_u32()? as u64;
+        let time_reference_high = reader.read_u32()? as u64;
+        let version = reader.read_u16()?;
+
+        let mut umid = [0u8; UMID_SIZE];
+        reader.read_exact(&mut umid)?;
+
+        // Loudness and reserved
+        reader.skip(BEXT_FIXED_SIZE - 348 - UMID_SIZE)?;
+
+        let coding_history = read_fixed_string(reader, chunk_size - BEXT_FIXED_SIZE)?;
+
+        Ok(Some(BroadcastExtension {
+            description,
+            originator,
+            originator_reference,
+            origination_date,
+            origination_time,
+            time_reference: (time_reference_high << 32) | time_reference_low,
+            version,
+            umid,
+            coding_history,
+        }))
+    }
+
+    /// Writes the bext chunk's contents, excluding the chunk's name and length
+    ///
+    /// # Arguments
+    ///
+    /// * 'writer' - The writer to write the chunk into
+    pub(crate) fn to_writer(&self, writer: &mut impl Write) -> Result<()> {
+        write_fixed_string(writer, &self.description, 256)?;
+        write_fixed_string(writer, &self.originator, 32)?;
+        write_fixed_string(writer, &self.originator_reference, 32)?;
+        write_fixed_string(writer, &self.origination_date, 10)?;
+        write_fixed_string(writer, &self.origination_time, 8)?;
+        writer.write_u32(self.time_reference as u32)?;
+        writer.write_u32((self.time_reference >> 32) as u32)?;
+        writer.write_u16(self.version)?;
+
+        writer.write_all(&self.umid)?;
+
+        // Loudness and reserved
+        writer.write_all(&[0u8; BEXT_FIXED_SIZE - 348 - UMID_SIZE])?;
+
+        writer.write_all(self.coding_history.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+/// Verifies that a date is in yyyy-mm-dd format
+pub(crate) fn validate_origination_date(date: &str) -> Result<()> {
+    validate_format(date, "dddd-dd-dd", "Origination date must be yyyy-mm-dd")
+}
+
+/// Verifies that a time is in hh:mm:ss format
+pub(crate) fn validate_origination_time(time: &str) -> Result<()> {
+    validate_format(time, "dd:dd:dd", "Origination time must be hh:mm:ss")
+}
+
+// Compares to a pattern, where 'd' is any digit
+fn validate_format(value: &str, pattern: &str, message: &str) -> Result<()> {
+    let valid = value.len() == pattern.len()
+        && value.bytes().zip(pattern.bytes()).all(|(c, p)| match p {
+            b'd' => c.is_ascii_digit(),
+            _ => c == p,
+        });
+
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("{message}, actual value: {value}"),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::chunks::list_chunks;
+    use crate::open_wav::OpenWav;
+    use crate::samples_by_channel::SamplesByChannel;
+    use crate::wave_header::{Channels, SampleFormat, WavHeader};
+    use crate::{read_wav_from_file_path, write_wav_to_file_path};
+
+    #[test]
+    fn origination_round_trip() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("bext.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Int24,
+            channels: Channels::new().front_left(),
+            sample_rate: 48000,
+        };
+
+        let mut open_wav = write_wav_to_file_path(&path, header).unwrap();
+
+        let err = open_wav
+            .set_origination("2024/01/31", "12:34:56")
+            .expect_err("Wrong date format");
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+        let err = open_wav
+            .set_origination("2024-01-31", "12:34")
+            .expect_err("Wrong time format");
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+
+        open_wav.set_origination("2024-01-31", "12:34:56").unwrap();
+        open_wav
+            .write_all_i24((0..10).map(|sample| Ok(SamplesByChannel::new().front_left(sample))))
+            .unwrap();
+
+        let open_wav = read_wav_from_file_path(&path).unwrap();
+        assert_eq!(Some("2024-01-31"), open_wav.origination_date());
+        assert_eq!(Some("12:34:56"), open_wav.origination_time());
+    }
+
+    #[test]
+    fn coding_history_round_trip() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("bext.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Int24,
+            channels: Channels::new().front_left().front_right(),
+            sample_rate: 48000,
+        };
+
+        let mut umid = [0u8; UMID_SIZE];
+        for (index, byte) in umid.iter_mut().enumerate() {
+            *byte = index as u8;
+        }
+
+        let broadcast_extension = BroadcastExtension {
+            description: "Interview".to_string(),
+            origination_date: "2024-01-31".to_string(),
+            origination_time: "12:34:56".to_string(),
+            version: 1,
+            umid,
+            coding_history:
+                "A=ANALOGUE,M=stereo,T=Studer A807\r\nA=PCM,F=48000,W=24,M=stereo,T=Transfer\r\n"
+                    .to_string(),
+            ..Default::default()
+        };
+
+        let mut open_wav = write_wav_to_file_path(&path, header).unwrap();
+        open_wav
+            .set_broadcast_extension(&broadcast_extension)
+            .unwrap();
+        open_wav
+            .write_all_i24(
+                (0..10).map(|sample| Ok(SamplesByChannel::new().front_left(sample).front_right(0))),
+            )
+            .unwrap();
+
+        let open_wav = read_wav_from_file_path(&path).unwrap();
+        assert_eq!(Some(&broadcast_extension), open_wav.broadcast_extension());
+        assert_eq!(Some("2024-01-31"), open_wav.origination_date());
+
+        let chunks = list_chunks(&mut std::fs::File::open(&path).unwrap()).unwrap();
+        let bext = chunks
+            .iter()
+            .find(|chunk| &chunk.id == b"bext")
+            .expect("bext chunk missing");
+        assert_eq!(
+            BEXT_FIXED_SIZE + broadcast_extension.coding_history.len(),
+            bext.size
+        );
+    }
+
+    #[test]
+    fn short_bext_chunk_is_ignored() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("bext.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Int24,
+            channels: Channels::new().front_left(),
+            sample_rate: 48000,
+        };
+
+        let mut open_wav = write_wav_to_file_path(&path, header).unwrap();
+        open_wav.set_chunk(b"bext", vec![b'a'; 256]).unwrap();
+        open_wav
+            .write_all_i24((0..10).map(|sample| Ok(SamplesByChannel::new().front_left(sample))))
+            .unwrap();
+
+        let open_wav = read_wav_from_file_path(&path).unwrap();
+        assert_eq!(None, open_wav.broadcast_extension());
+        assert_eq!(None, open_wav.origination_date());
+        assert_eq!(10, open_wav.len_samples());
+    }
+}
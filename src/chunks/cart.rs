@@ -0,0 +1,270 @@
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+use super::{read_fixed_string, write_fixed_string};
+use crate::{ReadEx, WriteEx};
+
+/// The size of the fixed part of a cart chunk, excluding the chunk's name and length
+pub(crate) const CART_FIXED_SIZE: usize = 2048;
+
+/// The number of timers in a cart chunk
+pub const CART_TIMER_COUNT: usize = 8;
+
+/// A marker in a cart chunk. (IE, "SEGs" for the segue start, or "INTe" for the end of the intro)
+#[derive(Debug, Clone, PartialEq)]
+pub struct CartTimer {
+    /// The timer's usage, up to 4 characters
+    pub usage: String,
+    /// The timer's position, in samples
+    pub value: u32,
+}
+
+/// Metadata that radio automation systems store in a cart chunk, from AES46
+#[derive(Debug, Clone, PartialEq)]
+pub struct CartChunk {
+    /// The version of the cart chunk's layout, (IE, "0101",) 4 characters
+    pub version: String,
+    pub title: String,
+    pub artist: String,
+    pub cut_id: String,
+    pub client_id: String,
+    pub category: String,
+    pub classification: String,
+    pub out_cue: String,
+    /// yyyy-mm-dd
+    pub start_date: String,
+    /// hh:mm:ss
+    pub start_time: String,
+    /// yyyy-mm-dd
+    pub end_date: String,
+    /// hh:mm:ss
+    pub end_time: String,
+    pub producer_app_id: String,
+    pub producer_app_version: String,
+    pub user_def: String,
+    /// The sample value of 0dB
+    pub level_reference: i32,
+    /// Up to CART_TIMER_COUNT timers. Unused timers are not included
+    pub post_timers: Vec<CartTimer>,
+    pub url: String,
+    pub tag_text: String,
+}
+
+impl Default for CartChunk {
+    fn default() -> Self {
+        CartChunk {
+            version: "0101".to_string(),
+            title: String::new(),
+            artist: String::new(),
+            cut_id: String::new(),
+            client_id: String::new(),
+            category: String::new(),
+            classification: String::new(),
+            out_cue: String::new(),
+            start_date: String::new(),
+            start_time: String::new(),
+            end_date: String::new(),
+            end_time: String::new(),
+            producer_app_id: String::new(),
+            producer_app_version: String::new(),
+            user_def: String::new(),
+            level_reference: 0,
+            post_timers: Vec::new(),
+            url: String::new(),
+            tag_text: String::new(),
+        }
+    }
+}
+
+impl CartChunk {
+    /// Reads a cart chunk, or None if the chunk is shorter than the fixed-size fields. (A malformed cart chunk doesn't
+    /// prevent reading the samples.) The reader must be positioned after the chunk's name and length
+    ///
+    /// # Arguments
+    ///
+    /// * 'reader' - The reader to read the chunk from
+    /// * 'chunk_size' - The length of the chunk, as written in the wav
+    pub(crate) fn from_reader(
+        reader: &mut impl Read,
+        chunk_size: usize,
+    ) -> Result<Option<CartChunk>> {
+        if chunk_size < CART_FIXED_SIZE {
+            reader.skip(chunk_size)?;
+            return Ok(None);
+        }
+
+        let version = read_fixed_string(reader, 4)?;
+        let title = read_fixed_string(reader, 64)?;
+        let artist = read_fixed_string(reader, 64)?;
+        let cut_id = read_fixed_string(reader, 64)?;
+        let client_id = read_fixed_string(reader, 64)?;
+        let category = read_fixed_string(reader, 64)?;
+        let classification = read_fixed_string(reader, 64)?;
+        let out_cue = read_fixed_string(reader, 64)?;
+        let start_date = read_fixed_string(reader, 10)?;
+        let start_time = read_fixed_string(reader, 8)?;
+        let end_date = read_fixed_string(reader, 10)?;
+        let end_time = read_fixed_string(reader, 8)?;
+        let producer_app_id = read_fixed_string(reader, 64)?;
+        let producer_app_version = read_fixed_string(reader, 64)?;
+        let user_def = read_fixed_string(reader, 64)?;
+        let level_reference = reader.read_u32()? as i32;
+
+        let mut post_timers = Vec::new();
+        for _ in 0..CART_TIMER_COUNT {
+            let usage = read_fixed_string(reader, 4)?;
+            let value = reader.read_u32()?;
+
+            if !usage.is_empty() {
+                post_timers.push(CartTimer { usage, value });
+            }
+        }
+
+        // Reserved
+        reader.skip(276)?;
+
+        let url = read_fixed_string(reader, 1024)?;
+        let tag_text = read_fixed_string(reader, chunk_size - CART_FIXED_SIZE)?;
+
+        Ok(Some(CartChunk {
+            version,
+            title,
+            artist,
+            cut_id,
+            client_id,
+            category,
+            classification,
+            out_cue,
+            start_date,
+            start_time,
+            end_date,
+            end_time,
+            producer_app_id,
+            producer_app_version,
+            user_def,
+            level_reference,
+            post_timers,
+            url,
+            tag_text,
+        }))
+    }
+
+    /// Writes the cart chunk's contents, excluding the chunk's name and length
+    ///
+    /// # Arguments
+    ///
+    /// * 'writer' - The writer to write the chunk into
+    pub(crate) fn to_writer(&self, writer: &mut impl Write) -> Result<()> {
+        if self.post_timers.len() > CART_TIMER_COUNT {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "A cart chunk can only have {} timers, actual value: {}",
+                    CART_TIMER_COUNT,
+                    self.post_timers.len()
+                ),
+            ));
+        }
+
+        write_fixed_string(writer, &self.version, 4)?;
+        write_fixed_string(writer, &self.title, 64)?;
+        write_fixed_string(writer, &self.artist, 64)?;
+        write_fixed_string(writer, &self.cut_id, 64)?;
+        write_fixed_string(writer, &self.client_id, 64)?;
+        write_fixed_string(writer, &self.category, 64)?;
+        write_fixed_string(writer, &self.classification, 64)?;
+        write_fixed_string(writer, &self.out_cue, 64)?;
+        write_fixed_string(writer, &self.start_date, 10)?;
+        write_fixed_string(writer, &self.start_time, 8)?;
+        write_fixed_string(writer, &self.end_date, 10)?;
+        write_fixed_string(writer, &self.end_time, 8)?;
+        write_fixed_string(writer, &self.producer_app_id, 64)?;
+        write_fixed_string(writer, &self.producer_app_version, 64)?;
+        write_fixed_string(writer, &self.user_def, 64)?;
+        writer.write_i32(self.level_reference)?;
+
+        for post_timer in self.post_timers.iter() {
+            write_fixed_string(writer, &post_timer.usage, 4)?;
+            writer.write_u32(post_timer.value)?;
+        }
+
+        // Unused timers, and reserved
+        writer.write_all(&vec![
+            0u8;
+            (CART_TIMER_COUNT - self.post_timers.len()) * 8 + 276
+        ])?;
+
+        write_fixed_string(writer, &self.url, 1024)?;
+        writer.write_all(self.tag_text.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::open_wav::OpenWav;
+    use crate::samples_by_channel::SamplesByChannel;
+    use crate::wave_header::{Channels, SampleFormat, WavHeader};
+    use crate::{read_wav_from_file_path, write_wav_to_file_path};
+
+    #[test]
+    fn cart_chunk_round_trip() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("cart.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Int16,
+            channels: Channels::new().front_left().front_right(),
+            sample_rate: 44100,
+        };
+
+        let cart_chunk = CartChunk {
+            title: "Station ID".to_string(),
+            artist: "Morning Crew".to_string(),
+            post_timers: vec![CartTimer {
+                usage: "SEGs".to_string(),
+                value: 40000,
+            }],
+            tag_text: "Recorded live".to_string(),
+            ..Default::default()
+        };
+
+        let mut open_wav = write_wav_to_file_path(&path, header).unwrap();
+        open_wav.set_cart_chunk(&cart_chunk).unwrap();
+        open_wav
+            .write_all_i16(
+                (0..44100).map(|_| Ok(SamplesByChannel::new().front_left(0).front_right(0))),
+            )
+            .unwrap();
+
+        let open_wav = read_wav_from_file_path(&path).unwrap();
+        assert_eq!(Some(&cart_chunk), open_wav.cart_chunk());
+    }
+
+    #[test]
+    fn short_cart_chunk_is_ignored() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("cart.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Int16,
+            channels: Channels::new().front_left(),
+            sample_rate: 44100,
+        };
+
+        let mut open_wav = write_wav_to_file_path(&path, header).unwrap();
+        open_wav
+            .set_chunk(b"cart", b"0101Station ID".to_vec())
+            .unwrap();
+        open_wav
+            .write_all_i16((0..100).map(|_| Ok(SamplesByChannel::new().front_left(0))))
+            .unwrap();
+
+        let open_wav = read_wav_from_file_path(&path).unwrap();
+        assert_eq!(None, open_wav.cart_chunk());
+        assert_eq!(100, open_wav.len_samples());
+    }
+}
@@ -0,0 +1,113 @@
+use std::io::{Read, Result};
+
+use crate::ReadEx;
+
+/// The DISP chunk's type for text. (From the Windows clipboard formats)
+const CF_TEXT: u32 = 1;
+
+/// Reads the title from a DISP chunk, or None if the chunk isn't text or is too short to have a type. Invalid UTF-8
+/// is replaced, because titles are often written in a legacy code page. The reader must be positioned after the
+/// chunk's name and length
+///
+/// # Arguments
+///
+/// * 'reader' - The reader to read the chunk from
+/// * 'chunk_size' - The length of the chunk, as written in the wav
+pub(crate) fn read_display_title(
+    reader: &mut impl Read,
+    chunk_size: usize,
+) -> Result<Option<String>> {
+    if chunk_size < 4 {
+        reader.skip(chunk_size)?;
+        return Ok(None);
+    }
+
+    let disp_type = reader.read_u32()?;
+    if disp_type != CF_TEXT {
+        reader.skip(chunk_size - 4)?;
+        return Ok(None);
+    }
+
+    let mut title = vec![0u8; chunk_size - 4];
+    reader.read_exact(&mut title)?;
+
+    // The title is null-terminated
+    if let Some(end) = title.iter().position(|c| *c == 0) {
+        title.truncate(end);
+    }
+
+    Ok(Some(String::from_utf8_lossy(&title).into_owned()))
+}
+
+/// The contents of a DISP chunk that contains a title, excluding the chunk's name and length
+///
+/// # Arguments
+///
+/// * 'title' - The title to display
+pub(crate) fn display_title_chunk(title: &str) -> Vec<u8> {
+    let mut chunk = Vec::with_capacity(title.len() + 5);
+    chunk.extend(CF_TEXT.to_le_bytes());
+    chunk.extend(title.as_bytes());
+    chunk.push(0);
+
+    chunk
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::read_display_title;
+    use crate::open_wav::OpenWav;
+    use crate::samples_by_channel::SamplesByChannel;
+    use crate::wave_header::{Channels, SampleFormat, WavHeader};
+    use crate::wave_reader::RandomAccessOpenWavReader;
+    use crate::{read_wav_from_file_path, write_wav_to_file_path};
+
+    #[test]
+    fn display_title_round_trip() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("titled.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Int16,
+            channels: Channels::new().front_left(),
+            sample_rate: 44100,
+        };
+
+        // The odd length of the chunk requires a pad byte
+        let title = "Door slams";
+
+        let mut open_wav = write_wav_to_file_path(&path, header).unwrap();
+        open_wav.set_display_title(title).unwrap();
+        open_wav
+            .write_all_i16((0..100i16).map(|sample| Ok(SamplesByChannel::new().front_left(sample))))
+            .unwrap();
+
+        let open_wav = read_wav_from_file_path(&path).unwrap();
+        assert_eq!(Some(title), open_wav.display_title());
+        assert_eq!(100, open_wav.len_samples());
+
+        let mut reader = open_wav.get_random_access_i16_reader().unwrap();
+        assert_eq!(
+            SamplesByChannel::new().front_left(99),
+            reader.read_sample(99).unwrap()
+        );
+    }
+
+    #[test]
+    fn malformed_display_title() {
+        let mut chunk = vec![1u8, 0];
+        assert_eq!(
+            None,
+            read_display_title(&mut &chunk[..], chunk.len()).unwrap()
+        );
+
+        // Windows-1252 "Café"
+        chunk = vec![1, 0, 0, 0, b'C', b'a', b'f', 0xE9, 0];
+        assert_eq!(
+            Some("Caf\u{FFFD}".to_string()),
+            read_display_title(&mut &chunk[..], chunk.len()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use std::io::{ErrorKind, Read, Result, Seek, SeekFrom};
+
+use crate::ReadEx;
+
+/// The location of a chunk in a wav
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ChunkInfo {
+    /// The chunk's name. (IE, "fmt ", "data", "LIST")
+    pub id: [u8; 4],
+    /// The position of the chunk's name, from the start of the wav
+    pub offset: usize,
+    /// The length of the chunk's contents, excluding the chunk's name, length, and pad byte
+    pub size: usize,
+}
+
+/// Lists all chunks in a wav, without reading the samples. Intended for diagnosing malformed wavs
+///
+/// # Arguments
+///
+/// * 'reader' - The wav. It is read from the start
+pub fn list_chunks<R: Read + Seek>(reader: &mut R) -> Result<Vec<ChunkInfo>> {
+    let len = reader.seek(SeekFrom::End(0))? as usize;
+    reader.seek(SeekFrom::Start(0))?;
+
+    reader.assert_str(
+        "RIFF",
+        ErrorKind::InvalidInput,
+        "Not a WAVE file (Missing RIFF Header)",
+    )?;
+    let _file_length = reader.read_u32()?;
+    reader.assert_str(
+        "WAVE",
+        ErrorKind::Unsupported,
+        "Not a WAVE file (Missing WAVE header)",
+    )?;
+
+    let mut chunks = Vec::new();
+    let mut offset = 12usize;
+    while offset + 8 <= len {
+        let mut id = [0u8; 4];
+        reader.read_exact(&mut id)?;
+        let size = reader.read_u32()? as usize;
+
+        chunks.push(ChunkInfo { id, offset, size });
+
+        // Chunks are word-aligned
+        offset += 8 + size + (size % 2);
+        reader.seek(SeekFrom::Start(offset as u64))?;
+    }
+
+    Ok(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::samples_by_channel::SamplesByChannel;
+    use crate::wave_header::{Channels, SampleFormat, WavHeader};
+    use crate::write_wav_to_file_path;
+
+    #[test]
+    fn list_chunks_sanity() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("chunks.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Int16,
+            channels: Channels::new().front_left(),
+            sample_rate: 44100,
+        };
+
+        let open_wav = write_wav_to_file_path(&path, header).unwrap();
+        open_wav
+            .write_all_i16((0..10i16).map(|sample| Ok(SamplesByChannel::new().front_left(sample))))
+            .unwrap();
+
+        // A trailing LIST chunk, with an odd length and a pad byte. (And another chunk after it)
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"LIST\x05\x00\x00\x00INFOa\x00").unwrap();
+        file.write_all(b"junk\x02\x00\x00\x00ab").unwrap();
+        drop(file);
+
+        let mut file = std::fs::File::open(&path).unwrap();
+        let chunks = list_chunks(&mut file).unwrap();
+
+        assert_eq!(
+            vec![
+                ChunkInfo {
+                    id: *b"fmt ",
+                    offset: 12,
+                    size: 40
+                },
+                ChunkInfo {
+                    id: *b"data",
+                    offset: 60,
+                    size: 20
+                },
+                ChunkInfo {
+                    id: *b"LIST",
+                    offset: 88,
+                    size: 5
+                },
+                ChunkInfo {
+                    id: *b"junk",
+                    offset: 102,
+                    size: 2
+                },
+            ],
+            chunks
+        );
+    }
+}
@@ -0,0 +1,46 @@
+//! Optional chunks that can be read from, and written into, a wav in addition to the fmt and data chunks
+
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+mod acid;
+mod bext;
+mod cart;
+mod disp;
+mod list;
+mod peak;
+
+pub use acid::AcidChunk;
+pub(crate) use bext::{validate_origination_date, validate_origination_time};
+pub use bext::{BroadcastExtension, UMID_SIZE};
+pub use cart::{CartChunk, CartTimer, CART_TIMER_COUNT};
+pub(crate) use disp::{display_title_chunk, read_display_title};
+pub use list::{list_chunks, ChunkInfo};
+pub use peak::{ChannelPeak, PeakChunk};
+
+// Reads a null-padded string from a fixed-length field
+pub(crate) fn read_fixed_string(reader: &mut impl Read, len: usize) -> Result<String> {
+    let mut buf = vec![0u8; len];
+    reader.read_exact(&mut buf)?;
+
+    if let Some(end) = buf.iter().position(|c| *c == 0) {
+        buf.truncate(end);
+    }
+
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+// Writes a string into a fixed-length field, padded with nulls
+pub(crate) fn write_fixed_string(writer: &mut impl Write, value: &str, len: usize) -> Result<()> {
+    let bytes = value.as_bytes();
+    if bytes.len() > len {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("{value} is longer than {len} bytes"),
+        ));
+    }
+
+    writer.write_all(bytes)?;
+    writer.write_all(&vec![0u8; len - bytes.len()])?;
+
+    Ok(())
+}
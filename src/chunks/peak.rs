@@ -0,0 +1,126 @@
+use std::io::{Read, Result, Write};
+
+use crate::{ReadEx, WriteEx};
+
+/// The size of the fixed part of a PEAK chunk, excluding the chunk's name and length
+const PEAK_FIXED_SIZE: usize = 8;
+
+/// The size of each channel's peak in a PEAK chunk
+const CHANNEL_PEAK_SIZE: usize = 8;
+
+/// The loudest sample in a channel
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ChannelPeak {
+    /// The absolute value of the loudest sample. 1.0 is full scale
+    pub value: f32,
+    /// The index of the sample that contains the loudest sample
+    pub position: u32,
+}
+
+/// The loudest sample in each channel, in a PEAK chunk. Floating point wavs often include this chunk so that
+/// programs can draw or normalize the wav without scanning every sample
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeakChunk {
+    /// The version of the chunk's layout. Always 1
+    pub version: u32,
+    /// When the peaks were calculated, in seconds since January 1st, 1970
+    pub timestamp: u32,
+    /// Each channel's peak, in the order that the wav's channels are written
+    pub peaks: Vec<ChannelPeak>,
+}
+
+impl PeakChunk {
+    /// The size of a PEAK chunk, excluding the chunk's name and length
+    ///
+    /// # Arguments
+    ///
+    /// * 'num_channels' - The number of channels in the wav
+    pub(crate) fn chunk_size(num_channels: usize) -> usize {
+        PEAK_FIXED_SIZE + num_channels * CHANNEL_PEAK_SIZE
+    }
+
+    /// Reads a PEAK chunk, or None if the chunk is too short to be a PEAK chunk. (A malformed PEAK chunk doesn't
+    /// prevent reading the samples.) The reader must be positioned after the chunk's name and length
+    ///
+    /// # Arguments
+    ///
+    /// * 'reader' - The reader to read the chunk from
+    /// * 'chunk_size' - The length of the chunk, as written in the wav
+    pub(crate) fn from_reader(
+        reader: &mut impl Read,
+        chunk_size: usize,
+    ) -> Result<Option<PeakChunk>> {
+        if chunk_size < PEAK_FIXED_SIZE {
+            reader.skip(chunk_size)?;
+            return Ok(None);
+        }
+
+        let version = reader.read_u32()?;
+        let timestamp = reader.read_u32()?;
+
+        let num_channels = (chunk_size - PEAK_FIXED_SIZE) / CHANNEL_PEAK_SIZE;
+        let mut peaks = Vec::with_capacity(num_channels);
+        for _ in 0..num_channels {
+            let value = reader.read_f32()?;
+            let position = reader.read_u32()?;
+
+            peaks.push(ChannelPeak { value, position });
+        }
+
+        // Ignore a partial channel peak
+        reader.skip((chunk_size - PEAK_FIXED_SIZE) % CHANNEL_PEAK_SIZE)?;
+
+        Ok(Some(PeakChunk {
+            version,
+            timestamp,
+            peaks,
+        }))
+    }
+
+    /// Writes the PEAK chunk's contents, excluding the chunk's name and length
+    ///
+    /// # Arguments
+    ///
+    /// * 'writer' - The writer to write the chunk into
+    pub(crate) fn to_writer(&self, writer: &mut impl Write) -> Result<()> {
+        writer.write_u32(self.version)?;
+        writer.write_u32(self.timestamp)?;
+
+        for peak in self.peaks.iter() {
+            writer.write_f32(peak.value)?;
+            writer.write_u32(peak.position)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::read_wav_from_file_path;
+
+    use super::*;
+
+    #[test]
+    fn read_peak_chunk() {
+        let open_wav = read_wav_from_file_path(Path::new("test_data/short_float.wav")).unwrap();
+        let peak_chunk = open_wav.peak_chunk().expect("PEAK chunk missing");
+
+        assert_eq!(1, peak_chunk.version);
+        assert_eq!(
+            vec![ChannelPeak {
+                value: 0.22160532,
+                position: 1026
+            }],
+            peak_chunk.peaks
+        );
+    }
+
+    #[test]
+    fn read_short_peak_chunk() {
+        let peak_chunk = PeakChunk::from_reader(&mut &[1u8, 0, 0, 0][..], 4).unwrap();
+        assert_eq!(None, peak_chunk);
+    }
+}
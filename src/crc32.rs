@@ -0,0 +1,61 @@
+// CRC-32, (IEEE 802.3, as used by zip and png,) for checking the integrity of a wav's data
+
+const CRC32_POLYNOMIAL: u32 = 0xEDB88320;
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        let mut crc = byte as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ CRC32_POLYNOMIAL
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+
+        table[byte] = crc;
+        byte += 1;
+    }
+
+    table
+}
+
+// Computes a CRC-32 a buffer at a time
+pub(crate) struct Crc32 {
+    crc: u32,
+}
+
+impl Crc32 {
+    pub(crate) fn new() -> Crc32 {
+        Crc32 { crc: 0xFFFFFFFF }
+    }
+
+    pub(crate) fn update(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.crc = CRC32_TABLE[((self.crc ^ *byte as u32) & 0xFF) as usize] ^ (self.crc >> 8);
+        }
+    }
+
+    pub(crate) fn finish(&self) -> u32 {
+        self.crc ^ 0xFFFFFFFF
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_value() {
+        let mut crc32 = Crc32::new();
+        crc32.update(b"1234");
+        crc32.update(b"56789");
+        assert_eq!(0xCBF43926, crc32.finish());
+    }
+}
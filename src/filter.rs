@@ -0,0 +1,214 @@
+//! Simple filters that are applied while copying one wav into another
+
+use std::f32::consts::TAU;
+use std::io::Result;
+
+use crate::open_wav::header_of;
+use crate::samples_by_channel::SamplesByChannel;
+use crate::wave_header::Channels;
+use crate::wave_reader::StreamWavReader;
+use crate::wave_writer::OpenWavWriter;
+
+/// Normalized coefficients of a biquad filter. (a0 is always 1)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BiquadCoeffs {
+    pub b0: f32,
+    pub b1: f32,
+    pub b2: f32,
+    pub a1: f32,
+    pub a2: f32,
+}
+
+impl BiquadCoeffs {
+    /// A low-pass filter, from the Audio EQ Cookbook
+    ///
+    /// # Arguments
+    ///
+    /// * 'cutoff' - The cutoff frequency, in Hz
+    /// * 'q' - The filter's Q. FRAC_1_SQRT_2 (0.707) is a Butterworth response
+    /// * 'sample_rate' - The sample rate of the wav that is filtered
+    pub fn low_pass(cutoff: f32, q: f32, sample_rate: u32) -> BiquadCoeffs {
+        let (cos_w0, alpha) = BiquadCoeffs::cos_w0_and_alpha(cutoff, q, sample_rate);
+
+        BiquadCoeffs::normalize(
+            (1.0 - cos_w0) / 2.0,
+            1.0 - cos_w0,
+            (1.0 - cos_w0) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos_w0,
+            1.0 - alpha,
+        )
+    }
+
+    /// A high-pass filter, from the Audio EQ Cookbook
+    ///
+    /// # Arguments
+    ///
+    /// * 'cutoff' - The cutoff frequency, in Hz
+    /// * 'q' - The filter's Q. FRAC_1_SQRT_2 (0.707) is a Butterworth response
+    /// * 'sample_rate' - The sample rate of the wav that is filtered
+    pub fn high_pass(cutoff: f32, q: f32, sample_rate: u32) -> BiquadCoeffs {
+        let (cos_w0, alpha) = BiquadCoeffs::cos_w0_and_alpha(cutoff, q, sample_rate);
+
+        BiquadCoeffs::normalize(
+            (1.0 + cos_w0) / 2.0,
+            -(1.0 + cos_w0),
+            (1.0 + cos_w0) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos_w0,
+            1.0 - alpha,
+        )
+    }
+
+    fn cos_w0_and_alpha(cutoff: f32, q: f32, sample_rate: u32) -> (f32, f32) {
+        let w0 = TAU * cutoff / (sample_rate as f32);
+        (w0.cos(), w0.sin() / (2.0 * q))
+    }
+
+    fn normalize(b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) -> BiquadCoeffs {
+        BiquadCoeffs {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        }
+    }
+}
+
+/// The state of a direct-form-II biquad for a single channel
+#[derive(Debug, Copy, Clone, Default)]
+pub(crate) struct BiquadState {
+    w1: f32,
+    w2: f32,
+}
+
+impl BiquadState {
+    pub(crate) fn process(&mut self, coeffs: &BiquadCoeffs, sample: f32) -> f32 {
+        let w = sample - coeffs.a1 * self.w1 - coeffs.a2 * self.w2;
+        let filtered = coeffs.b0 * w + coeffs.b1 * self.w1 + coeffs.b2 * self.w2;
+
+        self.w2 = self.w1;
+        self.w1 = w;
+
+        filtered
+    }
+}
+
+/// Copies a wav while applying a biquad filter to each channel
+///
+/// # Arguments
+///
+/// * 'reader' - The wav to filter
+/// * 'out' - The wav to write. It must have the same channels and sample rate as 'reader'
+/// * 'coeffs' - The filter to apply. (IE, BiquadCoeffs::low_pass)
+pub fn filter_biquad(
+    reader: StreamWavReader<f32>,
+    out: OpenWavWriter,
+    coeffs: BiquadCoeffs,
+) -> Result<()> {
+    header_of(reader.info().as_ref()).compatible_with(out.header())?;
+
+    let mut states = [BiquadState::default(); 18];
+
+    let samples_itr = reader.into_iter().map(move |samples_result| {
+        let samples = samples_result?;
+        let mut filtered_samples = SamplesByChannel::new();
+
+        for (position, state) in Channels::all_positions().into_iter().zip(states.iter_mut()) {
+            if let Some(sample) = samples.get(position) {
+                filtered_samples.set(position, state.process(&coeffs, *sample));
+            }
+        }
+
+        Ok(filtered_samples)
+    });
+
+    out.write_all_f32(samples_itr)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_1_SQRT_2;
+    use std::path::Path;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::wave_header::{SampleFormat, WavHeader};
+    use crate::wave_reader::StreamOpenWavReader;
+    use crate::{read_wav_from_file_path, write_wav_to_file_path};
+
+    const SAMPLE_RATE: u32 = 48000;
+
+    fn header() -> WavHeader {
+        WavHeader {
+            sample_format: SampleFormat::Float,
+            channels: Channels::new().front_left(),
+            sample_rate: SAMPLE_RATE,
+        }
+    }
+
+    fn filter_tone(path: &Path, frequency: f32, coeffs: BiquadCoeffs) -> f32 {
+        let tone_path = path.join("tone.wav");
+        let filtered_path = path.join("filtered.wav");
+
+        let open_wav = write_wav_to_file_path(&tone_path, header()).unwrap();
+        open_wav
+            .write_all_f32((0..SAMPLE_RATE).map(|sample| {
+                let value = (TAU * frequency * sample as f32 / SAMPLE_RATE as f32).sin();
+                Ok(SamplesByChannel::new().front_left(value))
+            }))
+            .unwrap();
+
+        let reader = read_wav_from_file_path(&tone_path)
+            .unwrap()
+            .get_stream_f32_reader()
+            .unwrap();
+        let out = write_wav_to_file_path(&filtered_path, header()).unwrap();
+        filter_biquad(reader, out, coeffs).unwrap();
+
+        // Skip the first 1000 samples while the filter settles
+        let mut peak = 0f32;
+        let reader = read_wav_from_file_path(&filtered_path)
+            .unwrap()
+            .get_stream_f32_reader()
+            .unwrap();
+        for samples_result in reader.into_iter().skip(1000) {
+            let sample = samples_result.unwrap().front_left.unwrap();
+            peak = peak.max(sample.abs());
+        }
+
+        peak
+    }
+
+    #[test]
+    fn low_pass_attenuates_high_frequency() {
+        let temp_dir = tempdir().unwrap();
+        let coeffs = BiquadCoeffs::low_pass(1000.0, FRAC_1_SQRT_2, SAMPLE_RATE);
+
+        let low_peak = filter_tone(temp_dir.path(), 100.0, coeffs);
+        assert!(low_peak > 0.95, "100Hz should pass, peak: {low_peak}");
+
+        let high_peak = filter_tone(temp_dir.path(), 10000.0, coeffs);
+        assert!(
+            high_peak < 0.02,
+            "10kHz should be attenuated, peak: {high_peak}"
+        );
+    }
+
+    #[test]
+    fn high_pass_attenuates_low_frequency() {
+        let temp_dir = tempdir().unwrap();
+        let coeffs = BiquadCoeffs::high_pass(1000.0, FRAC_1_SQRT_2, SAMPLE_RATE);
+
+        let low_peak = filter_tone(temp_dir.path(), 100.0, coeffs);
+        assert!(
+            low_peak < 0.02,
+            "100Hz should be attenuated, peak: {low_peak}"
+        );
+
+        let high_peak = filter_tone(temp_dir.path(), 10000.0, coeffs);
+        assert!(high_peak > 0.95, "10kHz should pass, peak: {high_peak}");
+    }
+}
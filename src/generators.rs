@@ -0,0 +1,120 @@
+//! Test tones, for use in tests and examples. Each generator yields mono samples forever; use take() to limit the
+//! length, and SamplesByChannel::set() to copy the samples into more channels
+
+use std::f32::consts::TAU;
+use std::io::Result;
+
+use crate::samples_by_channel::SamplesByChannel;
+
+/// Generates a sine wave
+///
+/// # Arguments
+///
+/// * 'freq' - The frequency, in hz
+/// * 'amplitude' - The peak amplitude. 1.0 is full scale
+/// * 'sample_rate' - The samples per second
+pub fn sine(
+    freq: f32,
+    amplitude: f32,
+    sample_rate: u32,
+) -> impl Iterator<Item = Result<SamplesByChannel<f32>>> {
+    phases(freq, sample_rate)
+        .map(move |phase| Ok(SamplesByChannel::new().front_left((phase * TAU).sin() * amplitude)))
+}
+
+/// Generates a square wave, which alternates between +amplitude and -amplitude
+///
+/// # Arguments
+///
+/// * 'freq' - The frequency, in hz
+/// * 'amplitude' - The peak amplitude. 1.0 is full scale
+/// * 'sample_rate' - The samples per second
+pub fn square(
+    freq: f32,
+    amplitude: f32,
+    sample_rate: u32,
+) -> impl Iterator<Item = Result<SamplesByChannel<f32>>> {
+    phases(freq, sample_rate).map(move |phase| {
+        let sample = if phase < 0.5 { amplitude } else { -amplitude };
+        Ok(SamplesByChannel::new().front_left(sample))
+    })
+}
+
+/// Generates full-scale white noise, between -1.0 and 1.0. The same seed always generates the same noise
+///
+/// # Arguments
+///
+/// * 'seed' - Seeds the random number generator
+pub fn white_noise(seed: u64) -> impl Iterator<Item = Result<SamplesByChannel<f32>>> {
+    // xorshift64 can not start from 0
+    let mut state = if seed == 0 { 0x9E3779B97F4A7C15 } else { seed };
+
+    std::iter::repeat_with(move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+
+        // The top 24 bits fill the float's mantissa
+        let sample = (state >> 40) as f32 / (1u64 << 23) as f32 - 1.0;
+        Ok(SamplesByChannel::new().front_left(sample))
+    })
+}
+
+// The position in the wave's cycle, from 0.0 up to (but not including) 1.0, of each sample
+fn phases(freq: f32, sample_rate: u32) -> impl Iterator<Item = f32> {
+    let samples_per_cycle = sample_rate as f64 / freq as f64;
+    (0u64..).map(move |sample| (sample as f64 / samples_per_cycle).fract() as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn left(samples_result: Result<SamplesByChannel<f32>>) -> f32 {
+        samples_result.unwrap().front_left.unwrap()
+    }
+
+    #[test]
+    fn sine_period() {
+        // 480hz at 48khz has a period of 100 samples
+        let samples: Vec<f32> = sine(480.0, 0.5, 48000).take(300).map(left).collect();
+
+        for sample in 0..200 {
+            assert!(
+                (samples[sample] - samples[sample + 100]).abs() < 0.0001,
+                "Sample {sample} does not repeat after one period"
+            );
+        }
+
+        assert!(samples[0].abs() < 0.0001);
+        assert!((samples[25] - 0.5).abs() < 0.0001);
+        assert!((samples[75] + 0.5).abs() < 0.0001);
+    }
+
+    #[test]
+    fn square_amplitude() {
+        let samples: Vec<f32> = square(480.0, 0.25, 48000).take(200).map(left).collect();
+
+        assert!(samples
+            .iter()
+            .all(|sample| *sample == 0.25 || *sample == -0.25));
+        assert_eq!(&[0.25; 50], &samples[0..50]);
+        assert_eq!(&[-0.25; 50], &samples[50..100]);
+        assert_eq!(samples[0..100], samples[100..200]);
+    }
+
+    #[test]
+    fn white_noise_range() {
+        let samples: Vec<f32> = white_noise(1234).take(10000).map(left).collect();
+
+        assert!(samples.iter().all(|sample| (-1.0..1.0).contains(sample)));
+        assert!(samples.iter().any(|sample| *sample > 0.9));
+        assert!(samples.iter().any(|sample| *sample < -0.9));
+
+        let repeated: Vec<f32> = white_noise(1234).take(10000).map(left).collect();
+        assert_eq!(
+            samples, repeated,
+            "The same seed must generate the same noise"
+        );
+    }
+}
@@ -149,23 +149,41 @@
 //!
 //! ```
 
-use std::fs::File;
-use std::io::{BufReader, BufWriter, ErrorKind, Read, Result, Seek, Write};
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, Error, ErrorKind, Read, Result, Seek, SeekFrom, Write};
 use std::path::Path;
+use std::time::Duration;
 
+pub mod analysis;
+pub mod chunks;
+pub mod filter;
+pub mod generators;
+pub mod loudness;
+pub mod offsets;
 pub mod open_wav;
+pub mod processing;
 pub mod reader;
+pub mod wav;
+pub mod wav_sample;
 pub mod wave_header;
 pub mod wave_reader;
 pub mod wave_writer;
 pub mod writer;
 
+mod adpcm;
 mod assertions;
+mod byte_counter;
 mod constants;
+mod crc32;
 pub mod samples_by_channel;
 mod upconvert;
 
-use reader::ReadEx;
+pub use upconvert::RoundingMode;
+
+use open_wav::{OpenWav, WavInfo};
+use reader::{lenient_id_matches, ReadEx, RecordingReader};
+use samples_by_channel::SamplesByChannel;
+use wav_sample::WavSample;
 use wave_header::*;
 use wave_reader::*;
 use wave_writer::*;
@@ -181,7 +199,9 @@ pub fn read_wav_from_file_path(file_path: &Path) -> Result<OpenWavReader<BufRead
     let file = File::open(file_path)?;
     let reader = BufReader::new(file);
 
-    read_wav(reader)
+    let mut open_wav = read_wav(reader)?;
+    open_wav.set_path(file_path.to_path_buf());
+    Ok(open_wav)
 }
 
 /// Reads a wav from a Read struct
@@ -189,8 +209,31 @@ pub fn read_wav_from_file_path(file_path: &Path) -> Result<OpenWavReader<BufRead
 /// # Arguments
 ///
 /// * 'reader' - A Read struct. It is strongly recommended that this struct implement some form of buffering, such as via a BufReader
-pub fn read_wav<TReader: 'static + Read>(mut reader: TReader) -> Result<OpenWavReader<TReader>> {
-    // Verify that this is a RIFF file
+pub fn read_wav<TReader: 'static + Read>(reader: TReader) -> Result<OpenWavReader<TReader>> {
+    read_wav_from_position(reader, 0, false)
+}
+
+/// Reads a wav from a Read struct, and accepts the RIFF form type, fmt, and data ids with the wrong case or with
+/// spaces or nulls around them. (IE, "wave" instead of "WAVE") Some broken encoders write these files. Each
+/// correction is recorded in OpenWavReader::warnings()
+///
+/// # Arguments
+///
+/// * 'reader' - A Read struct. It is strongly recommended that this struct implement some form of buffering, such as via a BufReader
+pub fn read_wav_lenient<TReader: 'static + Read>(
+    reader: TReader,
+) -> Result<OpenWavReader<TReader>> {
+    read_wav_from_position(reader, 0, true)
+}
+
+/// Reads a wav as interleaved floats, like OpenWavReader::get_raw_stream_f32(), including wavs whose channels can't
+/// be represented by Channels. (IE, more than 18 channels, or a channel mask that doesn't match the number of
+/// channels.) read_wav() returns an error for these wavs
+///
+/// # Arguments
+///
+/// * 'reader' - A Read struct. It is strongly recommended that this struct implement some form of buffering, such as via a BufReader
+pub fn read_wav_raw_f32<TReader: Read>(mut reader: TReader) -> Result<RawStreamReader<TReader>> {
     reader.assert_str(
         "RIFF",
         ErrorKind::InvalidInput,
@@ -199,18 +242,293 @@ pub fn read_wav<TReader: 'static + Read>(mut reader: TReader) -> Result<OpenWavR
     let _file_length = reader.read_u32()?;
     reader.assert_str(
         "WAVE",
-        ErrorKind::Unsupported,
+        ErrorKind::InvalidInput,
+        "Not a WAVE file (Missing WAVE header)",
+    )?;
+    reader.assert_str("fmt ", ErrorKind::Unsupported, "Not a WAVE file")?;
+
+    let mut subchunk_size = 0usize;
+    let (header, num_channels) =
+        WavHeader::from_reader_after_id_any_channels(&mut reader, &mut subchunk_size)?;
+
+    // subchunk size doesn't include 4-letter prefix and 4-byte length
+    RawStreamReader::new(reader, header, num_channels, 20 + subchunk_size)
+}
+
+/// Reads a wav that is embedded inside of a larger file, (IE, a game's asset bundle,) starting at 'offset'
+///
+/// # Arguments
+///
+/// * 'reader' - A (Read + Seek) struct. It is strongly recommended that this struct implement some form of buffering, such as via a BufReader
+/// * 'offset' - The position of the wav's RIFF header within 'reader'
+pub fn read_wav_at_offset<TReader: 'static + Read + Seek>(
+    mut reader: TReader,
+    offset: u64,
+) -> Result<OpenWavReader<TReader>> {
+    reader.seek(SeekFrom::Start(offset))?;
+    read_wav_from_position(reader, offset as usize, false)
+}
+
+// Reads a wav whose RIFF header is at 'position'. The reader must be positioned at the RIFF header
+fn read_wav_from_position<TReader: 'static + Read>(
+    mut reader: TReader,
+    position: usize,
+    lenient: bool,
+) -> Result<OpenWavReader<TReader>> {
+    let mut warnings = Vec::new();
+
+    // Verify that this is a RIFF file
+    reader.assert_str(
+        "RIFF",
+        ErrorKind::InvalidInput,
+        "Not a WAVE file (Missing RIFF Header)",
+    )?;
+    let _file_length = reader.read_u32()?;
+    read_id(
+        &mut reader,
+        "WAVE",
+        lenient,
+        &mut warnings,
         "Not a WAVE file (Missing WAVE header)",
     )?;
 
-    // file position is 12
+    // file position is 12, relative to the start of the wav
 
+    read_id(
+        &mut reader,
+        "fmt ",
+        lenient,
+        &mut warnings,
+        "Not a WAVE file",
+    )?;
     let mut subchunk_size = 0usize;
-    let header = WavHeader::from_reader(&mut reader, &mut subchunk_size)?;
+    let mut fmt_reader = RecordingReader::new(&mut reader);
+    let header = WavHeader::from_reader_after_id(&mut fmt_reader, &mut subchunk_size)?;
+
+    // The recorded fmt chunk starts with its length
+    let raw_fmt_chunk = fmt_reader.into_recorded().split_off(4);
 
     // subchunk size doesn't include 4-letter prefix and 4-byte length
 
-    OpenWavReader::new(reader, header, 20 + subchunk_size)
+    let mut open_wav = OpenWavReader::new_with_warnings(
+        reader,
+        header,
+        position + 20 + subchunk_size,
+        lenient,
+        warnings,
+    )?;
+    open_wav.set_raw_fmt_chunk(raw_fmt_chunk);
+    Ok(open_wav)
+}
+
+// Reads a 4-character id, and returns an error if it isn't 'expected'. When 'lenient' is true, ids with the wrong
+// case or spacing are accepted, and recorded in 'warnings'
+fn read_id(
+    reader: &mut impl Read,
+    expected: &str,
+    lenient: bool,
+    warnings: &mut Vec<String>,
+    message: &str,
+) -> Result<()> {
+    let mut id = [0u8; 4];
+    reader.read_fixed_size(&mut id)?;
+
+    if id == expected.as_bytes() {
+        Ok(())
+    } else if lenient && lenient_id_matches(&id, expected) {
+        warnings.push(format!(
+            "Read \"{}\" as \"{}\"",
+            String::from_utf8_lossy(&id),
+            expected
+        ));
+        Ok(())
+    } else {
+        Err(Error::new(ErrorKind::Unsupported, message))
+    }
+}
+
+/// Checks that every sample in a wav file can be decoded, without keeping the samples in memory. Returns an error if
+/// the file is truncated, has invalid chunk sizes, or uses an unsupported format
+///
+/// # Arguments
+///
+/// * 'file_path' - The path to the wav file to check
+pub fn verify_decodable(file_path: &Path) -> Result<()> {
+    let open_wav = read_wav_from_file_path(file_path)?;
+
+    // The data chunk ends in the middle of a sample
+    let trailing_bytes = open_wav.trailing_bytes();
+    if trailing_bytes != 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("The last sample is incomplete: {trailing_bytes} bytes are left over"),
+        ));
+    }
+
+    // Reading stops at the first sample that can't be decoded, (IE, because the file is truncated,) so every sample
+    // was read if there's no error
+    for samples_result in open_wav.get_stream_f32_reader()? {
+        samples_result?;
+    }
+
+    Ok(())
+}
+
+/// Reads a wav file's header and length, without reading the samples
+///
+/// # Arguments
+///
+/// * 'file_path' - The path to the wav file to read
+pub fn probe(file_path: &Path) -> Result<WavInfo> {
+    let open_wav = read_wav_from_file_path(file_path)?;
+    let len_samples = open_wav.len_samples();
+
+    // A corrupt header can have a sample rate of 0
+    let nanos = (len_samples as u128 * 1_000_000_000)
+        .checked_div(open_wav.sample_rate() as u128)
+        .unwrap_or(0);
+    let duration = Duration::from_nanos(nanos as u64);
+
+    Ok(WavInfo {
+        header: *open_wav.header(),
+        len_samples,
+        duration,
+        extra_chunk_ids: open_wav.chunk_ids(),
+    })
+}
+
+/// Returns true if two wav files have the same audio: The same header, length, and sample data. Metadata, (IE, a
+/// cart chunk or a title,) is ignored. The headers are compared first, so that files that obviously differ are
+/// detected without reading their samples. Matching samples are detected with a CRC-32, so a collision is possible,
+/// but unlikely
+///
+/// # Arguments
+///
+/// * 'a' - The path to the first wav file
+/// * 'b' - The path to the second wav file
+pub fn same_audio(a: &Path, b: &Path) -> Result<bool> {
+    let mut open_wav_a = read_wav_from_file_path(a)?;
+    let mut open_wav_b = read_wav_from_file_path(b)?;
+
+    if open_wav_a.header() != open_wav_b.header()
+        || open_wav_a.len_samples() != open_wav_b.len_samples()
+    {
+        return Ok(false);
+    }
+
+    Ok(open_wav_a.data_crc32()? == open_wav_b.data_crc32()?)
+}
+
+/// Loads a wav as overlapping windows of mono samples, for spectral analysis. (IE, FFTs) Each window has 'window'
+/// samples, and starts 'hop' samples after the previous window. Wavs with more than one channel are downmixed by
+/// averaging the channels. The last window is padded with silence. Returns the sample rate and the windows
+///
+/// # Arguments
+///
+/// * 'file_path' - The path to the wav file to read
+/// * 'window' - The number of samples in each window. Must be at least 1
+/// * 'hop' - The number of samples between the start of each window. Must be at least 1
+pub fn load_fft_frames(
+    file_path: &Path,
+    window: usize,
+    hop: usize,
+) -> Result<(u32, Vec<Vec<f32>>)> {
+    if window == 0 || hop == 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "The window and hop must be at least 1 sample",
+        ));
+    }
+
+    let open_wav = read_wav_from_file_path(file_path)?;
+    let sample_rate = open_wav.sample_rate();
+    let num_channels = open_wav.num_channels() as f32;
+
+    let mut mono = Vec::with_capacity(open_wav.len_samples());
+    for samples_result in open_wav.get_stream_f32_reader()? {
+        let samples = samples_result?.to_vec();
+        mono.push(samples.iter().sum::<f32>() / num_channels);
+    }
+
+    let mut windows = Vec::new();
+    let mut start = 0;
+    while start < mono.len() {
+        let end = (start + window).min(mono.len());
+        let mut samples_window = mono[start..end].to_vec();
+        samples_window.resize(window, 0.0);
+        windows.push(samples_window);
+
+        // The window that reaches the end of the wav is the last
+        if start + window >= mono.len() {
+            break;
+        }
+
+        start += hop;
+    }
+
+    Ok((sample_rate, windows))
+}
+
+/// Replaces the header of an existing wav file, by overwriting its fmt chunk in place. The rest of the file, including
+/// the samples, is not moved or modified. The new fmt chunk must be the same size as the existing one, because a
+/// different size would require moving the rest of the file. Note that the samples are not converted: A header with a
+/// different sample format or number of channels will make the samples unreadable
+///
+/// # Arguments
+///
+/// * 'file_path' - The path to the wav file to modify
+/// * 'new_header' - The header to write
+pub fn rewrite_header_in_place(file_path: &Path, new_header: WavHeader) -> Result<()> {
+    let mut file = OpenOptions::new().read(true).write(true).open(file_path)?;
+
+    file.assert_str(
+        "RIFF",
+        ErrorKind::InvalidInput,
+        "Not a WAVE file (Missing RIFF Header)",
+    )?;
+    let _file_length = file.read_u32()?;
+    file.assert_str(
+        "WAVE",
+        ErrorKind::Unsupported,
+        "Not a WAVE file (Missing WAVE header)",
+    )?;
+    file.assert_str("fmt ", ErrorKind::Unsupported, "Not a WAVE file")?;
+    let subchunk_size = file.read_u32()? as usize;
+
+    // Prefer the extensible layout, but use the classic layout if that's what fits
+    let mut fmt_chunk = Vec::new();
+    WavHeader::to_writer_with_style(&mut fmt_chunk, &new_header, FmtChunkStyle::Extensible)?;
+    if fmt_chunk.len() != subchunk_size + 8 {
+        let mut classic_fmt_chunk = Vec::new();
+        if WavHeader::to_writer_with_style(
+            &mut classic_fmt_chunk,
+            &new_header,
+            FmtChunkStyle::Classic,
+        )
+        .is_ok()
+            && classic_fmt_chunk.len() == subchunk_size + 8
+        {
+            fmt_chunk = classic_fmt_chunk;
+        }
+    }
+
+    if fmt_chunk.len() != subchunk_size + 8 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "The new fmt chunk is {} bytes, but the existing fmt chunk is {} bytes. Changing the size of the fmt chunk would require moving the samples",
+                fmt_chunk.len() - 8,
+                subchunk_size
+            ),
+        ));
+    }
+
+    // The fmt chunk always starts after the RIFF header
+    file.seek(SeekFrom::Start(12))?;
+    file.write_all(&fmt_chunk)?;
+    file.flush()?;
+
+    Ok(())
 }
 
 /// Starts writing a wav to a Path. Returns an OpenWavWriter struct that is used to write the contents of the wav
@@ -325,10 +643,61 @@ pub fn read_wav<TReader: 'static + Read>(mut reader: TReader) -> Result<OpenWavR
 /// writer.flush().unwrap();
 /// ```
 pub fn write_wav_to_file_path(file_path: &Path, header: WavHeader) -> Result<OpenWavWriter> {
-    let file = File::create(file_path)?;
-    let writer = BufWriter::new(file);
+    WavWriterBuilder::new(header).build_to_path(file_path)
+}
+
+/// Estimates the size, in bytes, of a wav that is written with write_wav_to_file_path. Optional chunks, (IE, LIST,)
+/// are not included
+///
+/// # Arguments
+///
+/// * 'header' - The header of the wav that will be written
+/// * 'expected_frames' - The number of samples, (across all channels,) that will be written
+pub fn estimate_wav_size(header: &WavHeader, expected_frames: usize) -> u64 {
+    // RIFF header, extensible fmt chunk, and the data chunk's header
+    let header_size = 12 + 48 + 8;
+    let frame_bytes =
+        header.channels.count() as u64 * header.sample_format.bytes_per_sample() as u64;
+
+    header_size + expected_frames as u64 * frame_bytes
+}
+
+/// Starts writing a wav to a Path, like write_wav_to_file_path, after checking that there is enough free disk space
+/// for the entire wav. This avoids running out of space partway through a long render. Requires the disk_space
+/// feature
+///
+/// # Arguments
+///
+/// * 'file_path' - The path to where the wav will be written
+/// * 'header' - The header information in the wav. This specifies things like sampling rate, sample bit depth, ect
+/// * 'expected_frames' - The number of samples, (across all channels,) that will be written
+#[cfg(feature = "disk_space")]
+pub fn write_wav_to_file_path_checked(
+    file_path: &Path,
+    header: WavHeader,
+    expected_frames: usize,
+) -> Result<OpenWavWriter> {
+    let directory = match file_path.parent() {
+        Some(directory) if !directory.as_os_str().is_empty() => directory,
+        _ => Path::new("."),
+    };
+
+    let available = fs2::available_space(directory)?;
+    check_disk_space(estimate_wav_size(&header, expected_frames), available)?;
+
+    write_wav_to_file_path(file_path, header)
+}
+
+#[cfg(feature = "disk_space")]
+fn check_disk_space(required: u64, available: u64) -> Result<()> {
+    if required > available {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!("The wav requires {required} bytes, but only {available} bytes are available"),
+        ));
+    }
 
-    write_wav(writer, header)
+    Ok(())
 }
 
 /// Starts writing a wav to a (Write + Seek) struct. Returns an OpenWavWriter struct that is used to write the contents of the wav
@@ -338,26 +707,243 @@ pub fn write_wav_to_file_path(file_path: &Path, header: WavHeader) -> Result<Ope
 /// * 'writer' - The (Write + Seek) struct to write the wav into. It is strongly recommended that this struct implement some form of buffering, such as via a BufWriter
 /// * 'header' - The header information in the wav. This specifies things like sampling rate, sample bit depth, ect
 pub fn write_wav<TWriter: 'static + Write + Seek>(
-    mut writer: TWriter,
+    writer: TWriter,
+    header: WavHeader,
+) -> Result<OpenWavWriter> {
+    WavWriterBuilder::new(header).build(writer)
+}
+
+/// Starts writing a wav to a Path, with a fmt chunk that is exactly 'raw_fmt_chunk', instead of a fmt chunk that
+/// wave_stream generates. (IE, to reproduce the fmt chunk of a wav that was read, from
+/// OpenWavReader::raw_fmt_chunk(), for tools that require a specific fmt layout.) The header is read from
+/// 'raw_fmt_chunk'
+///
+/// # Arguments
+///
+/// * 'file_path' - The path to where the wav will be written
+/// * 'raw_fmt_chunk' - The contents of the fmt chunk, excluding its id and length
+pub fn write_wav_preserving_fmt(file_path: &Path, raw_fmt_chunk: &[u8]) -> Result<OpenWavWriter> {
+    let header = WavHeader::from_raw_fmt_chunk(raw_fmt_chunk)?;
+
+    WavWriterBuilder::new(header)
+        .raw_fmt_chunk(raw_fmt_chunk.to_vec())
+        .build_to_path(file_path)
+}
+
+/// Starts writing a wav to a (Write + Seek) struct, with a data chunk of a fixed size. The size is written in the
+/// header before any samples, and is never updated: The caller must write exactly 'data_bytes' of samples
+///
+/// # Arguments
+///
+/// * 'writer' - The (Write + Seek) struct to write the wav into. It is strongly recommended that this struct implement some form of buffering, such as via a BufWriter
+/// * 'header' - The header information in the wav. This specifies things like sampling rate, sample bit depth, ect
+/// * 'data_bytes' - The size of the data chunk. It must be a multiple of the size of each sample, across all channels
+pub fn write_wav_fixed_size<TWriter: 'static + Write + Seek>(
+    writer: TWriter,
     header: WavHeader,
+    data_bytes: usize,
 ) -> Result<OpenWavWriter> {
-    // Write RIFF header and format
-    writer.write(b"RIFF    WAVE")?;
+    WavWriterBuilder::new(header)
+        .fixed_data_size(data_bytes)
+        .build(writer)
+}
+
+/// Writes a wav, with the sample format selected by the type of the samples. (i8 is written as 8-bit, i16 as 16-bit,
+/// i32 as 24-bit, and f32 as float)
+///
+/// # Arguments
+///
+/// * 'writer' - The (Write + Seek) struct to write the wav into. It is strongly recommended that this struct implement some form of buffering, such as via a BufWriter
+/// * 'channels' - The channels in the wav
+/// * 'sample_rate' - The samples per second
+/// * 'samples_itr' - The samples to write
+pub fn write_wav_auto<TWriter, T, TIterator>(
+    writer: TWriter,
+    channels: Channels,
+    sample_rate: u32,
+    samples_itr: TIterator,
+) -> Result<()>
+where
+    TWriter: 'static + Write + Seek,
+    T: WavSample,
+    TIterator: Iterator<Item = Result<SamplesByChannel<T>>>,
+{
+    let header = WavHeader {
+        sample_format: T::SAMPLE_FORMAT,
+        channels,
+        sample_rate,
+    };
+
+    let open_wav = write_wav(writer, header)?;
+    open_wav.write_all_samples(samples_itr)
+}
+
+/// Reads a whole wav into memory, as interleaved floating point samples. Intended for quick experiments
+///
+/// Note: The whole wav is held in memory, as 4 bytes per channel per sample. (IE, a 10-minute stereo wav at 48kHz
+/// uses about 230MB.) Use a reader from read_wav_from_file_path for long wavs
+///
+/// # Arguments
+///
+/// * 'file_path' - A Path that is the path to the wav file to read
+pub fn load_wav_f32(file_path: &Path) -> Result<(WavHeader, Vec<f32>)> {
+    let open_wav = read_wav_from_file_path(file_path)?;
+    let header = *open_wav.header();
+
+    let mut samples = Vec::with_capacity(open_wav.len_samples() * open_wav.num_channels() as usize);
+    for samples_result in open_wav.get_stream_f32_reader()? {
+        samples.extend(samples_result?.to_vec());
+    }
+
+    Ok((header, samples))
+}
+
+/// Reads a whole wav into memory, as a Vec of floating point samples for each channel. (IE, for feature extraction,
+/// which usually processes each channel separately) The channels are in the order that they are interleaved in the
+/// wav. (Channels::positions())
+///
+/// Note: The whole wav is held in memory, as 4 bytes per channel per sample, like load_wav_f32
+///
+/// # Arguments
+///
+/// * 'file_path' - A Path that is the path to the wav file to read
+pub fn load_wav_planar_f32(file_path: &Path) -> Result<(WavHeader, Vec<Vec<f32>>)> {
+    let open_wav = read_wav_from_file_path(file_path)?;
+    let header = *open_wav.header();
+
+    let positions: Vec<ChannelPosition> = header.channels.positions().collect();
+    let mut channels: Vec<Vec<f32>> = positions
+        .iter()
+        .map(|_| Vec::with_capacity(open_wav.len_samples()))
+        .collect();
+
+    for samples_result in open_wav.get_stream_f32_reader()? {
+        let samples = samples_result?;
+        for (position, channel) in positions.iter().zip(channels.iter_mut()) {
+            channel.push(*samples.get(*position).expect("Channel missing"));
+        }
+    }
+
+    Ok((header, channels))
+}
+
+/// Writes interleaved floating point samples into a new wav. This is the counterpart to load_wav_f32
+///
+/// # Arguments
+///
+/// * 'file_path' - The path to where the wav will be written
+/// * 'sample_rate' - The samples per second
+/// * 'channels' - The channels in the wav, in the order that they are interleaved
+/// * 'interleaved' - The samples. Its length must be a multiple of the number of channels
+pub fn save_wav_f32(
+    file_path: &Path,
+    sample_rate: u32,
+    channels: Channels,
+    interleaved: &[f32],
+) -> Result<()> {
+    let num_channels = channels.count() as usize;
+    if num_channels == 0 || interleaved.len() % num_channels != 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "{} samples can't be split evenly into {} channels",
+                interleaved.len(),
+                num_channels
+            ),
+        ));
+    }
+
+    let header = WavHeader {
+        sample_format: SampleFormat::Float,
+        channels,
+        sample_rate,
+    };
+
+    let open_wav = write_wav_to_file_path(file_path, header)?;
+    let samples_itr = interleaved.chunks(num_channels).map(|frame| {
+        let mut samples_by_channel = SamplesByChannel::new();
+        for (position, sample) in channels.positions().zip(frame) {
+            samples_by_channel.set(position, *sample);
+        }
+
+        Ok(samples_by_channel)
+    });
+
+    open_wav.write_all_f32(samples_itr)
+}
+
+/// Converts a wav file to another sample format, keeping its channels and sample rate. Only lossless conversions are
+/// supported: Samples can be converted to a higher bit depth, or from integers to floating point. (IE, 8-bit to
+/// 16-bit, or 24-bit to float.) Returns an error, without writing the output, if the conversion would lose precision
+///
+/// # Arguments
+///
+/// * 'input' - The path to the wav file to read
+/// * 'output' - The path to where the converted wav will be written
+/// * 'target' - The sample format to convert to
+pub fn transcode(input: &Path, output: &Path, target: SampleFormat) -> Result<()> {
+    if let SampleFormat::ImaAdpcm { .. } = target {
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            "Writing IMA ADPCM is unsupported",
+        ));
+    }
+
+    let open_wav = read_wav_from_file_path(input)?;
+    match open_wav.sample_format() {
+        SampleFormat::Int8 => transcode_as::<i8>(open_wav, output, target),
+        // IMA ADPCM is decoded to 16-bit
+        SampleFormat::Int16 | SampleFormat::ImaAdpcm { .. } => {
+            transcode_as::<i16>(open_wav, output, target)
+        }
+        SampleFormat::Int24 => transcode_as::<i32>(open_wav, output, target),
+        SampleFormat::Int64 => transcode_as::<i64>(open_wav, output, target),
+        SampleFormat::Float => transcode_as::<f32>(open_wav, output, target),
+    }
+}
 
-    WavHeader::to_writer(&mut writer, &header)?;
+// Reads the samples as T, which holds them without conversion, and writes them in the target sample format
+fn transcode_as<T: WavSample + Default>(
+    open_wav: OpenWavReader<BufReader<File>>,
+    output: &Path,
+    target: SampleFormat,
+) -> Result<()> {
+    if T::write_to(target).is_err() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Converting {:?} to {:?} would lose precision",
+                open_wav.sample_format(),
+                target
+            ),
+        ));
+    }
+
+    let header = WavHeader {
+        sample_format: target,
+        ..*open_wav.header()
+    };
 
-    OpenWavWriter::new(writer, header)
+    let open_wav_writer = write_wav_to_file_path(output, header)?;
+    open_wav_writer.write_all_samples(open_wav.get_stream_reader::<T>()?.into_iter())
 }
 
 #[cfg(test)]
 mod tests {
+    use std::cell::Cell;
     use std::fmt::Debug;
     use std::i8;
-    use std::io::{Cursor, Take};
+    use std::io::{BufWriter, Cursor, Take};
+    use std::rc::Rc;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
 
     use tempfile::tempdir;
+    use test_case::test_case;
 
     use super::*;
+    use crate::chunks::ChannelPeak;
+    use crate::constants::{MAX_INT_24, MIN_INT_24};
     use crate::open_wav::OpenWav;
     use crate::samples_by_channel::SamplesByChannel;
     use crate::upconvert::{
@@ -390,57 +976,658 @@ mod tests {
     }
 
     #[test]
-    fn read_float_sanity() {
-        let open_wav = read_wav_from_file_path(Path::new("test_data/short_float.wav")).unwrap();
-        let wave_reader_float = open_wav.get_random_access_f32_reader().unwrap();
+    fn len_samples_u64_large_header() {
+        let header = WavHeader {
+            sample_format: SampleFormat::Int8,
+            channels: Channels::new().front_left(),
+            sample_rate: 48000,
+        };
+
+        // Only the header is written; the data chunk claims to be nearly 4GB
+        let mut cursor = Cursor::new(Vec::new());
+        cursor.write_all(b"RIFF    WAVE").unwrap();
+        WavHeader::to_writer(&mut cursor, &header).unwrap();
+        cursor.write_all(b"data").unwrap();
+        cursor.write_u32(u32::MAX - 1).unwrap();
+        cursor.set_position(0);
+
+        let open_wav = read_wav(cursor).unwrap();
+        assert_eq!((u32::MAX - 1) as u64, open_wav.len_samples_u64());
+
+        #[cfg(target_pointer_width = "64")]
         assert_eq!(
-            SampleFormat::Float,
-            wave_reader_float.info().sample_format()
+            open_wav.len_samples() as u64,
+            open_wav.len_samples_u64(),
+            "len_samples and len_samples_u64 should agree on 64-bit targets"
         );
-        assert_eq!(1, wave_reader_float.info().num_channels());
-        assert_eq!(32, wave_reader_float.info().bits_per_sample());
-        assert_eq!(48000, wave_reader_float.info().sample_rate());
-        assert_eq!(1267, wave_reader_float.info().len_samples());
     }
 
     #[test]
-    fn read_random_i8() {
-        read_random(
-            Path::new("test_data/short_8.wav"),
-            Box::new(|open_wav| open_wav.get_random_access_i8_reader()),
-            i8::from_le_bytes([0x7D]),
-            i8::from_le_bytes([0x7F]),
-            i8::from_le_bytes([0x7A]),
-        )
-        .unwrap();
+    fn read_zero_channels() {
+        let header = WavHeader {
+            sample_format: SampleFormat::Int16,
+            channels: Channels::new(),
+            sample_rate: 48000,
+        };
+
+        let mut cursor = Cursor::new(Vec::new());
+        cursor.write_all(b"RIFF    WAVE").unwrap();
+        WavHeader::to_writer_with_style(&mut cursor, &header, FmtChunkStyle::Classic).unwrap();
+        cursor.write_all(b"data").unwrap();
+        cursor.write_u32(100).unwrap();
+        cursor.write_all(&[0u8; 100]).unwrap();
+        cursor.set_position(0);
+
+        match read_wav(cursor) {
+            Ok(_) => panic!("A wav with no channels should not be read"),
+            Err(err) => assert_eq!(ErrorKind::InvalidData, err.kind()),
+        }
     }
 
     #[test]
-    fn read_random_i8_as_i16() {
-        read_random(
-            Path::new("test_data/short_8.wav"),
-            Box::new(|open_wav| open_wav.get_random_access_i16_reader()),
-            32255,
-            32767,
-            31487,
-        )
-        .unwrap();
+    fn read_raw_frames() {
+        let bytes = std::fs::read("test_data/short_16.wav").unwrap();
+        let data_start = bytes
+            .windows(4)
+            .position(|window| window == b"data")
+            .expect("data chunk missing")
+            + 8;
+        let data_length =
+            u32::from_le_bytes(bytes[data_start - 4..data_start].try_into().unwrap()) as usize;
+
+        let open_wav = read_wav_from_file_path(Path::new("test_data/short_16.wav")).unwrap();
+        assert_eq!(2, open_wav.frame_bytes());
+
+        let mut raw_bytes = Vec::new();
+        for frame_result in open_wav.into_raw_frame_iter() {
+            let frame = frame_result.unwrap();
+            assert_eq!(2, frame.len());
+            raw_bytes.extend(frame);
+        }
+
+        assert_eq!(&bytes[data_start..data_start + data_length], &raw_bytes[..]);
     }
 
     #[test]
-    fn read_random_i8_as_i24() {
-        read_random(
-            Path::new("test_data/short_8.wav"),
-            Box::new(|open_wav| open_wav.get_random_access_i24_reader()),
-            8257535,
-            8388607,
-            8060927,
-        )
-        .unwrap();
+    fn read_from_parts_trailing_bytes() {
+        let header = WavHeader {
+            sample_format: SampleFormat::Int16,
+            channels: Channels::new().front_left().front_right(),
+            sample_rate: 48000,
+        };
+
+        // 10 samples, and 3 bytes of a partial sample
+        let open_wav =
+            OpenWavReader::from_parts(Cursor::new(vec![1u8; 43]), header, 0, 43).unwrap();
+        assert_eq!(10, open_wav.len_samples());
+        assert_eq!(3, open_wav.trailing_bytes());
+
+        let mut samples_read = 0;
+        for samples_result in open_wav.get_stream_i16_reader().unwrap() {
+            samples_result.unwrap();
+            samples_read += 1;
+        }
+
+        assert_eq!(10, samples_read);
     }
 
     #[test]
-    fn read_random_i8_as_f32() {
+    fn read_from_parts() {
+        // Embed the wav in a container with its own 16-byte header
+        let wav = std::fs::read("test_data/short_16.wav").unwrap();
+        let wav_data_start = wav
+            .windows(4)
+            .position(|window| window == b"data")
+            .expect("data chunk missing")
+            + 8;
+        let mut container = vec![0xFFu8; 16];
+        container.extend(&wav);
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Int16,
+            channels: Channels::new().front_left(),
+            sample_rate: 48000,
+        };
+
+        let data_start = 16 + wav_data_start;
+        let mut cursor = Cursor::new(container);
+        cursor.set_position(data_start as u64);
+        let open_wav = OpenWavReader::from_parts(cursor, header, data_start, 1267 * 2).unwrap();
+        assert_eq!(1267, open_wav.len_samples());
+
+        let mut reader = open_wav.get_random_access_i16_reader().unwrap();
+        let mut expected_reader = read_wav_from_file_path(Path::new("test_data/short_16.wav"))
+            .unwrap()
+            .get_random_access_i16_reader()
+            .unwrap();
+
+        for sample in [0, 1, 600, 1266] {
+            assert_eq!(
+                expected_reader.read_sample(sample).unwrap(),
+                reader.read_sample(sample).unwrap(),
+                "Wrong sample read at {sample}"
+            );
+        }
+    }
+
+    #[test]
+    fn read_u8_literal_bytes() {
+        let bytes = std::fs::read("test_data/short_8.wav").unwrap();
+        let data_start = bytes
+            .windows(4)
+            .position(|window| window == b"data")
+            .expect("data chunk missing")
+            + 8;
+
+        let open_wav = read_wav_from_file_path(Path::new("test_data/short_8.wav")).unwrap();
+        let len_samples = open_wav.len_samples();
+        let mut reader = open_wav.get_random_access_u8_reader().unwrap();
+        for sample in 0..len_samples {
+            assert_eq!(
+                Some(bytes[data_start + sample]),
+                reader.read_sample(sample).unwrap().front_left,
+                "Wrong sample read at {sample}"
+            );
+        }
+
+        let open_wav = read_wav_from_file_path(Path::new("test_data/short_8.wav")).unwrap();
+        let samples: Vec<u8> = open_wav
+            .get_stream_u8_reader()
+            .unwrap()
+            .into_iter()
+            .map(|samples_result| samples_result.unwrap().front_left.unwrap())
+            .collect();
+        assert_eq!(&bytes[data_start..data_start + len_samples], &samples[..]);
+
+        let open_wav = read_wav_from_file_path(Path::new("test_data/short_16.wav")).unwrap();
+        assert!(open_wav.get_stream_u8_reader().is_err());
+    }
+
+    #[test]
+    fn read_trailing_bytes() {
+        let header = WavHeader {
+            sample_format: SampleFormat::Int16,
+            channels: Channels::new().front_left(),
+            sample_rate: 48000,
+        };
+
+        // The data chunk is one byte longer than 100 samples
+        let mut cursor = Cursor::new(Vec::new());
+        cursor.write_all(b"RIFF    WAVE").unwrap();
+        WavHeader::to_writer(&mut cursor, &header).unwrap();
+        cursor.write_all(b"data").unwrap();
+        cursor.write_u32(201).unwrap();
+        cursor.write_all(&[1u8; 201]).unwrap();
+        cursor.set_position(0);
+
+        let open_wav = read_wav(cursor).unwrap();
+        assert_eq!(100, open_wav.len_samples());
+        assert_eq!(1, open_wav.trailing_bytes());
+
+        let mut samples_read = 0;
+        for samples_result in open_wav.get_stream_i16_reader().unwrap() {
+            samples_result.unwrap();
+            samples_read += 1;
+        }
+        assert_eq!(100, samples_read);
+    }
+
+    // Returns an error, once, when a read reaches 'fail_at'
+    struct FaultyReader {
+        cursor: Cursor<Vec<u8>>,
+        fail_at: u64,
+        failed: bool,
+    }
+
+    impl Read for FaultyReader {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            let position = self.cursor.position();
+            if !self.failed && position + buf.len() as u64 > self.fail_at {
+                if position < self.fail_at {
+                    let len = (self.fail_at - position) as usize;
+                    return self.cursor.read(&mut buf[..len]);
+                }
+
+                self.failed = true;
+                return Err(Error::other("Bad sector"));
+            }
+
+            self.cursor.read(buf)
+        }
+    }
+
+    // A stereo wav with 6 frames, where reading fails between the left and right channels of the 4th frame
+    fn faulty_wav() -> FaultyReader {
+        let header = WavHeader {
+            sample_format: SampleFormat::Int16,
+            channels: Channels::new().front_left().front_right(),
+            sample_rate: 48000,
+        };
+
+        let mut cursor = Cursor::new(Vec::new());
+        cursor.write_all(b"RIFF    WAVE").unwrap();
+        WavHeader::to_writer(&mut cursor, &header).unwrap();
+        cursor.write_all(b"data").unwrap();
+        cursor.write_u32(6 * 4).unwrap();
+        let data_start = cursor.position();
+        for sample in 0..6 {
+            cursor.write_i16(sample).unwrap();
+            cursor.write_i16(-sample).unwrap();
+        }
+        cursor.set_position(0);
+
+        FaultyReader {
+            cursor,
+            fail_at: data_start + 3 * 4 + 2,
+            failed: false,
+        }
+    }
+
+    #[test_case(ErrorPolicy::SkipFrame, &[Some(0), Some(1), Some(2), Some(0), Some(4), Some(5)] ; "skip frame")]
+    #[test_case(ErrorPolicy::Stop, &[Some(0), Some(1), Some(2)] ; "stop")]
+    #[test_case(ErrorPolicy::Propagate, &[Some(0), Some(1), Some(2), None, Some(4), Some(5)] ; "propagate")]
+    fn read_lenient(on_error: ErrorPolicy, expected: &[Option<i16>]) {
+        let samples: Vec<Option<i16>> = read_wav(faulty_wav())
+            .unwrap()
+            .get_stream_i16_reader()
+            .unwrap()
+            .into_lenient_iter(on_error)
+            .map(|samples_result| match samples_result {
+                Ok(samples) => {
+                    assert_eq!(samples.front_left.map(|left| -left), samples.front_right);
+                    samples.front_left
+                }
+                Err(_) => None,
+            })
+            .collect();
+
+        assert_eq!(expected, &samples[..]);
+    }
+
+    #[test]
+    fn read_stream_fused_after_error() {
+        let mut iter = read_wav(faulty_wav())
+            .unwrap()
+            .get_stream_i16_reader()
+            .unwrap()
+            .into_iter();
+
+        for sample in 0..3 {
+            assert_eq!(Some(sample), iter.next().unwrap().unwrap().front_left);
+        }
+
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn read_error_includes_sample() {
+        let err = read_wav(faulty_wav())
+            .unwrap()
+            .get_stream_i16_reader()
+            .unwrap()
+            .into_iter()
+            .find_map(|samples_result| samples_result.err())
+            .unwrap();
+        assert_eq!(ErrorKind::Other, err.kind());
+        assert_eq!("Sample 3, FrontRight channel: Bad sector", err.to_string());
+
+        // Truncated in the middle of the samples
+        let wav = std::fs::read("test_data/short_16.wav").unwrap();
+        let mut reader = read_wav(Cursor::new(wav[..1000].to_vec()))
+            .unwrap()
+            .get_random_access_i16_reader()
+            .unwrap();
+        let err = reader.read_sample(482).expect_err("The wav is truncated");
+        assert_eq!(ErrorKind::UnexpectedEof, err.kind());
+        assert!(
+            err.to_string().starts_with("Sample 482, FrontLeft channel"),
+            "{err}"
+        );
+
+        let err = reader
+            .read_range(482..490)
+            .expect_err("The wav is truncated");
+        assert!(
+            err.to_string().starts_with("Sample 482, FrontLeft channel"),
+            "{err}"
+        );
+
+        let mut samples_by_channel = SamplesByChannel::new();
+        let err = reader
+            .read_frame_into(482, &mut samples_by_channel)
+            .expect_err("The wav is truncated");
+        assert!(
+            err.to_string().starts_with("Sample 482, FrontLeft channel"),
+            "{err}"
+        );
+
+        let err = read_wav(Cursor::new(wav[..1000].to_vec()))
+            .unwrap()
+            .get_raw_stream_f32()
+            .unwrap()
+            .find_map(|samples_result| samples_result.err())
+            .unwrap();
+        assert!(err.to_string().starts_with("Sample 478: "), "{err}");
+    }
+
+    #[test]
+    fn reopen_reads_independently() {
+        let open_wav = read_wav_from_file_path(Path::new("test_data/short_24.wav")).unwrap();
+        let len_samples = open_wav.len_samples();
+        let middle = len_samples / 2;
+
+        let first_half = open_wav.reopen().unwrap();
+        let second_half = open_wav.reopen().unwrap();
+
+        let read_range = |reopened: OpenWavReader<BufReader<File>>,
+                          range: std::ops::Range<usize>| {
+            let mut reader = reopened.get_random_access_f32_reader().unwrap();
+            range
+                .map(|sample| reader.read_sample(sample).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let (mut samples, second_samples) = std::thread::scope(|scope| {
+            let first = scope.spawn(|| read_range(first_half, 0..middle));
+            let second = scope.spawn(|| read_range(second_half, middle..len_samples));
+            (first.join().unwrap(), second.join().unwrap())
+        });
+        samples.extend(second_samples);
+
+        let expected: Vec<SamplesByChannel<f32>> = open_wav
+            .get_stream_f32_reader()
+            .unwrap()
+            .into_iter()
+            .map(|samples_result| samples_result.unwrap())
+            .collect();
+        assert_eq!(expected, samples);
+
+        // Wavs that aren't read from a file can't be reopened
+        let wav = std::fs::read("test_data/short_24.wav").unwrap();
+        let err = read_wav(Cursor::new(wav))
+            .unwrap()
+            .reopen()
+            .err()
+            .expect("Wavs read from a Cursor can't be reopened");
+        assert_eq!(ErrorKind::Unsupported, err.kind());
+    }
+
+    #[test]
+    fn read_with_rounding_mode() {
+        let read_i24 = |rounding_mode: Option<RoundingMode>| -> Vec<i32> {
+            let mut open_wav =
+                read_wav_from_file_path(Path::new("test_data/short_16.wav")).unwrap();
+            if let Some(rounding_mode) = rounding_mode {
+                open_wav.set_rounding_mode(rounding_mode);
+            }
+
+            open_wav
+                .get_stream_i24_reader()
+                .unwrap()
+                .into_iter()
+                .map(|samples| samples.unwrap().front_left.unwrap())
+                .collect()
+        };
+
+        let samples_i16: Vec<i16> = read_wav_from_file_path(Path::new("test_data/short_16.wav"))
+            .unwrap()
+            .get_stream_i16_reader()
+            .unwrap()
+            .into_iter()
+            .map(|samples| samples.unwrap().front_left.unwrap())
+            .collect();
+
+        // The default is full scale
+        assert_eq!(read_i24(None), read_i24(Some(RoundingMode::FullScale)));
+
+        let shifted = read_i24(Some(RoundingMode::Shift));
+        let full_scale = read_i24(None);
+        for ((sample_i16, shifted), full_scale) in samples_i16.iter().zip(shifted).zip(full_scale) {
+            assert_eq!(*sample_i16 as i32 * 256, shifted);
+            if *sample_i16 >= 0 {
+                assert_eq!(shifted + 255, full_scale);
+            } else {
+                assert_eq!(shifted, full_scale);
+            }
+        }
+    }
+
+    #[test]
+    fn data_crc32() {
+        let temp_dir = tempdir().unwrap();
+        let header = WavHeader {
+            sample_format: SampleFormat::Int16,
+            channels: Channels::new().front_left().front_right(),
+            sample_rate: 48000,
+        };
+
+        let write = |file_name: &str, changed_sample: Option<usize>| {
+            let path = temp_dir.path().join(file_name);
+            let open_wav = write_wav_to_file_path(&path, header).unwrap();
+            open_wav
+                .write_all_i16((0..1000).map(|sample| {
+                    let left = if Some(sample) == changed_sample {
+                        1
+                    } else {
+                        sample as i16
+                    };
+                    Ok(SamplesByChannel::new()
+                        .front_left(left)
+                        .front_right(-(sample as i16)))
+                }))
+                .unwrap();
+
+            read_wav_from_file_path(&path)
+                .unwrap()
+                .data_crc32()
+                .unwrap()
+        };
+
+        let crc32 = write("original.wav", None);
+        assert_eq!(crc32, write("copy.wav", None));
+        assert_ne!(crc32, write("changed.wav", Some(500)));
+
+        // The crc only covers the data chunk
+        let path = temp_dir.path().join("titled.wav");
+        let mut open_wav = write_wav_to_file_path(&path, header).unwrap();
+        open_wav.set_display_title("Titled").unwrap();
+        open_wav
+            .write_all_i16((0..1000).map(|sample| {
+                Ok(SamplesByChannel::new()
+                    .front_left(sample as i16)
+                    .front_right(-(sample as i16)))
+            }))
+            .unwrap();
+        let mut open_wav = read_wav_from_file_path(&path).unwrap();
+        assert_eq!(crc32, open_wav.data_crc32().unwrap());
+
+        // The wav can still be read after the crc is computed
+        let first_sample = open_wav
+            .get_stream_i16_reader()
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            SamplesByChannel::new().front_left(0).front_right(0),
+            first_sample
+        );
+    }
+
+    #[test]
+    fn read_ima_adpcm() {
+        let mut cursor = Cursor::new(Vec::new());
+        cursor.write_all(b"RIFF    WAVE").unwrap();
+
+        cursor.write_all(b"fmt ").unwrap();
+        cursor.write_u32(20).unwrap();
+        // wFormatTag: IMA ADPCM
+        cursor.write_u16(0x11).unwrap();
+        // nChannels
+        cursor.write_u16(1).unwrap();
+        // nSamplesPerSec
+        cursor.write_u32(8000).unwrap();
+        // nAvgBytesPerSec
+        cursor.write_u32(8000 * 8 / 9).unwrap();
+        // nBlockAlign
+        cursor.write_u16(8).unwrap();
+        // wBitsPerSample
+        cursor.write_u16(4).unwrap();
+        // cbSize
+        cursor.write_u16(2).unwrap();
+        // wSamplesPerBlock
+        cursor.write_u16(9).unwrap();
+
+        // Two blocks, and a partial block that only has its header
+        cursor.write_all(b"data").unwrap();
+        cursor.write_u32(22).unwrap();
+        cursor
+            .write_all(&[100, 0, 0, 0, 0x74, 0x98, 0x00, 0x00])
+            .unwrap();
+        cursor
+            .write_all(&[0x18, 0xFC, 0, 0, 0x00, 0x00, 0x00, 0x00])
+            .unwrap();
+        cursor.write_all(&[0xE8, 0x03, 0, 0, 0x77, 0x77]).unwrap();
+        cursor.set_position(0);
+
+        let open_wav = read_wav(cursor).unwrap();
+        assert_eq!(
+            SampleFormat::ImaAdpcm {
+                block_align: 8,
+                samples_per_block: 9
+            },
+            open_wav.sample_format()
+        );
+        assert_eq!(Channels::new().front_left(), *open_wav.channels());
+        assert_eq!(19, open_wav.len_samples());
+
+        let samples: Vec<i16> = open_wav
+            .get_stream_i16_reader()
+            .unwrap()
+            .into_iter()
+            .map(|samples_result| samples_result.unwrap().front_left.unwrap())
+            .collect();
+
+        let mut expected = vec![100, 107, 123, 121, 115, 117, 118, 119, 120];
+        expected.extend([-1000; 9]);
+        expected.push(1000);
+        assert_eq!(expected, samples);
+    }
+
+    #[test]
+    fn ima_adpcm_random_access_unsupported() {
+        let header = WavHeader {
+            sample_format: SampleFormat::ImaAdpcm {
+                block_align: 8,
+                samples_per_block: 9,
+            },
+            channels: Channels::new().front_left(),
+            sample_rate: 8000,
+        };
+
+        let open_wav =
+            OpenWavReader::from_parts(Cursor::new(vec![0u8; 16]), header, 0, 16).unwrap();
+        assert_eq!(18, open_wav.len_samples());
+        let err = open_wav
+            .get_random_access_i16_reader()
+            .err()
+            .expect("IMA ADPCM can only be streamed");
+        assert_eq!(ErrorKind::Unsupported, err.kind());
+
+        let err = WavHeader::to_writer(&mut Cursor::new(Vec::new()), &header)
+            .expect_err("IMA ADPCM can not be written");
+        assert_eq!(ErrorKind::Unsupported, err.kind());
+    }
+
+    #[test]
+    fn load_wav_f32_sanity() {
+        let (header, samples) = load_wav_f32(Path::new("test_data/short_float.wav")).unwrap();
+        assert_eq!(SampleFormat::Float, header.sample_format);
+        assert_eq!(Channels::new().front_left(), header.channels);
+        assert_eq!(48000, header.sample_rate);
+        assert_eq!(1267, samples.len());
+
+        let mut reader = read_wav_from_file_path(Path::new("test_data/short_float.wav"))
+            .unwrap()
+            .get_random_access_f32_reader()
+            .unwrap();
+        assert_eq!(reader.read_sample(0).unwrap().front_left, Some(samples[0]));
+    }
+
+    #[test]
+    fn save_wav_f32_round_trip() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("saved.wav");
+
+        let channels = Channels::new().front_left().front_right();
+        let interleaved: Vec<f32> = (0..200).map(|sample| (sample as f32) / 200.0).collect();
+        save_wav_f32(&path, 44100, channels, &interleaved).unwrap();
+
+        let (header, samples) = load_wav_f32(&path).unwrap();
+        assert_eq!(channels, header.channels);
+        assert_eq!(44100, header.sample_rate);
+        assert_eq!(interleaved, samples);
+
+        let err = save_wav_f32(&path, 44100, channels, &interleaved[..199])
+            .expect_err("199 samples can't be split into 2 channels");
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+    }
+
+    #[test]
+    fn read_float_sanity() {
+        let open_wav = read_wav_from_file_path(Path::new("test_data/short_float.wav")).unwrap();
+        let wave_reader_float = open_wav.get_random_access_f32_reader().unwrap();
+        assert_eq!(
+            SampleFormat::Float,
+            wave_reader_float.info().sample_format()
+        );
+        assert_eq!(1, wave_reader_float.info().num_channels());
+        assert_eq!(32, wave_reader_float.info().bits_per_sample());
+        assert_eq!(48000, wave_reader_float.info().sample_rate());
+        assert_eq!(1267, wave_reader_float.info().len_samples());
+    }
+
+    #[test]
+    fn read_random_i8() {
+        read_random(
+            Path::new("test_data/short_8.wav"),
+            Box::new(|open_wav| open_wav.get_random_access_i8_reader()),
+            i8::from_le_bytes([0x7D]),
+            i8::from_le_bytes([0x7F]),
+            i8::from_le_bytes([0x7A]),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn read_random_i8_as_i16() {
+        read_random(
+            Path::new("test_data/short_8.wav"),
+            Box::new(|open_wav| open_wav.get_random_access_i16_reader()),
+            32255,
+            32767,
+            31487,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn read_random_i8_as_i24() {
+        read_random(
+            Path::new("test_data/short_8.wav"),
+            Box::new(|open_wav| open_wav.get_random_access_i24_reader()),
+            8257535,
+            8388607,
+            8060927,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn read_random_i8_as_f32() {
         read_random(
             Path::new("test_data/short_8.wav"),
             Box::new(|open_wav| open_wav.get_random_access_f32_reader()),
@@ -559,6 +1746,148 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn read_frame_into_sanity() {
+        let open_wav = read_wav_from_file_path(Path::new("test_data/short_16.wav")).unwrap();
+        let mut wave_reader = open_wav.get_random_access_f32_reader().unwrap();
+
+        // The stale front_right value must be cleared, because the wav is mono
+        let mut samples_by_channel = SamplesByChannel::new().front_right(1.0);
+
+        for sample in 0..wave_reader.info().len_samples() {
+            wave_reader
+                .read_frame_into(sample, &mut samples_by_channel)
+                .unwrap();
+
+            assert_eq!(
+                wave_reader.read_sample(sample).unwrap(),
+                samples_by_channel,
+                "Wrong sample read at {sample}"
+            );
+        }
+    }
+
+    #[test_case(1 ; "one sample per block")]
+    #[test_case(100 ; "partial first block")]
+    #[test_case(10000 ; "one block")]
+    fn read_reverse_chunks(block: usize) {
+        let path = Path::new("test_data/short_24.wav");
+        let forward: Vec<SamplesByChannel<i32>> = read_wav_from_file_path(path)
+            .unwrap()
+            .get_stream_i24_reader()
+            .unwrap()
+            .into_iter()
+            .collect::<Result<_>>()
+            .unwrap();
+
+        let reversed: Vec<SamplesByChannel<i32>> = read_wav_from_file_path(path)
+            .unwrap()
+            .get_random_access_i24_reader()
+            .unwrap()
+            .into_reverse_chunks(block)
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+
+        assert!(!forward.is_empty());
+        assert_eq!(forward.into_iter().rev().collect::<Vec<_>>(), reversed);
+    }
+
+    #[test]
+    fn read_reverse_chunks_empty_block() {
+        let err = read_wav_from_file_path(Path::new("test_data/short_16.wav"))
+            .unwrap()
+            .get_random_access_i16_reader()
+            .unwrap()
+            .into_reverse_chunks(0)
+            .err()
+            .expect("Blocks must have at least 1 sample");
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+    }
+
+    #[test]
+    fn read_random_generic() {
+        let mut generic_reader = read_wav_from_file_path(Path::new("test_data/short_16.wav"))
+            .unwrap()
+            .get_random_access_reader::<i16>()
+            .unwrap();
+        let mut named_reader = read_wav_from_file_path(Path::new("test_data/short_16.wav"))
+            .unwrap()
+            .get_random_access_i16_reader()
+            .unwrap();
+        assert_eq!(
+            named_reader.read_sample(100).unwrap(),
+            generic_reader.read_sample(100).unwrap()
+        );
+
+        let mut generic_reader = read_wav_from_file_path(Path::new("test_data/short_16.wav"))
+            .unwrap()
+            .get_random_access_reader::<f32>()
+            .unwrap();
+        let mut named_reader = read_wav_from_file_path(Path::new("test_data/short_16.wav"))
+            .unwrap()
+            .get_random_access_f32_reader()
+            .unwrap();
+        assert_eq!(
+            named_reader.read_sample(100).unwrap(),
+            generic_reader.read_sample(100).unwrap()
+        );
+
+        let err = read_wav_from_file_path(Path::new("test_data/short_16.wav"))
+            .unwrap()
+            .get_random_access_reader::<i8>()
+            .err()
+            .expect("Downsampling isn't supported");
+        assert_eq!(ErrorKind::InvalidData, err.kind());
+    }
+
+    #[test]
+    fn read_stream_generic() {
+        let generic_samples: Vec<SamplesByChannel<i32>> =
+            read_wav_from_file_path(Path::new("test_data/short_24.wav"))
+                .unwrap()
+                .get_stream_reader::<i32>()
+                .unwrap()
+                .into_iter()
+                .map(|samples| samples.unwrap())
+                .collect();
+        let named_samples: Vec<SamplesByChannel<i32>> =
+            read_wav_from_file_path(Path::new("test_data/short_24.wav"))
+                .unwrap()
+                .get_stream_i24_reader()
+                .unwrap()
+                .into_iter()
+                .map(|samples| samples.unwrap())
+                .collect();
+
+        assert_eq!(1267, generic_samples.len());
+        assert_eq!(named_samples, generic_samples);
+    }
+
+    #[test]
+    fn write_random_generic() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Int24,
+                channels: Channels::new().front_left(),
+                sample_rate: 48000,
+            };
+
+            let open_wav = write_wav_to_file_path(path, header)?;
+            let mut writer = open_wav.get_random_access_writer::<i16>()?;
+            writer.write_samples(0, SamplesByChannel::new().front_left(i16::MAX))?;
+            writer.flush()?;
+
+            let mut reader = read_wav_from_file_path(path)?.get_random_access_reader::<i32>()?;
+            assert_eq!(
+                SamplesByChannel::new().front_left(MAX_INT_24),
+                reader.read_sample(0)?
+            );
+
+            Ok(())
+        }));
+    }
+
     #[test]
     fn read_stream_f32_sanity() {
         let file = File::open(Path::new("test_data/short_float.wav")).unwrap();
@@ -696,6 +2025,133 @@ mod tests {
         .unwrap();
     }
 
+    #[test]
+    fn windowed_boundaries() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("windowed.wav");
+
+        save_wav_f32(
+            &path,
+            48000,
+            Channels::new().front_left(),
+            &[1.0, 2.0, 3.0, 4.0, 5.0],
+        )
+        .unwrap();
+
+        let read_windows = |lookahead: usize| -> Vec<Vec<f32>> {
+            read_wav_from_file_path(&path)
+                .unwrap()
+                .get_stream_f32_reader()
+                .unwrap()
+                .windowed(lookahead)
+                .map(|window_result| {
+                    window_result
+                        .unwrap()
+                        .iter()
+                        .map(|samples| samples.front_left.unwrap())
+                        .collect()
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            vec![
+                vec![1.0, 2.0, 3.0],
+                vec![2.0, 3.0, 4.0],
+                vec![3.0, 4.0, 5.0],
+                vec![4.0, 5.0, 0.0],
+                vec![5.0, 0.0, 0.0],
+            ],
+            read_windows(2)
+        );
+
+        assert_eq!(
+            vec![vec![1.0], vec![2.0], vec![3.0], vec![4.0], vec![5.0]],
+            read_windows(0)
+        );
+
+        let windows = read_windows(6);
+        assert_eq!(5, windows.len());
+        assert_eq!(vec![1.0, 2.0, 3.0, 4.0, 5.0, 0.0, 0.0], windows[0]);
+        assert_eq!(vec![5.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0], windows[4]);
+    }
+
+    #[test]
+    fn read_time_range() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("two_seconds.wav");
+
+        let samples: Vec<f32> = (0..2000).map(|sample| sample as f32).collect();
+        save_wav_f32(&path, 1000, Channels::new().front_left(), &samples).unwrap();
+
+        let read_range = |start_ms: u64, end_ms: u64| {
+            read_wav_from_file_path(&path).unwrap().read_time_range_f32(
+                Duration::from_millis(start_ms),
+                Duration::from_millis(end_ms),
+            )
+        };
+
+        let range = read_range(500, 1000).unwrap();
+        assert_eq!(500, range.len());
+        assert_eq!(Some(500.0), range[0].front_left);
+        assert_eq!(Some(999.0), range[499].front_left);
+
+        // Clamped to the end of the wav
+        let range = read_range(1500, 5000).unwrap();
+        assert_eq!(500, range.len());
+        assert_eq!(Some(1999.0), range[499].front_left);
+
+        assert!(read_range(5000, 6000).unwrap().is_empty());
+
+        let err = read_range(1000, 500).expect_err("The start is after the end");
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+    }
+
+    #[test]
+    fn broadcast_to_5_1() {
+        let open_wav = read_wav_from_file_path(Path::new("test_data/short_float.wav")).unwrap();
+        let expected_reader = read_wav_from_file_path(Path::new("test_data/short_float.wav"))
+            .unwrap()
+            .get_stream_f32_reader()
+            .unwrap();
+
+        let channels = Channels::new()
+            .front_left()
+            .front_right()
+            .front_center()
+            .low_frequency()
+            .back_left()
+            .back_right();
+
+        let broadcast = open_wav
+            .get_stream_f32_reader()
+            .unwrap()
+            .broadcast_to(channels)
+            .unwrap();
+
+        let mut samples_read = 0;
+        for (samples_result, expected_result) in broadcast.zip(expected_reader) {
+            let samples = samples_result.unwrap();
+            let expected = expected_result.unwrap().front_left.unwrap();
+
+            assert_eq!(
+                SamplesByChannel::new()
+                    .front_left(expected)
+                    .front_right(expected)
+                    .front_center(expected)
+                    .low_frequency(expected)
+                    .back_left(expected)
+                    .back_right(expected),
+                samples,
+                "Wrong sample read at {samples_read}"
+            );
+
+            samples_read += 1;
+        }
+
+        assert_eq!(1267, samples_read);
+    }
+
     fn read_stream<T: Debug + PartialEq + Default + Clone>(
         path: &Path,
         get_stream_reader: Box<
@@ -1242,6 +2698,2076 @@ mod tests {
         }));
     }
 
+    #[test]
+    fn write_channel_sample_by_pass() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Int16,
+                channels: Channels::new().front_left().front_right().front_center(),
+                sample_rate: 96000,
+            };
+
+            let open_wav = write_wav_to_file_path(path, header)?;
+            let mut writer = open_wav.get_random_access_i16_writer()?;
+
+            // Each channel is written in a separate pass
+            for sample in 0..100usize {
+                writer.write_channel_sample(sample, ChannelPosition::FrontLeft, sample as i16)?;
+            }
+            for sample in 0..100usize {
+                writer.write_channel_sample(
+                    sample,
+                    ChannelPosition::FrontRight,
+                    -(sample as i16),
+                )?;
+            }
+            for sample in 0..100usize {
+                writer.write_channel_sample(
+                    sample,
+                    ChannelPosition::FrontCenter,
+                    (sample * 2) as i16,
+                )?;
+            }
+
+            let err = writer
+                .write_channel_sample(0, ChannelPosition::LowFrequency, 0)
+                .expect_err("low_frequency isn't in the wav");
+            assert_eq!(ErrorKind::InvalidInput, err.kind());
+
+            writer.flush()?;
+
+            let open_wav = read_wav_from_file_path(path)?;
+            assert_eq!(100, open_wav.len_samples());
+
+            let mut reader = open_wav.get_random_access_i16_reader()?;
+            for sample in 0..100usize {
+                assert_eq!(
+                    SamplesByChannel::new()
+                        .front_left(sample as i16)
+                        .front_right(-(sample as i16))
+                        .front_center((sample * 2) as i16),
+                    reader.read_sample(sample)?,
+                    "Wrong sample read at {sample}"
+                );
+            }
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn rewrite_header_in_place_keeps_data() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Int16,
+                channels: Channels::new().front_left().front_right(),
+                sample_rate: 44100,
+            };
+
+            let open_wav = write_wav_to_file_path(path, header)?;
+            open_wav.write_all_i16((0..100i16).map(|sample| {
+                Ok(SamplesByChannel::new()
+                    .front_left(sample)
+                    .front_right(-sample))
+            }))?;
+
+            let original = std::fs::read(path)?;
+
+            let new_header = WavHeader {
+                channels: Channels::new().side_left().side_right(),
+                sample_rate: 48000,
+                ..header
+            };
+            rewrite_header_in_place(path, new_header)?;
+
+            let rewritten = std::fs::read(path)?;
+            assert_eq!(original.len(), rewritten.len());
+            // The RIFF header and everything after the fmt chunk are unchanged
+            assert_eq!(original[0..12], rewritten[0..12]);
+            assert_eq!(original[60..], rewritten[60..]);
+
+            let open_wav = read_wav_from_file_path(path)?;
+            assert_eq!(new_header.channels, *open_wav.channels());
+            assert_eq!(48000, open_wav.sample_rate());
+
+            let mut reader = open_wav.get_random_access_i16_reader()?;
+            assert_eq!(
+                SamplesByChannel::new().side_left(50).side_right(-50),
+                reader.read_sample(50)?
+            );
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn rewrite_header_in_place_rejects_size_change() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Int16,
+                channels: Channels::new().front_left(),
+                sample_rate: 44100,
+            };
+
+            let open_wav = WavWriterBuilder::new(header)
+                .fmt_style(FmtChunkStyle::Classic)
+                .build_to_path(path)?;
+            open_wav.write_all_i16(
+                (0..100).map(|sample| Ok(SamplesByChannel::new().front_left(sample))),
+            )?;
+
+            let original = std::fs::read(path)?;
+
+            // A classic float fmt chunk has cbSize, so it's 2 bytes longer
+            let err = rewrite_header_in_place(
+                path,
+                WavHeader {
+                    sample_format: SampleFormat::Float,
+                    ..header
+                },
+            )
+            .expect_err("The fmt chunk can't change size");
+            assert_eq!(ErrorKind::InvalidInput, err.kind());
+            assert_eq!(original, std::fs::read(path)?);
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn estimate_wav_size_matches_written() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Int24,
+                channels: Channels::new().front_left().front_right(),
+                sample_rate: 48000,
+            };
+
+            let open_wav = write_wav_to_file_path(path, header)?;
+            open_wav.write_all_i24(
+                (0..1000).map(|_| Ok(SamplesByChannel::new().front_left(0).front_right(0))),
+            )?;
+
+            assert_eq!(
+                std::fs::metadata(path)?.len(),
+                estimate_wav_size(&header, 1000)
+            );
+
+            Ok(())
+        }));
+    }
+
+    #[cfg(feature = "disk_space")]
+    #[test]
+    fn check_disk_space_insufficient() {
+        check_disk_space(1000, 1000).unwrap();
+
+        let err = check_disk_space(1001, 1000).expect_err("Not enough space");
+        assert_eq!(ErrorKind::Other, err.kind());
+    }
+
+    #[cfg(feature = "disk_space")]
+    #[test]
+    fn write_wav_to_file_path_checked_writes() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Int16,
+                channels: Channels::new().front_left(),
+                sample_rate: 48000,
+            };
+
+            let open_wav = write_wav_to_file_path_checked(path, header, 100)?;
+            open_wav.write_all_i16(
+                (0..100).map(|sample| Ok(SamplesByChannel::new().front_left(sample))),
+            )?;
+
+            assert_eq!(100, read_wav_from_file_path(path)?.len_samples());
+
+            Ok(())
+        }));
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn ndarray_round_trip() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Float,
+                channels: Channels::new().front_left().front_right(),
+                sample_rate: 48000,
+            };
+
+            let data = ndarray::Array2::from_shape_fn((100, 2), |(sample, channel)| {
+                let value = sample as f32 / 100.0;
+                if channel == 0 {
+                    value
+                } else {
+                    -value
+                }
+            });
+
+            let open_wav = write_wav_to_file_path(path, header)?;
+            open_wav.write_all_ndarray_f32(data.view())?;
+
+            let read = read_wav_from_file_path(path)?.read_ndarray_f32()?;
+            assert_eq!(data, read);
+
+            // The array must have a column for each channel
+            let open_wav = write_wav_to_file_path(path, header)?;
+            let err = open_wav
+                .write_all_ndarray_f32(ndarray::Array2::zeros((100, 3)).view())
+                .expect_err("The wav has 2 channels");
+            assert_eq!(ErrorKind::InvalidInput, err.kind());
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn read_lowercase_wave_lenient() {
+        let path = Path::new("test_data/short_16_lowercase.wav");
+
+        match read_wav_from_file_path(path) {
+            Err(err) => assert_eq!(ErrorKind::Unsupported, err.kind()),
+            Ok(_) => panic!("The form type is \"wave\""),
+        }
+
+        let open_wav = read_wav_lenient(BufReader::new(File::open(path).unwrap())).unwrap();
+        assert_eq!(
+            vec!["Read \"wave\" as \"WAVE\"".to_string()],
+            open_wav.warnings()
+        );
+
+        let expected = read_wav_from_file_path(Path::new("test_data/short_16.wav")).unwrap();
+        assert!(expected.warnings().is_empty());
+        assert_eq!(expected.header(), open_wav.header());
+
+        let expected_samples: Vec<SamplesByChannel<i16>> = expected
+            .get_stream_i16_reader()
+            .unwrap()
+            .into_iter()
+            .collect::<Result<_>>()
+            .unwrap();
+        let actual_samples: Vec<SamplesByChannel<i16>> = open_wav
+            .get_stream_i16_reader()
+            .unwrap()
+            .into_iter()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(expected_samples, actual_samples);
+    }
+
+    #[test]
+    fn bytes_read_and_written() {
+        let (data_start, _) = read_wav_from_file_path(Path::new("test_data/short_16.wav"))
+            .unwrap()
+            .get_stream_i16_reader()
+            .unwrap()
+            .into_iter_with_offsets()
+            .next()
+            .unwrap()
+            .unwrap();
+
+        let open_wav = read_wav_from_file_path(Path::new("test_data/short_16.wav")).unwrap();
+        let frame_bytes = open_wav.frame_bytes();
+        assert!(open_wav.bytes_read() >= data_start);
+
+        let mut samples = open_wav.get_stream_i16_reader().unwrap().into_iter();
+        for _ in 0..100 {
+            samples.next().unwrap().unwrap();
+        }
+        assert!(samples.bytes_read() >= data_start + 100 * frame_bytes);
+
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Int16,
+                channels: Channels::new().front_left().front_right(),
+                sample_rate: 44100,
+            };
+
+            let mut writer =
+                write_wav_to_file_path(path, header)?.get_random_access_i16_writer()?;
+            let header_bytes = writer.info().bytes_written();
+            assert!(header_bytes > 0);
+
+            for sample in 0..100 {
+                writer.write_samples(
+                    sample,
+                    SamplesByChannel::new().front_left(1).front_right(-1),
+                )?;
+            }
+            writer.flush()?;
+
+            assert!(writer.info().bytes_written() >= header_bytes + 100 * 4);
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn same_audio_ignores_metadata() {
+        let temp_dir = tempdir().unwrap();
+        let original = Path::new("test_data/short_16.wav");
+
+        let write_copy = |name: &str, title: &str, change_sample: bool| {
+            let path = temp_dir.path().join(name);
+            let open_wav = read_wav_from_file_path(original).unwrap();
+            let mut copy = write_wav_to_file_path(&path, *open_wav.header()).unwrap();
+            copy.set_display_title(title).unwrap();
+            copy.write_all_i16(
+                open_wav
+                    .get_stream_i16_reader()
+                    .unwrap()
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(sample, samples_result)| {
+                        let samples = samples_result?;
+                        if change_sample && sample == 500 {
+                            let front_left = samples.front_left.unwrap().wrapping_add(1);
+                            return Ok(samples.front_left(front_left));
+                        }
+
+                        Ok(samples)
+                    }),
+            )
+            .unwrap();
+
+            path
+        };
+
+        let titled = write_copy("titled.wav", "Same audio", false);
+        let changed = write_copy("changed.wav", "Same audio", true);
+
+        assert!(same_audio(original, original).unwrap());
+        assert!(same_audio(original, &titled).unwrap());
+        assert!(!same_audio(original, &changed).unwrap());
+        assert!(!same_audio(original, Path::new("test_data/short_8.wav")).unwrap());
+    }
+
+    #[test]
+    fn load_fft_frames_windows() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Float,
+                channels: Channels::new().front_left().front_right(),
+                sample_rate: 48000,
+            };
+
+            let open_wav = write_wav_to_file_path(path, header)?;
+            open_wav.write_all_f32(
+                (0..1000).map(|_| Ok(SamplesByChannel::new().front_left(0.25).front_right(0.75))),
+            )?;
+
+            // Windows start at 0, 128, ... 768. The window at 768 reaches the end of the wav
+            let (sample_rate, windows) = load_fft_frames(path, 256, 128)?;
+            assert_eq!(48000, sample_rate);
+            assert_eq!(7, windows.len());
+            assert!(windows.iter().all(|window| window.len() == 256));
+
+            // The channels are averaged, and the last window is padded
+            assert_eq!(0.5, windows[0][0]);
+            assert_eq!(0.5, windows[6][231]);
+            assert_eq!(0.0, windows[6][232]);
+
+            let err = load_fft_frames(path, 256, 0).expect_err("The hop is 0");
+            assert_eq!(ErrorKind::InvalidInput, err.kind());
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn write_preserving_fmt() {
+        test_with_file(Box::new(|path| {
+            let original = Path::new("test_data/short_24.wav");
+            let open_wav = read_wav_from_file_path(original)?;
+            let raw_fmt_chunk = open_wav.raw_fmt_chunk().to_vec();
+            assert_eq!(16, raw_fmt_chunk.len());
+
+            // By default, the fmt chunk is written with the extensible layout
+            write_wav_to_file_path(path, *open_wav.header())?
+                .write_all_i24(std::iter::empty::<Result<SamplesByChannel<i32>>>())?;
+            assert_ne!(
+                raw_fmt_chunk,
+                read_wav_from_file_path(path)?.raw_fmt_chunk()
+            );
+
+            let copy = write_wav_preserving_fmt(path, &raw_fmt_chunk)?;
+            assert_eq!(open_wav.header(), copy.header());
+            copy.write_all_i24(open_wav.get_stream_i24_reader()?.into_iter())?;
+
+            let original_bytes = std::fs::read(original)?;
+            let copy_bytes = std::fs::read(path)?;
+            assert_eq!(original_bytes[12..36], copy_bytes[12..36]);
+            assert_eq!(
+                raw_fmt_chunk,
+                read_wav_from_file_path(path)?.raw_fmt_chunk()
+            );
+
+            // The fmt chunk must match the header
+            let header = WavHeader {
+                sample_format: SampleFormat::Int16,
+                ..*read_wav_from_file_path(original)?.header()
+            };
+            let err = match WavWriterBuilder::new(header)
+                .raw_fmt_chunk(raw_fmt_chunk)
+                .build_to_path(path)
+            {
+                Err(err) => err,
+                Ok(_) => panic!("The fmt chunk is for a 24-bit wav"),
+            };
+            assert_eq!(ErrorKind::InvalidInput, err.kind());
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn read_wav_at_offset_embedded() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("embedded.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Int16,
+            channels: Channels::new().front_left().front_right(),
+            sample_rate: 44100,
+        };
+
+        let open_wav = write_wav_to_file_path(&path, header).unwrap();
+        open_wav
+            .write_all_i16((0..100i16).map(|sample| {
+                Ok(SamplesByChannel::new()
+                    .front_left(sample)
+                    .front_right(-sample))
+            }))
+            .unwrap();
+
+        // The wav is preceded by junk, and followed by more junk
+        let mut bundle = vec![0xAAu8; 1000];
+        bundle.extend(std::fs::read(&path).unwrap());
+        bundle.extend([0x55u8; 100]);
+
+        let open_wav = read_wav_at_offset(Cursor::new(bundle.clone()), 1000).unwrap();
+        assert_eq!(100, open_wav.len_samples());
+        let mut reader = open_wav.get_random_access_i16_reader().unwrap();
+        for sample in [0usize, 1, 50, 99] {
+            assert_eq!(
+                SamplesByChannel::new()
+                    .front_left(sample as i16)
+                    .front_right(-(sample as i16)),
+                reader.read_sample(sample).unwrap()
+            );
+        }
+
+        let open_wav = read_wav_at_offset(Cursor::new(bundle), 1000).unwrap();
+        let samples: Vec<i16> = open_wav
+            .get_stream_i16_reader()
+            .unwrap()
+            .into_iter()
+            .map(|samples| samples.unwrap().front_left.unwrap())
+            .collect();
+        assert_eq!((0..100).collect::<Vec<i16>>(), samples);
+    }
+
+    #[test]
+    fn large_chunk_is_not_buffered() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Int16,
+                channels: Channels::new().front_left(),
+                sample_rate: 44100,
+            };
+
+            let large_chunk: Vec<u8> = (0..(wave_reader::MAX_BUFFERED_CHUNK_SIZE + 1))
+                .map(|i| i as u8)
+                .collect();
+
+            let mut open_wav = write_wav_to_file_path(path, header)?;
+            open_wav.set_chunk(b"LaRg", large_chunk.clone())?;
+            open_wav.write_all_i16(
+                (0..100).map(|sample| Ok(SamplesByChannel::new().front_left(sample))),
+            )?;
+
+            let open_wav = read_wav_from_file_path(path)?;
+            assert_eq!(None, open_wav.chunk(b"LaRg"));
+            assert_eq!(vec![*b"LaRg"], open_wav.chunk_ids());
+            assert_eq!(large_chunk.len(), open_wav.chunk_infos()[0].size);
+
+            let mut samples = open_wav.get_stream_i16_reader()?.into_iter();
+            assert_eq!(Some(0), samples.next().unwrap()?.front_left);
+
+            let mut open_wav = read_wav_from_file_path(path)?;
+            assert_eq!(Some(large_chunk), open_wav.read_chunk(b"LaRg")?);
+            assert_eq!(None, open_wav.read_chunk(b"LGWV")?);
+
+            let samples: Vec<i16> = open_wav
+                .get_stream_i16_reader()?
+                .into_iter()
+                .map(|samples| samples.unwrap().front_left.unwrap())
+                .collect();
+            assert_eq!((0..100).collect::<Vec<i16>>(), samples);
+
+            Ok(())
+        }))
+    }
+
+    #[test]
+    fn hostile_chunk_size_is_not_allocated() {
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&u32::MAX.to_le_bytes());
+        wav.extend_from_slice(b"WAVE");
+        WavHeader::to_writer(
+            &mut wav,
+            &WavHeader {
+                sample_format: SampleFormat::Int16,
+                channels: Channels::new().front_left(),
+                sample_rate: 44100,
+            },
+        )
+        .unwrap();
+        wav.extend_from_slice(b"HuGe");
+        wav.extend_from_slice(&u32::MAX.to_le_bytes());
+        wav.extend_from_slice(&[0u8; 16]);
+
+        match read_wav(Cursor::new(wav)) {
+            Ok(_) => panic!("The chunk is truncated"),
+            Err(err) => assert_eq!(ErrorKind::UnexpectedEof, err.kind()),
+        }
+    }
+
+    #[test]
+    fn arbitrary_chunk_round_trip() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Int16,
+                channels: Channels::new().front_left(),
+                sample_rate: 44100,
+            };
+
+            let mut open_wav = write_wav_to_file_path(path, header)?;
+
+            let err = open_wav
+                .set_chunk(b"data", vec![0])
+                .expect_err("The data chunk can't be set");
+            assert_eq!(ErrorKind::InvalidInput, err.kind());
+
+            // An odd length requires a pad byte
+            open_wav.set_chunk(b"TeSt", vec![1, 2, 3, 4, 5])?;
+            open_wav.set_display_title("After TeSt")?;
+            open_wav.write_all_i16(
+                (0..100).map(|sample| Ok(SamplesByChannel::new().front_left(sample))),
+            )?;
+
+            let chunks = chunks::list_chunks(&mut File::open(path)?)?;
+            let test_chunk = chunks
+                .iter()
+                .position(|chunk| &chunk.id == b"TeSt")
+                .unwrap();
+            assert_eq!(5, chunks[test_chunk].size);
+            assert_eq!(
+                chunks[test_chunk].offset + 8 + 6,
+                chunks[test_chunk + 1].offset
+            );
+
+            let open_wav = read_wav_from_file_path(path)?;
+            assert_eq!(Some(&[1u8, 2, 3, 4, 5][..]), open_wav.chunk(b"TeSt"));
+            assert_eq!(None, open_wav.chunk(b"LGWV"));
+            assert_eq!(Some("After TeSt"), open_wav.display_title());
+            assert!(open_wav.chunk(b"DISP").is_some());
+
+            let samples: Vec<i16> = open_wav
+                .get_stream_i16_reader()?
+                .into_iter()
+                .map(|samples| samples.unwrap().front_left.unwrap())
+                .collect();
+            assert_eq!((0..100).collect::<Vec<i16>>(), samples);
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn write_order_7_1() {
+        test_with_file(Box::new(|path| {
+            let channels = Channels::new()
+                .front_left()
+                .front_right()
+                .front_center()
+                .low_frequency()
+                .back_left()
+                .back_right()
+                .side_left()
+                .side_right();
+
+            // https://learn.microsoft.com/en-us/windows/win32/api/mmreg/ns-mmreg-waveformatextensible
+            let microsoft_order = vec![
+                ChannelPosition::FrontLeft,
+                ChannelPosition::FrontRight,
+                ChannelPosition::FrontCenter,
+                ChannelPosition::LowFrequency,
+                ChannelPosition::BackLeft,
+                ChannelPosition::BackRight,
+                ChannelPosition::SideLeft,
+                ChannelPosition::SideRight,
+            ];
+            assert_eq!(microsoft_order, channels.canonical_order());
+            assert_eq!(0x63F, channels.channel_mask());
+
+            let header = WavHeader {
+                sample_format: SampleFormat::Int16,
+                channels,
+                sample_rate: 48000,
+            };
+
+            // Each channel's sample is its index in Microsoft's order
+            let mut samples = SamplesByChannel::new();
+            for (index, position) in microsoft_order.iter().enumerate() {
+                samples.set(*position, index as i16);
+            }
+
+            let open_wav = write_wav_to_file_path(path, header)?;
+            open_wav.write_all_i16(std::iter::once(Ok(samples)))?;
+
+            let frame = read_wav_from_file_path(path)?
+                .into_raw_frame_iter()
+                .next()
+                .unwrap()?;
+
+            let expected: Vec<u8> = (0..8i16).flat_map(|index| index.to_le_bytes()).collect();
+            assert_eq!(expected, frame);
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn read_i32_full_scale() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Int16,
+                channels: Channels::new().front_left(),
+                sample_rate: 44100,
+            };
+
+            let values = [i16::MAX, i16::MIN, 0, -1, 1000, -1000];
+
+            let open_wav = write_wav_to_file_path(path, header)?;
+            open_wav.write_all_i16(
+                values
+                    .into_iter()
+                    .map(|value| Ok(SamplesByChannel::new().front_left(value))),
+            )?;
+
+            let samples: Vec<i32> = read_wav_from_file_path(path)?
+                .get_stream_i32_reader()?
+                .into_iter()
+                .map(|samples| samples.unwrap().front_left.unwrap())
+                .collect();
+
+            assert!(i32::MAX - samples[0] < 1 << 16, "{}", samples[0]);
+            assert_eq!(i32::MIN, samples[1]);
+
+            // The original value is in the top 16 bits
+            for (value, sample) in values.iter().zip(samples.iter()) {
+                assert_eq!(*value, (sample >> 16) as i16);
+            }
+
+            let mut reader = read_wav_from_file_path(path)?.get_random_access_i32_reader()?;
+            assert_eq!(Some(samples[4]), reader.read_sample(4)?.front_left);
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn read_i32_from_float_unsupported() {
+        let open_wav = read_wav_from_file_path(Path::new("test_data/short_float.wav")).unwrap();
+        assert!(open_wav.get_stream_i32_reader().is_err());
+    }
+
+    #[test_case("test_data/short_8.wav" ; "8-bit")]
+    #[test_case("test_data/short_16.wav" ; "16-bit")]
+    #[test_case("test_data/short_24.wav" ; "24-bit")]
+    #[test_case("test_data/short_float.wav" ; "float")]
+    fn typed_stream_reader_matches_dynamic(path: &str) {
+        let dynamic: Vec<SamplesByChannel<f32>> = read_wav_from_file_path(Path::new(path))
+            .unwrap()
+            .get_stream_f32_reader()
+            .unwrap()
+            .into_iter()
+            .map(|samples| samples.unwrap())
+            .collect();
+
+        let typed: Vec<SamplesByChannel<f32>> = read_wav_from_file_path(Path::new(path))
+            .unwrap()
+            .get_typed_stream_reader::<f32>()
+            .unwrap()
+            .map(|samples| samples.unwrap())
+            .collect();
+
+        assert!(!typed.is_empty());
+        assert_eq!(dynamic, typed);
+    }
+
+    // Counts how many times the underlying writer is flushed
+    struct FlushCounter {
+        inner: Cursor<Vec<u8>>,
+        flushes: Rc<Cell<usize>>,
+    }
+
+    impl Write for FlushCounter {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            self.inner.write(buf)
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            self.flushes.set(self.flushes.get() + 1);
+            self.inner.flush()
+        }
+    }
+
+    impl Seek for FlushCounter {
+        fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    // Fails all writes after the flag is set
+    struct FailingWriter {
+        inner: Cursor<Vec<u8>>,
+        fail: Rc<Cell<bool>>,
+    }
+
+    impl Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            if self.fail.get() {
+                return Err(Error::other("Disk full"));
+            }
+
+            self.inner.write(buf)
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            self.inner.flush()
+        }
+    }
+
+    impl Seek for FailingWriter {
+        fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn abandon_after_write_error() {
+        let header = WavHeader {
+            sample_format: SampleFormat::Int16,
+            channels: Channels::new().front_left(),
+            sample_rate: 48000,
+        };
+
+        let fail = Rc::new(Cell::new(false));
+        let mut open_wav = write_wav(
+            FailingWriter {
+                inner: Cursor::new(Vec::new()),
+                fail: fail.clone(),
+            },
+            header,
+        )
+        .unwrap();
+
+        fail.set(true);
+        let err = open_wav
+            .write_raw_frames(&[0, 0, 1, 0])
+            .expect_err("Writing must fail");
+        assert_eq!("Disk full", err.to_string());
+
+        // Dropping would flush, which would fail and panic
+        open_wav.abandon();
+    }
+
+    #[test]
+    fn write_all_f32_flush_every() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Float,
+                channels: Channels::new().front_left().front_right(),
+                sample_rate: 48000,
+            };
+
+            let samples_itr = || {
+                (0..1000).map(|sample| {
+                    Ok(SamplesByChannel::new()
+                        .front_left(sample as f32 / 1000.0)
+                        .front_right(-(sample as f32) / 1000.0))
+                })
+            };
+
+            let open_wav = write_wav_to_file_path(path, header)?;
+            open_wav.write_all_f32_flush_every(samples_itr(), 7)?;
+
+            let samples: Vec<SamplesByChannel<f32>> = read_wav_from_file_path(path)?
+                .get_stream_f32_reader()?
+                .into_iter()
+                .collect::<Result<_>>()?;
+            assert_eq!(samples_itr().collect::<Result<Vec<_>>>()?, samples);
+
+            // Flushed every 100 samples, and once more when the wav is finished
+            let flushes = Rc::new(Cell::new(0));
+            let open_wav = write_wav(
+                FlushCounter {
+                    inner: Cursor::new(Vec::new()),
+                    flushes: flushes.clone(),
+                },
+                header,
+            )?;
+            open_wav.write_all_f32_flush_every(samples_itr(), 100)?;
+            assert_eq!(11, flushes.get());
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn load_planar_f32() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Int16,
+                channels: Channels::new().front_left().front_right().low_frequency(),
+                sample_rate: 48000,
+            };
+
+            let open_wav = write_wav_to_file_path(path, header)?;
+            open_wav.write_all_i16((0..500).map(|sample| {
+                Ok(SamplesByChannel::new()
+                    .front_left(sample * 10)
+                    .front_right(-sample)
+                    .low_frequency(7))
+            }))?;
+
+            let (planar_header, channels) = load_wav_planar_f32(path)?;
+            assert_eq!(header, planar_header);
+            assert_eq!(3, channels.len());
+
+            let open_wav = read_wav_from_file_path(path)?;
+            let len_samples = open_wav.len_samples();
+            assert!(channels.iter().all(|channel| channel.len() == len_samples));
+
+            let mut reader = open_wav.get_random_access_f32_reader()?;
+            for (sample, front_left) in channels[0].iter().enumerate() {
+                assert_eq!(reader.read_sample(sample)?.front_left.unwrap(), *front_left);
+            }
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn read_legacy_14_byte_fmt() {
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.write_u32(4 + 22 + 8 + 4).unwrap();
+        wav.extend_from_slice(b"WAVE");
+
+        // WAVEFORMAT, without wBitsPerSample
+        wav.extend_from_slice(b"fmt ");
+        wav.write_u32(14).unwrap();
+        wav.write_u16(1).unwrap();
+        wav.write_u16(1).unwrap();
+        wav.write_u32(11025).unwrap();
+        wav.write_u32(11025).unwrap();
+        wav.write_u16(1).unwrap();
+
+        wav.extend_from_slice(b"data");
+        wav.write_u32(4).unwrap();
+        wav.extend_from_slice(&[0x80, 0xFF, 0x00, 0xC0]);
+
+        let open_wav = read_wav(Cursor::new(wav)).unwrap();
+        assert_eq!(SampleFormat::Int8, open_wav.sample_format());
+        assert_eq!(Channels::new().front_left(), *open_wav.channels());
+        assert_eq!(11025, open_wav.sample_rate());
+        assert_eq!(4, open_wav.len_samples());
+
+        let samples: Vec<u8> = open_wav
+            .get_stream_u8_reader()
+            .unwrap()
+            .into_iter()
+            .map(|samples| samples.unwrap().front_left.unwrap())
+            .collect();
+        assert_eq!(vec![0x80, 0xFF, 0x00, 0xC0], samples);
+    }
+
+    #[test_case(1, 8192, ErrorKind::Unsupported ; "large block align")]
+    #[test_case(1, 0, ErrorKind::InvalidData ; "zero block align")]
+    #[test_case(2, 1, ErrorKind::InvalidData ; "block align smaller than channels")]
+    #[test_case(2, 3, ErrorKind::InvalidData ; "block align not a multiple of channels")]
+    fn read_legacy_14_byte_fmt_invalid_block_align(
+        num_channels: u16,
+        block_align: u16,
+        expected_kind: ErrorKind,
+    ) {
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.write_u32(4 + 22 + 8).unwrap();
+        wav.extend_from_slice(b"WAVE");
+
+        wav.extend_from_slice(b"fmt ");
+        wav.write_u32(14).unwrap();
+        wav.write_u16(1).unwrap();
+        wav.write_u16(num_channels).unwrap();
+        wav.write_u32(11025).unwrap();
+        wav.write_u32(11025 * block_align as u32).unwrap();
+        wav.write_u16(block_align).unwrap();
+
+        wav.extend_from_slice(b"data");
+        wav.write_u32(0).unwrap();
+
+        match read_wav(Cursor::new(wav)) {
+            Ok(_) => panic!("The block align is invalid"),
+            Err(err) => assert_eq!(expected_kind, err.kind()),
+        }
+    }
+
+    #[test]
+    fn read_15_byte_fmt() {
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.write_u32(4 + 24 + 8 + 4).unwrap();
+        wav.extend_from_slice(b"WAVE");
+
+        // Part of wBitsPerSample, and a pad byte
+        wav.extend_from_slice(b"fmt ");
+        wav.write_u32(15).unwrap();
+        wav.write_u16(1).unwrap();
+        wav.write_u16(1).unwrap();
+        wav.write_u32(11025).unwrap();
+        wav.write_u32(11025).unwrap();
+        wav.write_u16(1).unwrap();
+        wav.extend_from_slice(&[8, 0]);
+
+        wav.extend_from_slice(b"data");
+        wav.write_u32(4).unwrap();
+        wav.extend_from_slice(&[0x80, 0xFF, 0x00, 0xC0]);
+
+        match read_wav(Cursor::new(wav)) {
+            Ok(_) => panic!("A 15-byte fmt chunk is invalid"),
+            Err(err) => assert_eq!(ErrorKind::InvalidData, err.kind()),
+        }
+    }
+
+    #[test]
+    fn read_stream_with_offsets() {
+        let path = Path::new("test_data/short_16.wav");
+        let bytes = std::fs::read(path).unwrap();
+        let data_chunk = chunks::list_chunks(&mut File::open(path).unwrap())
+            .unwrap()
+            .into_iter()
+            .find(|chunk| &chunk.id == b"data")
+            .unwrap();
+        let data_start = data_chunk.offset + 8;
+
+        let reader = read_wav_from_file_path(path)
+            .unwrap()
+            .get_stream_i16_reader()
+            .unwrap();
+        let mut samples_read = 0usize;
+        for (sample, samples_result) in reader.into_iter_with_offsets().enumerate() {
+            let (offset, samples) = samples_result.unwrap();
+            assert_eq!(data_start + sample * 2, offset);
+
+            let sample_bytes = [bytes[offset], bytes[offset + 1]];
+            assert_eq!(
+                i16::from_le_bytes(sample_bytes),
+                samples.front_left.unwrap()
+            );
+
+            samples_read += 1;
+        }
+
+        assert_eq!(data_chunk.size / 2, samples_read);
+    }
+
+    #[test]
+    fn read_raw_stream_f32() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Int24,
+                channels: Channels::new()
+                    .front_left()
+                    .front_right()
+                    .front_center()
+                    .low_frequency()
+                    .back_left()
+                    .back_right(),
+                sample_rate: 48000,
+            };
+
+            let open_wav = write_wav_to_file_path(path, header)?;
+            open_wav.write_all_i24((0..1000).map(|sample| {
+                Ok(SamplesByChannel::new()
+                    .front_left(sample * 100)
+                    .front_right(-sample * 100)
+                    .front_center(1)
+                    .low_frequency(2)
+                    .back_left(3)
+                    .back_right(4))
+            }))?;
+
+            let mut random_access_reader =
+                read_wav_from_file_path(path)?.get_random_access_f32_reader()?;
+
+            let mut frames = 0usize;
+            for (sample, frame_result) in read_wav_from_file_path(path)?
+                .get_raw_stream_f32()?
+                .enumerate()
+            {
+                let frame = frame_result?;
+                let samples = random_access_reader.read_sample(sample)?;
+                assert_eq!(samples.front_left.unwrap(), frame[0]);
+                assert_eq!(samples.to_vec(), frame);
+                frames += 1;
+            }
+
+            assert_eq!(1000, frames);
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn read_raw_20_channels() {
+        let value = |sample: usize, channel: usize| (sample * 100 + channel) as f32 / 10000.0;
+
+        let mut data = Vec::new();
+        for sample in 0..3 {
+            for channel in 0..20 {
+                data.write_f32(value(sample, channel)).unwrap();
+            }
+        }
+
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.write_u32(0).unwrap();
+        wav.extend_from_slice(b"WAVE");
+        wav.extend_from_slice(b"fmt ");
+        wav.write_u32(16).unwrap();
+        wav.write_u16(3).unwrap();
+        wav.write_u16(20).unwrap();
+        wav.write_u32(48000).unwrap();
+        wav.write_u32(48000 * 80).unwrap();
+        wav.write_u16(80).unwrap();
+        wav.write_u16(32).unwrap();
+        // An odd length requires a pad byte
+        wav.extend_from_slice(b"TeSt");
+        wav.write_u32(3).unwrap();
+        wav.extend_from_slice(&[1, 2, 3, 0]);
+        wav.extend_from_slice(b"data");
+        wav.write_u32(data.len() as u32).unwrap();
+        wav.extend_from_slice(&data);
+
+        match read_wav(Cursor::new(wav.clone())) {
+            Ok(_) => panic!("Channels can't represent 20 channels"),
+            Err(err) => assert_eq!(ErrorKind::Unsupported, err.kind()),
+        }
+
+        let raw_reader = read_wav_raw_f32(Cursor::new(wav)).unwrap();
+        assert_eq!(20, raw_reader.num_channels());
+        assert_eq!(None, raw_reader.channels());
+        assert_eq!(SampleFormat::Float, raw_reader.sample_format());
+        assert_eq!(48000, raw_reader.sample_rate());
+        assert_eq!(3, raw_reader.len_samples());
+
+        let frames: Vec<Vec<f32>> = raw_reader.collect::<Result<_>>().unwrap();
+        assert_eq!(3, frames.len());
+        for (sample, frame) in frames.iter().enumerate() {
+            let expected: Vec<f32> = (0..20).map(|channel| value(sample, channel)).collect();
+            assert_eq!(&expected, frame);
+        }
+    }
+
+    #[test_case("test_data/short_8.wav" ; "8-bit")]
+    #[test_case("test_data/short_16.wav" ; "16-bit")]
+    #[test_case("test_data/short_24.wav" ; "24-bit")]
+    #[test_case("test_data/short_float.wav" ; "float")]
+    fn verify_decodable_good(path: &str) {
+        verify_decodable(Path::new(path)).unwrap();
+    }
+
+    #[test_case("test_data/short_8.wav", SampleFormat::Int8, &[] ; "8-bit")]
+    #[test_case("test_data/short_16.wav", SampleFormat::Int16, &[] ; "16-bit")]
+    #[test_case("test_data/short_24.wav", SampleFormat::Int24, &[] ; "24-bit")]
+    #[test_case("test_data/short_float.wav", SampleFormat::Float, &[*b"fact", *b"PEAK"] ; "float")]
+    fn probe_fixtures(path: &str, sample_format: SampleFormat, extra_chunk_ids: &[[u8; 4]]) {
+        let info = probe(Path::new(path)).unwrap();
+        let open_wav = read_wav_from_file_path(Path::new(path)).unwrap();
+
+        assert_eq!(sample_format, info.header.sample_format);
+        assert_eq!(*open_wav.header(), info.header);
+        assert_eq!(open_wav.len_samples(), info.len_samples);
+        assert_eq!(extra_chunk_ids, info.extra_chunk_ids.as_slice());
+
+        let expected_duration = open_wav.len_samples() as f64 / open_wav.sample_rate() as f64;
+        assert!((info.duration.as_secs_f64() - expected_duration).abs() < 0.000001);
+    }
+
+    #[test]
+    fn verify_decodable_corrupted() {
+        test_with_file(Box::new(|path| {
+            let wav = std::fs::read("test_data/short_16.wav")?;
+
+            // Truncated in the middle of the samples
+            std::fs::write(path, &wav[..1000])?;
+            let err = verify_decodable(path).expect_err("The wav is truncated");
+            assert_eq!(ErrorKind::UnexpectedEof, err.kind());
+
+            // The data chunk ends with part of a sample
+            let header = WavHeader {
+                sample_format: SampleFormat::Int16,
+                channels: Channels::new().front_left(),
+                sample_rate: 48000,
+            };
+            let mut partial_sample = Cursor::new(Vec::new());
+            partial_sample.write_all(b"RIFF    WAVE")?;
+            WavHeader::to_writer(&mut partial_sample, &header)?;
+            partial_sample.write_all(b"data")?;
+            partial_sample.write_u32(201)?;
+            partial_sample.write_all(&[1u8; 201])?;
+            std::fs::write(path, partial_sample.into_inner())?;
+            let err = verify_decodable(path).expect_err("The last sample is incomplete");
+            assert_eq!(ErrorKind::InvalidData, err.kind());
+
+            // The fmt chunk is replaced with another chunk
+            let mut not_fmt = wav.clone();
+            not_fmt[12..16].copy_from_slice(b"junk");
+            std::fs::write(path, &not_fmt)?;
+            assert!(verify_decodable(path).is_err());
+
+            // The unmodified wav still decodes
+            std::fs::write(path, &wav)?;
+            verify_decodable(path)
+        }));
+    }
+
+    #[test]
+    fn header_round_trip() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Int24,
+                channels: Channels::new().front_left().front_right().low_frequency(),
+                sample_rate: 96000,
+            };
+
+            let open_wav = write_wav(BufWriter::new(File::create(path)?), header)?;
+            assert_eq!(&header, open_wav.header());
+            drop(open_wav);
+
+            assert_eq!(&header, read_wav_from_file_path(path)?.header());
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn layout_name_5_1() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Int16,
+                channels: Channels::new()
+                    .front_left()
+                    .front_right()
+                    .front_center()
+                    .low_frequency()
+                    .back_left()
+                    .back_right(),
+                sample_rate: 48000,
+            };
+
+            write_wav_to_file_path(path, header)?.flush()?;
+
+            let open_wav = read_wav_from_file_path(path)?;
+            assert_eq!(6, open_wav.num_channels());
+            assert_eq!(Some("5.1"), open_wav.layout_name());
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn write_all_per_channel_sines() {
+        fn sine_channel(freq: f32, len: usize) -> Box<dyn Iterator<Item = Result<f32>>> {
+            Box::new(
+                generators::sine(freq, 0.5, 48000)
+                    .take(len)
+                    .map(|samples_result| Ok(samples_result?.front_left.unwrap())),
+            )
+        }
+
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Float,
+                channels: Channels::new().front_left().front_right(),
+                sample_rate: 48000,
+            };
+
+            // The right channel is shorter, so it determines the length
+            let open_wav = write_wav_to_file_path(path, header)?;
+            open_wav.write_all_per_channel_f32(
+                SamplesByChannel::new()
+                    .front_left(sine_channel(440.0, 1000))
+                    .front_right(sine_channel(660.0, 800)),
+            )?;
+
+            let open_wav = read_wav_from_file_path(path)?;
+            assert_eq!(800, open_wav.len_samples());
+
+            let left = sine_channel(440.0, 800);
+            let right = sine_channel(660.0, 800);
+            let samples = open_wav.get_stream_f32_reader()?.into_iter();
+            for ((samples_result, left), right) in samples.zip(left).zip(right) {
+                let samples = samples_result?;
+                assert_eq!(Some(left?), samples.front_left);
+                assert_eq!(Some(right?), samples.front_right);
+            }
+
+            // Every channel needs an iterator
+            let open_wav = write_wav_to_file_path(path, header)?;
+            let err = open_wav
+                .write_all_per_channel_f32(
+                    SamplesByChannel::new().front_left(sine_channel(440.0, 1000)),
+                )
+                .expect_err("The right channel has no iterator");
+            assert_eq!(ErrorKind::InvalidInput, err.kind());
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn write_tracked_peaks() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Float,
+                channels: Channels::new().front_left().front_right(),
+                sample_rate: 48000,
+            };
+
+            let mut open_wav = write_wav_to_file_path(path, header)?;
+            open_wav.track_peaks()?;
+            open_wav.write_all_f32(generators::sine(440.0, 0.5, 48000).take(1000).map(
+                |samples_result| {
+                    let left = samples_result?.front_left.unwrap();
+                    Ok(SamplesByChannel::new()
+                        .front_left(left)
+                        .front_right(-left * 0.25))
+                },
+            ))?;
+
+            // Scan the samples separately
+            let mut expected = vec![
+                ChannelPeak {
+                    value: 0.0,
+                    position: 0
+                };
+                2
+            ];
+            let open_wav = read_wav_from_file_path(path)?;
+            let peak_chunk = open_wav.peak_chunk().expect("PEAK chunk missing").clone();
+            for (sample, samples_result) in
+                open_wav.get_stream_f32_reader()?.into_iter().enumerate()
+            {
+                for (channel_index, value) in samples_result?.to_vec().into_iter().enumerate() {
+                    if value.abs() > expected[channel_index].value {
+                        expected[channel_index] = ChannelPeak {
+                            value: value.abs(),
+                            position: sample as u32,
+                        };
+                    }
+                }
+            }
+
+            assert_eq!(1, peak_chunk.version);
+            assert_eq!(expected, peak_chunk.peaks);
+            assert!(peak_chunk.peaks[0].value > 0.49);
+
+            let err = write_wav_to_file_path(
+                path,
+                WavHeader {
+                    sample_format: SampleFormat::Int16,
+                    ..header
+                },
+            )?
+            .track_peaks()
+            .expect_err("Only floats can track peaks");
+            assert_eq!(ErrorKind::InvalidInput, err.kind());
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn transcode_8_to_16() {
+        test_with_file(Box::new(|path| {
+            transcode(
+                Path::new("test_data/short_8.wav"),
+                path,
+                SampleFormat::Int16,
+            )?;
+
+            let original = read_wav_from_file_path(Path::new("test_data/short_8.wav"))?;
+            let transcoded = read_wav_from_file_path(path)?;
+            assert_eq!(SampleFormat::Int16, transcoded.sample_format());
+            assert_eq!(original.channels(), transcoded.channels());
+            assert_eq!(original.sample_rate(), transcoded.sample_rate());
+            assert_eq!(original.len_samples(), transcoded.len_samples());
+
+            let expected: Vec<SamplesByChannel<i16>> = original
+                .get_stream_i16_reader()?
+                .into_iter()
+                .collect::<Result<_>>()?;
+            let actual: Vec<SamplesByChannel<i16>> = transcoded
+                .get_stream_i16_reader()?
+                .into_iter()
+                .collect::<Result<_>>()?;
+            assert_eq!(expected, actual);
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn transcode_float_to_16_is_lossy() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("transcoded.wav");
+
+        let err = transcode(
+            Path::new("test_data/short_float.wav"),
+            &path,
+            SampleFormat::Int16,
+        )
+        .expect_err("Converting float to 16-bit loses precision");
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+        assert!(
+            !path.exists(),
+            "Nothing is written when the conversion fails"
+        );
+    }
+
+    #[test]
+    fn write_all_stereo_f32() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Float,
+                channels: Channels::new().front_left().front_right(),
+                sample_rate: 48000,
+            };
+
+            let open_wav = write_wav_to_file_path(path, header)?;
+            open_wav.write_all_stereo_f32(
+                (0..100).map(|sample| Ok([sample as f32 / 100.0, -(sample as f32) / 100.0])),
+            )?;
+
+            let open_wav = read_wav_from_file_path(path)?;
+            assert_eq!(100, open_wav.len_samples());
+
+            let mut reader = open_wav.get_random_access_f32_reader()?;
+            for sample in 0..100usize {
+                assert_eq!(
+                    SamplesByChannel::new()
+                        .front_left(sample as f32 / 100.0)
+                        .front_right(-(sample as f32) / 100.0),
+                    reader.read_sample(sample)?
+                );
+            }
+
+            // The wav isn't mono
+            let open_wav = write_wav_to_file_path(path, header)?;
+            let err = open_wav
+                .write_all_mono_f32(std::iter::once(Ok(0.0)))
+                .expect_err("The wav is stereo");
+            assert_eq!(ErrorKind::InvalidInput, err.kind());
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn write_all_mono_f32() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Float,
+                channels: Channels::new().front_left(),
+                sample_rate: 48000,
+            };
+
+            let open_wav = write_wav_to_file_path(path, header)?;
+            open_wav.write_all_mono_f32([0.0, 1.0, -1.0].into_iter().map(Ok))?;
+
+            let samples: Vec<f32> = read_wav_from_file_path(path)?
+                .get_stream_f32_reader()?
+                .into_iter()
+                .map(|samples| samples.unwrap().front_left.unwrap())
+                .collect();
+            assert_eq!(vec![0.0, 1.0, -1.0], samples);
+
+            Ok(())
+        }));
+    }
+
+    #[test_case("test_data/short_8.wav", RoundingMode::FullScale ; "8-bit full scale")]
+    #[test_case("test_data/short_8.wav", RoundingMode::Shift ; "8-bit shift")]
+    #[test_case("test_data/short_16.wav", RoundingMode::FullScale ; "16-bit full scale")]
+    #[test_case("test_data/short_16.wav", RoundingMode::Shift ; "16-bit shift")]
+    #[test_case("test_data/short_24.wav", RoundingMode::FullScale ; "24-bit")]
+    fn typed_stream_reader_matches_dynamic_i24(path: &str, rounding_mode: RoundingMode) {
+        let mut open_wav = read_wav_from_file_path(Path::new(path)).unwrap();
+        open_wav.set_rounding_mode(rounding_mode);
+        let dynamic: Vec<SamplesByChannel<i32>> = open_wav
+            .get_stream_i24_reader()
+            .unwrap()
+            .into_iter()
+            .map(|samples| samples.unwrap())
+            .collect();
+
+        let mut open_wav = read_wav_from_file_path(Path::new(path)).unwrap();
+        open_wav.set_rounding_mode(rounding_mode);
+        let typed: Vec<SamplesByChannel<i32>> = open_wav
+            .get_typed_stream_reader::<i32>()
+            .unwrap()
+            .map(|samples| samples.unwrap())
+            .collect();
+
+        assert!(!typed.is_empty());
+        assert_eq!(dynamic, typed);
+    }
+
+    #[test]
+    fn read_samples_across_buffer_boundaries() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Int24,
+                channels: Channels::new().front_left().front_right(),
+                sample_rate: 48000,
+            };
+
+            // 6-byte samples don't line up with the reader's buffer
+            let len_samples = 10000;
+            let open_wav = write_wav_to_file_path(path, header)?;
+            open_wav.write_all_i24((0..len_samples).map(|sample| {
+                Ok(SamplesByChannel::new()
+                    .front_left(sample)
+                    .front_right(-sample))
+            }))?;
+
+            let mut count = 0;
+            for (sample, samples) in read_wav_from_file_path(path)?
+                .get_stream_i24_reader()?
+                .into_iter()
+                .enumerate()
+            {
+                let samples = samples?;
+                assert_eq!(Some(sample as i32), samples.front_left);
+                assert_eq!(Some(-(sample as i32)), samples.front_right);
+                count += 1;
+            }
+
+            assert_eq!(len_samples as usize, count);
+
+            Ok(())
+        }));
+    }
+
+    // Run with: cargo test --release typed_stream_reader_timing -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn typed_stream_reader_timing() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Int24,
+                channels: Channels::new().front_left().front_right(),
+                sample_rate: 48000,
+            };
+
+            // 5 minutes
+            let len_samples = 48000 * 60 * 5;
+            let open_wav = write_wav_to_file_path(path, header)?;
+            open_wav.write_all_i24((0..len_samples).map(|sample| {
+                let value = (sample % MAX_INT_24 as usize) as i32;
+                Ok(SamplesByChannel::new()
+                    .front_left(value)
+                    .front_right(-value))
+            }))?;
+
+            let start = std::time::Instant::now();
+            let mut dynamic_sum = 0.0f64;
+            for samples in read_wav_from_file_path(path)?
+                .get_stream_f32_reader()?
+                .into_iter()
+            {
+                dynamic_sum += samples?.front_left.unwrap() as f64;
+            }
+            let dynamic_elapsed = start.elapsed();
+
+            let start = std::time::Instant::now();
+            let mut typed_sum = 0.0f64;
+            for samples in read_wav_from_file_path(path)?.get_typed_stream_reader::<f32>()? {
+                typed_sum += samples?.front_left.unwrap() as f64;
+            }
+            let typed_elapsed = start.elapsed();
+
+            assert_eq!(dynamic_sum, typed_sum);
+            println!("StreamWavReader: {dynamic_elapsed:?}, TypedStreamReader: {typed_elapsed:?}");
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn push_interleaved() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Float,
+                channels: Channels::new().front_left().front_right(),
+                sample_rate: 48000,
+            };
+
+            let open_wav = write_wav_to_file_path(path, header)?;
+            let mut writer = open_wav.get_random_access_f32_writer()?;
+
+            // Buffers arrive in uneven sizes
+            let interleaved: Vec<f32> = (0..40).map(|value| value as f32 / 40.0).collect();
+            let mut pushed = 0;
+            for buf_len in [6, 14, 2, 0, 18] {
+                let samples = writer.push_interleaved(&interleaved[pushed..pushed + buf_len])?;
+                assert_eq!(buf_len / 2, samples);
+                pushed += buf_len;
+            }
+
+            let err = writer
+                .push_interleaved(&[0.0; 3])
+                .expect_err("Not a whole number of samples");
+            assert_eq!(ErrorKind::InvalidInput, err.kind());
+
+            writer.flush()?;
+
+            let open_wav = read_wav_from_file_path(path)?;
+            assert_eq!(20, open_wav.len_samples());
+
+            let mut reader = open_wav.get_random_access_f32_reader()?;
+            for sample in 0..20usize {
+                assert_eq!(
+                    SamplesByChannel::new()
+                        .front_left(interleaved[sample * 2])
+                        .front_right(interleaved[sample * 2 + 1]),
+                    reader.read_sample(sample)?,
+                    "Wrong sample read at {sample}"
+                );
+            }
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn write_raw_frames_lenient_pads() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Int16,
+                channels: Channels::new().front_left().front_right(),
+                sample_rate: 48000,
+            };
+
+            let mut open_wav = write_wav_to_file_path(path, header)?;
+
+            // 2 whole samples, and half of a sample
+            let bytes = [1u8, 0, 2, 0, 3, 0, 4, 0, 5, 0];
+            let err = open_wav
+                .write_raw_frames(&bytes)
+                .expect_err("Not a whole number of samples");
+            assert_eq!(ErrorKind::InvalidInput, err.kind());
+
+            let padding = open_wav.write_raw_frames_lenient(&bytes)?;
+            assert_eq!(2, padding);
+            assert_eq!(3, open_wav.len_samples());
+            open_wav.flush()?;
+
+            let open_wav = read_wav_from_file_path(path)?;
+            assert_eq!(3, open_wav.len_samples());
+
+            let mut reader = open_wav.get_random_access_i16_reader()?;
+            assert_eq!(
+                SamplesByChannel::new().front_left(3).front_right(4),
+                reader.read_sample(1)?
+            );
+            assert_eq!(
+                SamplesByChannel::new().front_left(5).front_right(0),
+                reader.read_sample(2)?
+            );
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn write_big_endian() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Float,
+                channels: Channels::new().front_left().front_right(),
+                sample_rate: 48000,
+            };
+
+            let samples = [0.5f32, -0.25, 1.0, -1.0];
+
+            let mut open_wav = write_wav_to_file_path(path, header)?;
+            open_wav.set_byte_order(ByteOrder::Big)?;
+            open_wav.write_all_f32(samples.chunks(2).map(|sample| {
+                Ok(SamplesByChannel::new()
+                    .front_left(sample[0])
+                    .front_right(sample[1]))
+            }))?;
+
+            // The samples are at the end of the file
+            let bytes = std::fs::read(path)?;
+            let mut data = &bytes[bytes.len() - samples.len() * 4..];
+            assert_eq!(&0.5f32.to_be_bytes(), &data[0..4]);
+            assert_eq!(&(-0.25f32).to_be_bytes(), &data[4..8]);
+
+            for expected in samples {
+                assert_eq!(expected, data.read_f32_be()?);
+            }
+
+            // The matching reader swaps the samples back
+            let mut open_wav = read_wav_from_file_path(path)?;
+            open_wav.set_byte_order(ByteOrder::Big)?;
+            let read_samples = open_wav
+                .get_stream_f32_reader()?
+                .into_iter()
+                .collect::<Result<Vec<_>>>()?;
+            assert_eq!(2, read_samples.len());
+            for (sample, read_sample) in samples.chunks(2).zip(read_samples) {
+                assert_eq!(Some(sample[0]), read_sample.front_left);
+                assert_eq!(Some(sample[1]), read_sample.front_right);
+            }
+
+            let mut open_wav = read_wav_from_file_path(path)?;
+            open_wav.set_byte_order(ByteOrder::Big)?;
+            let mut reader = open_wav.get_random_access_f32_reader()?;
+            assert_eq!(Some(1.0), reader.read_sample(1)?.front_left);
+
+            let mut open_wav = read_wav_from_file_path(path)?;
+            open_wav.set_byte_order(ByteOrder::Big)?;
+            let raw_samples = open_wav.get_raw_stream_f32()?.collect::<Result<Vec<_>>>()?;
+            assert_eq!(vec![vec![0.5, -0.25], vec![1.0, -1.0]], raw_samples);
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn write_big_endian_requires_float() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Int16,
+                channels: Channels::new().front_left(),
+                sample_rate: 48000,
+            };
+
+            let mut open_wav = write_wav_to_file_path(path, header)?;
+            let err = open_wav
+                .set_byte_order(ByteOrder::Big)
+                .expect_err("Only floats can be big-endian");
+            assert_eq!(ErrorKind::InvalidInput, err.kind());
+            open_wav.write_all_i16(std::iter::once(Ok(SamplesByChannel::new().front_left(1))))?;
+
+            let mut open_wav = read_wav_from_file_path(path)?;
+            let err = open_wav
+                .set_byte_order(ByteOrder::Big)
+                .expect_err("Only floats can be big-endian");
+            assert_eq!(ErrorKind::InvalidInput, err.kind());
+
+            Ok(())
+        }));
+    }
+
+    fn write_auto<T: WavSample + Debug + PartialEq>(
+        sample_format: SampleFormat,
+        samples: Vec<T>,
+    ) -> Result<()> {
+        let temp_dir = tempdir()?;
+        let path = temp_dir.path().join("auto.wav");
+
+        let file = File::create(&path)?;
+        let samples_itr = samples
+            .clone()
+            .into_iter()
+            .map(|sample| Ok(SamplesByChannel::new().front_left(sample)));
+        write_wav_auto(
+            BufWriter::new(file),
+            Channels::new().front_left(),
+            48000,
+            samples_itr,
+        )?;
+
+        let open_wav = read_wav_from_file_path(&path)?;
+        assert_eq!(sample_format, open_wav.sample_format());
+        assert_eq!(samples.len(), open_wav.len_samples());
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_auto_i8() {
+        write_auto(SampleFormat::Int8, vec![i8::MIN, 0, i8::MAX]).unwrap();
+    }
+
+    #[test]
+    fn write_auto_i16() {
+        write_auto(SampleFormat::Int16, vec![i16::MIN, 0, i16::MAX]).unwrap();
+    }
+
+    #[test]
+    fn write_auto_i24() {
+        write_auto(SampleFormat::Int24, vec![MIN_INT_24, 0, MAX_INT_24]).unwrap();
+    }
+
+    #[test]
+    fn write_auto_f32() {
+        write_auto(SampleFormat::Float, vec![-1.0f32, 0.0, 1.0]).unwrap();
+    }
+
+    #[test]
+    fn write_auto_f64() {
+        write_auto(SampleFormat::Float, vec![-1.0f64, 0.0, 1.0]).unwrap();
+
+        // f64 is written as f32
+        test_with_file(Box::new(|path| {
+            let samples = [-1.0f64, 0.25, 1.0 / 3.0, 1.0];
+            write_wav_auto(
+                BufWriter::new(File::create(path)?),
+                Channels::new().front_left(),
+                48000,
+                samples
+                    .iter()
+                    .map(|sample| Ok(SamplesByChannel::new().front_left(*sample))),
+            )?;
+
+            let read_samples = read_wav_from_file_path(path)?
+                .get_typed_stream_reader::<f64>()?
+                .map(|samples| Ok(samples?.front_left.unwrap()))
+                .collect::<Result<Vec<f64>>>()?;
+            let expected: Vec<f64> = samples.iter().map(|sample| *sample as f32 as f64).collect();
+            assert_eq!(expected, read_samples);
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn read_write_i64_ramp() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Int64,
+                channels: Channels::new().front_left().front_right(),
+                sample_rate: 48000,
+            };
+
+            let ramp: Vec<i64> = (0..=100)
+                .map(|step| (step - 50) * (i64::MAX / 50))
+                .collect();
+
+            let open_wav = write_wav_to_file_path(path, header)?;
+            assert_eq!((u32::MAX as usize - 32 + 8) / 2 / 8, open_wav.max_samples());
+            open_wav.write_all_samples(ramp.iter().map(|sample| {
+                Ok(SamplesByChannel::new()
+                    .front_left(*sample)
+                    .front_right(-*sample))
+            }))?;
+
+            let open_wav = read_wav_from_file_path(path)?;
+            assert_eq!(SampleFormat::Int64, open_wav.sample_format());
+            assert_eq!(64, open_wav.bits_per_sample());
+            assert_eq!(ramp.len(), open_wav.len_samples());
+
+            let samples: Vec<i64> = open_wav
+                .get_stream_reader::<i64>()?
+                .into_iter()
+                .map(|samples| Ok(samples?.front_left.unwrap()))
+                .collect::<Result<_>>()?;
+            assert_eq!(ramp, samples);
+
+            let samples: Vec<f32> = read_wav_from_file_path(path)?
+                .get_stream_f32_reader()?
+                .into_iter()
+                .map(|samples| Ok(samples?.front_left.unwrap()))
+                .collect::<Result<_>>()?;
+            assert_eq!(-1.0, samples[0]);
+            assert_eq!(1.0, samples[100]);
+            for pair in samples.windows(2) {
+                assert!(pair[0] < pair[1], "The ramp must increase");
+            }
+
+            let samples: Vec<f64> = read_wav_from_file_path(path)?
+                .get_stream_reader::<f64>()?
+                .into_iter()
+                .map(|samples| Ok(samples?.front_left.unwrap()))
+                .collect::<Result<_>>()?;
+            assert_eq!(-1.0, samples[0]);
+            assert_eq!(1.0, samples[100]);
+            assert_eq!(0.0, samples[50]);
+            for pair in samples.windows(2) {
+                assert!(pair[0] < pair[1], "The ramp must increase");
+            }
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn write_fixed_size() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Int16,
+                channels: Channels::new().front_left().front_right(),
+                sample_rate: 48000,
+            };
+
+            // The declared size is written before any samples
+            let mut open_wav =
+                write_wav_fixed_size(BufWriter::new(File::create(path)?), header, 100 * 4)?;
+            open_wav.flush()?;
+            let bytes = std::fs::read(path)?;
+            let data_start = bytes.len();
+            assert_eq!(b"data", &bytes[data_start - 8..data_start - 4]);
+            assert_eq!(
+                400,
+                u32::from_le_bytes(bytes[data_start - 4..data_start].try_into().unwrap())
+            );
+
+            // Fill the reserved region, back to front
+            let mut writer = open_wav.get_random_access_i16_writer()?;
+            for sample in (0..100usize).rev() {
+                writer.write_samples(
+                    sample,
+                    SamplesByChannel::new()
+                        .front_left(sample as i16)
+                        .front_right(-(sample as i16)),
+                )?;
+            }
+            writer.flush()?;
+            drop(writer);
+
+            let open_wav = read_wav_from_file_path(path)?;
+            assert_eq!(100, open_wav.len_samples());
+            let samples: Vec<SamplesByChannel<i16>> = open_wav
+                .get_stream_i16_reader()?
+                .into_iter()
+                .collect::<Result<_>>()?;
+            assert_eq!(Some(99), samples[99].front_left);
+            assert_eq!(Some(-99), samples[99].front_right);
+
+            let err = write_wav_fixed_size(BufWriter::new(File::create(path)?), header, 401)
+                .err()
+                .expect("401 bytes isn't a whole number of samples");
+            assert_eq!(ErrorKind::InvalidInput, err.kind());
+
+            let err = write_wav_fixed_size(
+                BufWriter::new(File::create(path)?),
+                header,
+                (u32::MAX as usize) + 4,
+            )
+            .err()
+            .expect("Wavs can't be larger than 4GB");
+            assert_eq!(ErrorKind::InvalidInput, err.kind());
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn reopen_as_reader() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Int16,
+                channels: Channels::new().front_left(),
+                sample_rate: 48000,
+            };
+
+            let mut open_wav = write_wav_to_file_path(path, header)?;
+            open_wav.write_raw_frames(&[0u8; 200])?;
+
+            let open_wav = open_wav.reopen_as_reader()?;
+            assert_eq!(100, open_wav.len_samples());
+
+            let open_wav = write_wav(Cursor::new(Vec::new()), header)?;
+            let err = open_wav
+                .reopen_as_reader()
+                .err()
+                .expect("Wavs written to a Cursor can't be reopened");
+            assert_eq!(ErrorKind::Unsupported, err.kind());
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn write_stream_missing_channel() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Float,
+                channels: Channels::new().front_left().front_right(),
+                sample_rate: 48000,
+            };
+
+            let open_wav = write_wav_to_file_path(path, header)?;
+            let samples_itr = (0..10).map(|sample| {
+                if sample == 5 {
+                    Ok(SamplesByChannel::new().front_left(0.0))
+                } else {
+                    Ok(SamplesByChannel::new().front_left(0.0).front_right(0.0))
+                }
+            });
+
+            let err = open_wav
+                .write_all_f32(samples_itr)
+                .expect_err("front_right is missing at sample 5");
+            assert_eq!(ErrorKind::InvalidInput, err.kind());
+            assert!(err.to_string().contains("Sample 5"), "{err}");
+            assert!(err.to_string().contains("FrontRight"), "{err}");
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn refresh_len_while_writing() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Int16,
+                channels: Channels::new().front_left(),
+                sample_rate: 44100,
+            };
+
+            let mut writer =
+                write_wav_to_file_path(path, header)?.get_random_access_i16_writer()?;
+            for sample in 0..10 {
+                writer.write_samples(sample, SamplesByChannel::new().front_left(sample as i16))?;
+            }
+            writer.flush()?;
+
+            let mut reader = read_wav_from_file_path(path)?.get_random_access_i16_reader()?;
+            assert_eq!(10, reader.info().len_samples());
+            assert!(reader.read_sample(20).is_err());
+
+            for sample in 10..30 {
+                writer.write_samples(sample, SamplesByChannel::new().front_left(sample as i16))?;
+            }
+
+            // Samples aren't visible until the writer flushes
+            assert_eq!(10, reader.refresh_len()?);
+
+            writer.flush()?;
+            assert_eq!(30, reader.refresh_len()?);
+            assert_eq!(
+                SamplesByChannel::new().front_left(20),
+                reader.read_sample(20)?
+            );
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn random_access_on_write() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Int16,
+                channels: Channels::new().front_left(),
+                sample_rate: 44100,
+            };
+
+            let written = Arc::new(Mutex::new(0usize));
+            let last_sample = Arc::new(Mutex::new(None));
+
+            let mut writer =
+                write_wav_to_file_path(path, header)?.get_random_access_i16_writer()?;
+            {
+                let written = written.clone();
+                let last_sample = last_sample.clone();
+                writer.set_on_write(Box::new(move |sample, samples_by_channel| {
+                    *written.lock().unwrap() += 1;
+                    *last_sample.lock().unwrap() = Some((sample, samples_by_channel.front_left));
+                }));
+            }
+
+            // The callback is Send, so the writer can still be moved to another thread
+            std::thread::spawn(move || -> Result<()> {
+                for sample in 0..100 {
+                    writer
+                        .write_samples(sample, SamplesByChannel::new().front_left(sample as i16))?;
+                }
+                writer.flush()
+            })
+            .join()
+            .unwrap()?;
+
+            assert_eq!(100, *written.lock().unwrap());
+            assert_eq!(Some((99, Some(99))), *last_sample.lock().unwrap());
+
+            Ok(())
+        }));
+    }
+
+    #[test]
+    fn write_extra_channel_strict() {
+        test_with_file(Box::new(|path| {
+            let header = WavHeader {
+                sample_format: SampleFormat::Float,
+                channels: Channels::new().front_left().front_right(),
+                sample_rate: 48000,
+            };
+            let with_center = SamplesByChannel::new()
+                .front_left(0.0)
+                .front_right(0.0)
+                .front_center(0.5);
+
+            let open_wav = write_wav_to_file_path(path, header)?;
+            let err = open_wav
+                .write_all_f32((0..10).map(|_| Ok(with_center)))
+                .expect_err("front_center is not in the wav");
+            assert_eq!(ErrorKind::InvalidInput, err.kind());
+            assert_eq!(
+                "Sample 0: FrontCenter channel is not in the wav",
+                err.to_string()
+            );
+
+            // Random access ignores extra channels, unless it's strict
+            let mut writer =
+                write_wav_to_file_path(path, header)?.get_random_access_f32_writer()?;
+            writer.write_samples(0, with_center)?;
+
+            writer.set_strict_channels(true);
+            let err = writer
+                .write_samples(1, with_center)
+                .expect_err("front_center is not in the wav");
+            assert_eq!(ErrorKind::InvalidInput, err.kind());
+            assert_eq!(
+                "Sample 1: FrontCenter channel is not in the wav",
+                err.to_string()
+            );
+
+            let err = writer
+                .write_samples(1, SamplesByChannel::new().front_left(0.0))
+                .expect_err("front_right is missing");
+            assert_eq!("Sample 1: FrontRight channel missing", err.to_string());
+
+            writer.flush()?;
+            assert_eq!(1, writer.info().len_samples());
+
+            Ok(())
+        }));
+    }
+
     #[test]
     fn write_stream_i8() {
         write_stream(
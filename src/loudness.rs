@@ -0,0 +1,187 @@
+//! Loudness measurement, as specified in ITU-R BS.1770
+
+use std::f64::consts::PI;
+use std::io::Result;
+
+use crate::filter::{BiquadCoeffs, BiquadState};
+use crate::wave_header::ChannelPosition;
+use crate::wave_reader::StreamWavReader;
+
+/// Blocks that are quieter than this are ignored, in LUFS
+const ABSOLUTE_GATE: f64 = -70.0;
+
+/// Blocks that are this much quieter than the ungated loudness are ignored, in LU
+const RELATIVE_GATE: f64 = -10.0;
+
+/// Measures the integrated loudness of a wav, in LUFS, using K-weighting and gating. The low frequency effects (LFE)
+/// channel is excluded. Returns negative infinity if the wav is silent or shorter than 400ms
+///
+/// # Arguments
+///
+/// * 'reader' - The wav to measure
+pub fn measure_loudness(reader: StreamWavReader<f32>) -> Result<f32> {
+    let sample_rate = reader.info().sample_rate();
+    let channels = *reader.info().channels();
+
+    let shelf = k_weighting_shelf(sample_rate);
+    let high_pass = k_weighting_high_pass(sample_rate);
+
+    let weights: Vec<(ChannelPosition, f64)> = channels
+        .positions()
+        .filter(|position| *position != ChannelPosition::LowFrequency)
+        .map(|position| (position, channel_weight(position)))
+        .collect();
+    let mut states = vec![(BiquadState::default(), BiquadState::default()); weights.len()];
+
+    // Blocks are 400ms, and overlap by 75%, so the weighted sum of squares is kept for each 100ms step
+    let step_len = (sample_rate / 10) as usize;
+    let mut steps = Vec::new();
+    let mut step_sum = 0f64;
+    let mut samples_in_step = 0usize;
+
+    for samples_result in reader.into_iter() {
+        let samples = samples_result?;
+
+        for ((position, weight), (shelf_state, high_pass_state)) in
+            weights.iter().zip(states.iter_mut())
+        {
+            let sample = *samples.get(*position).expect("Channel missing");
+            let filtered = high_pass_state.process(&high_pass, shelf_state.process(&shelf, sample));
+            step_sum += weight * (filtered as f64) * (filtered as f64);
+        }
+
+        samples_in_step += 1;
+        if samples_in_step == step_len {
+            steps.push(step_sum);
+            step_sum = 0.0;
+            samples_in_step = 0;
+        }
+    }
+
+    let block_len = (step_len * 4) as f64;
+    let block_powers: Vec<f64> = steps
+        .windows(4)
+        .map(|block| block.iter().sum::<f64>() / block_len)
+        .collect();
+
+    let absolute_gated: Vec<f64> = block_powers
+        .into_iter()
+        .filter(|power| power_to_lufs(*power) > ABSOLUTE_GATE)
+        .collect();
+    if absolute_gated.is_empty() {
+        return Ok(f32::NEG_INFINITY);
+    }
+
+    let relative_gate = power_to_lufs(mean(&absolute_gated)) + RELATIVE_GATE;
+    let relative_gated: Vec<f64> = absolute_gated
+        .into_iter()
+        .filter(|power| power_to_lufs(*power) > relative_gate)
+        .collect();
+    if relative_gated.is_empty() {
+        return Ok(f32::NEG_INFINITY);
+    }
+
+    Ok(power_to_lufs(mean(&relative_gated)) as f32)
+}
+
+fn power_to_lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / (values.len() as f64)
+}
+
+// Surround channels are louder, because they are closer to the listener's ears
+fn channel_weight(position: ChannelPosition) -> f64 {
+    match position {
+        ChannelPosition::BackLeft
+        | ChannelPosition::BackRight
+        | ChannelPosition::SideLeft
+        | ChannelPosition::SideRight => 1.41,
+        _ => 1.0,
+    }
+}
+
+// The first stage of K-weighting, a high shelf that models the acoustic effect of the head
+fn k_weighting_shelf(sample_rate: u32) -> BiquadCoeffs {
+    let f0 = 1681.974450955533;
+    let gain = 3.999843853973347;
+    let q = 0.7071752369554196;
+
+    let k = (PI * f0 / (sample_rate as f64)).tan();
+    let vh = 10f64.powf(gain / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+
+    BiquadCoeffs {
+        b0: ((vh + vb * k / q + k * k) / a0) as f32,
+        b1: (2.0 * (k * k - vh) / a0) as f32,
+        b2: ((vh - vb * k / q + k * k) / a0) as f32,
+        a1: (2.0 * (k * k - 1.0) / a0) as f32,
+        a2: ((1.0 - k / q + k * k) / a0) as f32,
+    }
+}
+
+// The second stage of K-weighting, the RLB high-pass
+fn k_weighting_high_pass(sample_rate: u32) -> BiquadCoeffs {
+    let f0 = 38.13547087602444;
+    let q = 0.5003270373238773;
+
+    let k = (PI * f0 / (sample_rate as f64)).tan();
+    let a0 = 1.0 + k / q + k * k;
+
+    BiquadCoeffs {
+        b0: 1.0,
+        b1: -2.0,
+        b2: 1.0,
+        a1: (2.0 * (k * k - 1.0) / a0) as f32,
+        a2: ((1.0 - k / q + k * k) / a0) as f32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::TAU;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::samples_by_channel::SamplesByChannel;
+    use crate::wave_header::{Channels, SampleFormat, WavHeader};
+    use crate::wave_reader::StreamOpenWavReader;
+    use crate::{read_wav_from_file_path, write_wav_to_file_path};
+
+    #[test]
+    fn measure_loudness_reference_tone() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("reference.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Float,
+            channels: Channels::new().front_left().front_right(),
+            sample_rate: 48000,
+        };
+
+        // EBU Tech 3341: A stereo 1kHz sine at -23 dBFS measures -23 LUFS
+        let amplitude = 10f32.powf(-23.0 / 20.0);
+        let open_wav = write_wav_to_file_path(&path, header).unwrap();
+        open_wav
+            .write_all_f32((0..48000 * 5).map(|sample| {
+                let value = (TAU * 1000.0 * sample as f32 / 48000.0).sin() * amplitude;
+                Ok(SamplesByChannel::new().front_left(value).front_right(value))
+            }))
+            .unwrap();
+
+        let reader = read_wav_from_file_path(&path)
+            .unwrap()
+            .get_stream_f32_reader()
+            .unwrap();
+        let loudness = measure_loudness(reader).unwrap();
+
+        assert!(
+            (loudness + 23.0).abs() < 0.1,
+            "Expected -23 LUFS, measured {loudness}"
+        );
+    }
+}
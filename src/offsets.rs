@@ -0,0 +1,120 @@
+//! Sample indices and byte offsets, as distinct types, so that seek math can't mix them up. Converting between them
+//! requires the number of bytes in each sample, across all channels. (OpenWav::frame_bytes())
+//!
+//! Public APIs accept usize; SampleIndex and ByteOffset convert to and from usize with From
+//!
+//! ```compile_fail
+//! use wave_stream::offsets::{ByteOffset, SampleIndex};
+//!
+//! // A sample index is not a byte offset
+//! let offset: ByteOffset = SampleIndex(10);
+//! ```
+
+use std::ops::{Add, Sub};
+
+/// The index of a sample, across all channels
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct SampleIndex(pub usize);
+
+/// A position or length, in bytes
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct ByteOffset(pub usize);
+
+impl SampleIndex {
+    /// The offset of the sample's first byte, relative to the first sample
+    ///
+    /// # Arguments
+    ///
+    /// * 'frame_bytes' - The number of bytes in each sample, across all channels
+    pub fn to_byte_offset(self, frame_bytes: usize) -> ByteOffset {
+        ByteOffset(self.0 * frame_bytes)
+    }
+}
+
+impl ByteOffset {
+    /// The index of the sample that contains the byte, where the offset is relative to the first sample
+    ///
+    /// # Arguments
+    ///
+    /// * 'frame_bytes' - The number of bytes in each sample, across all channels
+    pub fn to_sample_index(self, frame_bytes: usize) -> SampleIndex {
+        SampleIndex(self.0 / frame_bytes)
+    }
+}
+
+impl From<usize> for SampleIndex {
+    fn from(sample: usize) -> Self {
+        SampleIndex(sample)
+    }
+}
+
+impl From<SampleIndex> for usize {
+    fn from(sample: SampleIndex) -> Self {
+        sample.0
+    }
+}
+
+impl From<usize> for ByteOffset {
+    fn from(offset: usize) -> Self {
+        ByteOffset(offset)
+    }
+}
+
+impl From<ByteOffset> for usize {
+    fn from(offset: ByteOffset) -> Self {
+        offset.0
+    }
+}
+
+impl From<ByteOffset> for u64 {
+    fn from(offset: ByteOffset) -> Self {
+        offset.0 as u64
+    }
+}
+
+impl Add for ByteOffset {
+    type Output = ByteOffset;
+
+    fn add(self, rhs: ByteOffset) -> ByteOffset {
+        ByteOffset(self.0 + rhs.0)
+    }
+}
+
+impl Sub for ByteOffset {
+    type Output = ByteOffset;
+
+    fn sub(self, rhs: ByteOffset) -> ByteOffset {
+        ByteOffset(self.0 - rhs.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_to_byte_offset() {
+        // 16-bit stereo
+        assert_eq!(ByteOffset(0), SampleIndex(0).to_byte_offset(4));
+        assert_eq!(ByteOffset(40), SampleIndex(10).to_byte_offset(4));
+    }
+
+    #[test]
+    fn byte_offset_to_sample() {
+        // 24-bit 5.1
+        assert_eq!(SampleIndex(10), ByteOffset(180).to_sample_index(18));
+        // Bytes within a sample belong to that sample
+        assert_eq!(SampleIndex(10), ByteOffset(197).to_sample_index(18));
+    }
+
+    #[test]
+    fn usize_conversions() {
+        assert_eq!(SampleIndex(5), SampleIndex::from(5));
+        assert_eq!(5usize, SampleIndex(5).into());
+        assert_eq!(ByteOffset(7), ByteOffset::from(7));
+        assert_eq!(7usize, ByteOffset(7).into());
+        assert_eq!(7u64, ByteOffset(7).into());
+        assert_eq!(ByteOffset(10), ByteOffset(7) + ByteOffset(3));
+        assert_eq!(ByteOffset(4), ByteOffset(7) - ByteOffset(3));
+    }
+}
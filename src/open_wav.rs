@@ -1,4 +1,6 @@
-use crate::{wave_header::Channels, SampleFormat};
+use std::time::Duration;
+
+use crate::{wave_header::Channels, SampleFormat, WavHeader};
 
 /// Represents an open wav file
 pub trait OpenWav {
@@ -14,6 +16,43 @@ pub trait OpenWav {
     fn bits_per_sample(&self) -> u16;
     /// The bytes per sample
     fn bytes_per_sample(&self) -> u16;
+    /// Returns true if the wav has a low frequency effects (LFE) channel
+    fn has_lfe(&self) -> bool {
+        self.channels().has_lfe()
+    }
+    /// The name of the wav's channel layout, (IE, "5.1",) or None if the channels aren't a standard layout
+    fn layout_name(&self) -> Option<&'static str> {
+        self.channels().layout_name()
+    }
+    /// The number of bytes in each sample, across all channels
+    fn frame_bytes(&self) -> usize {
+        (self.num_channels() as usize) * (self.bytes_per_sample() as usize)
+    }
     /// The total number of samples in the wav file
     fn len_samples(&self) -> usize;
+    /// The total number of samples in the wav file, computed as u64 so that it can't truncate on 32-bit targets
+    fn len_samples_u64(&self) -> u64;
+}
+
+/// A summary of a wav file, from probe(). Unlike OpenWavReader, it doesn't hold the file open, so it's easy to store
+/// and pass around
+#[derive(Debug, Clone, PartialEq)]
+pub struct WavInfo {
+    /// The wav's sample format, channels, and sample rate
+    pub header: WavHeader,
+    /// The total number of samples in the wav
+    pub len_samples: usize,
+    /// The length of the wav, rounded down to the nearest nanosecond
+    pub duration: Duration,
+    /// The ids of the chunks between the fmt and data chunks, in the order that they appear in the wav
+    pub extra_chunk_ids: Vec<[u8; 4]>,
+}
+
+// The header of an open wav, for comparing with another wav's header
+pub(crate) fn header_of<TOpenWav: OpenWav + ?Sized>(open_wav: &TOpenWav) -> WavHeader {
+    WavHeader {
+        sample_format: open_wav.sample_format(),
+        channels: *open_wav.channels(),
+        sample_rate: open_wav.sample_rate(),
+    }
 }
@@ -0,0 +1,973 @@
+//! Helpers that read wavs, process their samples, and write the results into another wav
+
+use std::f32::consts::FRAC_PI_2;
+use std::io::{Error, ErrorKind, Read, Result};
+use std::iter;
+use std::time::Duration;
+
+use crate::open_wav::{header_of, OpenWav};
+use crate::samples_by_channel::SamplesByChannel;
+use crate::wave_header::{ChannelPosition, Channels, WavHeader};
+use crate::wave_reader::{
+    OpenWavReader, RandomAccessWavReader, StreamWavReader, StreamWavReaderIterator,
+};
+use crate::wave_writer::OpenWavWriter;
+
+/// Merges separate mono wavs into a single multichannel wav. (IE, combining the stems of a film mix)
+///
+/// # Arguments
+///
+/// * 'inputs' - The mono wavs to merge, and the channel position that each one is written to
+/// * 'out' - The wav to write. Its channels must be exactly the positions used in 'inputs'
+///
+/// All inputs must have the same sample rate as 'out'. Inputs that are shorter than the longest input are padded
+/// with silence.
+pub fn merge_channels(
+    inputs: Vec<(ChannelPosition, StreamWavReader<f32>)>,
+    out: OpenWavWriter,
+) -> Result<()> {
+    let mut channels = Channels::new();
+    for (position, reader) in inputs.iter() {
+        if reader.info().num_channels() != 1 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "All inputs must be mono",
+            ));
+        }
+
+        if reader.info().sample_rate() != out.sample_rate() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "All inputs must have the same sample rate as the output",
+            ));
+        }
+
+        if channels.contains(*position) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{:?} is used by more than one input", position),
+            ));
+        }
+
+        channels = channels.with_position(*position);
+    }
+
+    if channels != *out.channels() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "The output's channels must match the positions of the inputs",
+        ));
+    }
+
+    let mut inputs: Vec<(ChannelPosition, StreamWavReaderIterator<f32>)> = inputs
+        .into_iter()
+        .map(|(position, reader)| (position, reader.into_iter()))
+        .collect();
+
+    let samples_itr = iter::from_fn(move || {
+        let mut samples_by_channel = SamplesByChannel::new();
+        let mut sample_read = false;
+
+        for (position, input) in inputs.iter_mut() {
+            let sample = match input.next() {
+                Some(Ok(samples)) => {
+                    sample_read = true;
+                    samples.to_vec()[0]
+                }
+                Some(Err(err)) => return Some(Err(err)),
+                None => 0.0,
+            };
+
+            samples_by_channel.set(*position, sample);
+        }
+
+        if sample_read {
+            Some(Ok(samples_by_channel))
+        } else {
+            None
+        }
+    });
+
+    out.write_all_f32(samples_itr)
+}
+
+// The number of samples that split_stereo buffers before writing
+const SPLIT_SAMPLES_PER_WRITE: usize = 4096;
+
+/// Splits a stereo wav into two mono wavs, (IE, for stem extraction,) in a single pass
+///
+/// # Arguments
+///
+/// * 'reader' - The wav to split. It must be stereo
+/// * 'left_out' - The wav that the left channel is written to. It must be mono, with the same sample rate as 'reader'
+/// * 'right_out' - The wav that the right channel is written to. It must be mono, with the same sample rate as
+///   'reader'
+pub fn split_stereo(
+    reader: StreamWavReader<f32>,
+    left_out: OpenWavWriter,
+    right_out: OpenWavWriter,
+) -> Result<()> {
+    if *reader.info().channels() != Channels::new().front_left().front_right() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Only stereo wavs can be split",
+        ));
+    }
+
+    for out in [&left_out, &right_out] {
+        if out.num_channels() != 1 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Stereo wavs can only be split into mono wavs",
+            ));
+        }
+
+        if out.sample_rate() != reader.info().sample_rate() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Sample rate {} does not match sample rate {}",
+                    reader.info().sample_rate(),
+                    out.sample_rate()
+                ),
+            ));
+        }
+    }
+
+    let mut left_writer = left_out.get_random_access_f32_writer()?;
+    let mut right_writer = right_out.get_random_access_f32_writer()?;
+    let mut left = Vec::with_capacity(SPLIT_SAMPLES_PER_WRITE);
+    let mut right = Vec::with_capacity(SPLIT_SAMPLES_PER_WRITE);
+
+    for samples_result in reader.into_iter() {
+        let samples = samples_result?;
+        left.push(samples.front_left.expect("Left channel missing"));
+        right.push(samples.front_right.expect("Right channel missing"));
+
+        if left.len() >= SPLIT_SAMPLES_PER_WRITE {
+            left_writer.push_interleaved(&left)?;
+            right_writer.push_interleaved(&right)?;
+            left.clear();
+            right.clear();
+        }
+    }
+
+    left_writer.push_interleaved(&left)?;
+    right_writer.push_interleaved(&right)?;
+    left_writer.flush()?;
+    right_writer.flush()
+}
+
+/// Delays each channel by a number of samples, inserting silence at the start of the channel. (IE, time-aligning
+/// the microphones in a multi-mic recording.) The written wav is longer than 'reader' by the longest delay
+///
+/// # Arguments
+///
+/// * 'reader' - The wav to delay
+/// * 'out' - The wav to write. It must have the same channels and sample rate as 'reader'
+/// * 'delays' - The number of samples to delay each channel by. Channels without a delay are not delayed
+pub fn apply_channel_delays(
+    mut reader: RandomAccessWavReader<f32>,
+    out: OpenWavWriter,
+    delays: SamplesByChannel<usize>,
+) -> Result<()> {
+    header_of(reader.info().as_ref()).compatible_with(out.header())?;
+
+    let channels = *reader.info().channels();
+
+    let mut max_delay = 0;
+    for position in Channels::all_positions() {
+        if let Some(delay) = delays.get(position) {
+            if !channels.contains(position) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("{:?} is delayed, but it is not in the wav", position),
+                ));
+            }
+
+            max_delay = max_delay.max(*delay);
+        }
+    }
+
+    let len_samples = reader.info().len_samples();
+    let samples_itr = (0..len_samples + max_delay).map(move |sample| {
+        let mut samples_by_channel = SamplesByChannel::new();
+        for position in channels.positions() {
+            let delay = delays.get(position).copied().unwrap_or(0);
+            let value = if sample >= delay && sample - delay < len_samples {
+                match reader.read_sample(sample - delay)?.get(position) {
+                    Some(value) => *value,
+                    None => 0.0,
+                }
+            } else {
+                0.0
+            };
+
+            samples_by_channel.set(position, value);
+        }
+
+        Ok(samples_by_channel)
+    });
+
+    out.write_all_f32(samples_itr)
+}
+
+/// Copies a wav while changing its level
+///
+/// # Arguments
+///
+/// * 'reader' - The wav to copy
+/// * 'out' - The wav to write. It must have the same channels and sample rate as 'reader'
+/// * 'gain_db' - The gain, in decibels. (IE, -6.0 roughly halves the level of the wav)
+pub fn apply_gain_db(reader: StreamWavReader<f32>, out: OpenWavWriter, gain_db: f32) -> Result<()> {
+    header_of(reader.info().as_ref()).compatible_with(out.header())?;
+
+    let gain = 10f32.powf(gain_db / 20.0);
+    out.write_all_f32(reader.scaled(gain))
+}
+
+/// Mixes a wav's channels into a wav with a different number of channels, (IE, 5.1 to stereo, or stereo to 5.1,)
+/// where each written channel is a weighted sum of the read channels
+///
+/// # Arguments
+///
+/// * 'reader' - The wav to mix
+/// * 'out' - The wav to write. It must have the same sample rate as 'reader'
+/// * 'matrix' - The coefficients, where matrix[out_channel][in_channel] is how much of in_channel is mixed into
+///   out_channel. Channels are indexed in the order that they are interleaved. (IE, Channels::positions()) There must
+///   be a row for each of out's channels, and each row must have a coefficient for each of reader's channels
+pub fn downmix_matrix(
+    reader: StreamWavReader<f32>,
+    out: OpenWavWriter,
+    matrix: &[&[f32]],
+) -> Result<()> {
+    if reader.info().sample_rate() != out.sample_rate() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Sample rate {} does not match sample rate {}",
+                reader.info().sample_rate(),
+                out.sample_rate()
+            ),
+        ));
+    }
+
+    let in_positions: Vec<ChannelPosition> = reader.info().channels().positions().collect();
+    let out_positions: Vec<ChannelPosition> = out.channels().positions().collect();
+
+    if matrix.len() != out_positions.len() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "The matrix has {} rows, but the output has {} channels",
+                matrix.len(),
+                out_positions.len()
+            ),
+        ));
+    }
+
+    for (out_channel, row) in matrix.iter().enumerate() {
+        if row.len() != in_positions.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Row {} of the matrix has {} coefficients, but the input has {} channels",
+                    out_channel,
+                    row.len(),
+                    in_positions.len()
+                ),
+            ));
+        }
+    }
+
+    let matrix: Vec<Vec<f32>> = matrix.iter().map(|row| row.to_vec()).collect();
+    let samples_itr = reader.into_iter().map(move |samples_result| {
+        let samples = samples_result?;
+        let in_samples: Vec<f32> = in_positions
+            .iter()
+            .map(|position| samples.get(*position).copied().unwrap_or(0.0))
+            .collect();
+
+        let mut samples_by_channel = SamplesByChannel::new();
+        for (position, row) in out_positions.iter().zip(matrix.iter()) {
+            let sample = row
+                .iter()
+                .zip(in_samples.iter())
+                .map(|(coefficient, sample)| coefficient * sample)
+                .sum();
+            samples_by_channel.set(*position, sample);
+        }
+
+        Ok(samples_by_channel)
+    });
+
+    out.write_all_f32(samples_itr)
+}
+
+/// Copies a wav, and then appends silence so that the written wav is exactly 'target' long. (IE, for broadcast
+/// deliverables that must be an exact length)
+///
+/// # Arguments
+///
+/// * 'reader' - The wav to copy. It can not be longer than 'target'
+/// * 'out' - The wav to write. It must have the same channels and sample rate as 'reader'
+/// * 'target' - The length of the written wav. Partial samples are rounded down
+pub fn pad_to_duration(
+    reader: StreamWavReader<f32>,
+    out: OpenWavWriter,
+    target: Duration,
+) -> Result<()> {
+    header_of(reader.info().as_ref()).compatible_with(out.header())?;
+
+    let target_samples = samples_in_duration(target, out.sample_rate());
+    let len_samples = reader.info().len_samples();
+    if len_samples as u128 > target_samples {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "The wav is {} samples long, which is longer than the target of {} samples",
+                len_samples, target_samples
+            ),
+        ));
+    }
+
+    let silence = silence(*out.channels(), target_samples as usize - len_samples);
+    out.write_all_f32(reader.into_iter().chain(silence))
+}
+
+/// Crossfades from the end of one wav into the start of another, (IE, for a gapless playlist,) using an
+/// equal-power curve. The written wav is 'a', then the overlap where 'a' fades out while 'b' fades in, and then the
+/// rest of 'b'
+///
+/// # Arguments
+///
+/// * 'a' - The wav that fades out
+/// * 'b' - The wav that fades in. It must have the same channels and sample rate as 'a'
+/// * 'out' - The wav to write. It must have the same channels and sample rate as 'a'
+/// * 'overlap' - The length of the crossfade. Partial samples are rounded down. It can not be longer than either wav
+pub fn crossfade(
+    mut a: RandomAccessWavReader<f32>,
+    mut b: RandomAccessWavReader<f32>,
+    out: OpenWavWriter,
+    overlap: Duration,
+) -> Result<()> {
+    let header = header_of(a.info().as_ref());
+    header.compatible_with(&header_of(b.info().as_ref()))?;
+    header.compatible_with(out.header())?;
+
+    let overlap_samples = samples_in_duration(overlap, out.sample_rate()) as usize;
+    let a_len_samples = a.info().len_samples();
+    let b_len_samples = b.info().len_samples();
+    if overlap_samples > a_len_samples || overlap_samples > b_len_samples {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "The overlap of {} samples is longer than a wav. (Lengths: {} and {} samples)",
+                overlap_samples, a_len_samples, b_len_samples
+            ),
+        ));
+    }
+
+    let channels = *out.channels();
+    let fade_start = a_len_samples - overlap_samples;
+    let samples_itr = (0..a_len_samples + b_len_samples - overlap_samples).map(move |sample| {
+        if sample < fade_start {
+            return a.read_sample(sample);
+        } else if sample >= a_len_samples {
+            return b.read_sample(sample - fade_start);
+        }
+
+        // Equal-power: The gains are a quarter cycle of cosine and sine, so the power stays constant
+        let position = (sample - fade_start) as f32 / overlap_samples as f32;
+        let a_gain = (position * FRAC_PI_2).cos();
+        let b_gain = (position * FRAC_PI_2).sin();
+
+        let a_samples = a.read_sample(sample)?;
+        let b_samples = b.read_sample(sample - fade_start)?;
+
+        let mut samples_by_channel = SamplesByChannel::new();
+        for position in channels.positions() {
+            let a_value = a_samples.get(position).copied().unwrap_or(0.0);
+            let b_value = b_samples.get(position).copied().unwrap_or(0.0);
+            samples_by_channel.set(position, a_value * a_gain + b_value * b_gain);
+        }
+
+        Ok(samples_by_channel)
+    });
+
+    out.write_all_f32(samples_itr)
+}
+
+// The number of samples that reinterpret_sample_rate copies at a time
+const REINTERPRET_FRAMES_PER_WRITE: usize = 4096;
+
+/// Copies a wav's samples without changing them, into a wav with a different sample rate. This changes the speed
+/// and pitch, (IE, doubling the sample rate plays back twice as fast and an octave higher,) without resampling
+///
+/// # Arguments
+///
+/// * 'reader' - The wav to copy
+/// * 'out_rate' - The new sample rate
+/// * 'out' - The wav to write. It must have the same channels and sample format as 'reader', and 'out_rate' as its
+///   sample rate
+pub fn reinterpret_sample_rate<TReader: Read>(
+    reader: OpenWavReader<TReader>,
+    out_rate: u32,
+    mut out: OpenWavWriter,
+) -> Result<()> {
+    let header = WavHeader {
+        sample_rate: out_rate,
+        ..*reader.header()
+    };
+    header.compatible_with_sample_format(out.header())?;
+
+    let bytes_per_write = reader.frame_bytes() * REINTERPRET_FRAMES_PER_WRITE;
+    let mut frames = Vec::with_capacity(bytes_per_write);
+    for frame_result in reader.into_raw_frame_iter() {
+        frames.extend(frame_result?);
+
+        if frames.len() >= bytes_per_write {
+            out.write_raw_frames(&frames)?;
+            frames.clear();
+        }
+    }
+
+    out.write_raw_frames(&frames)?;
+    out.flush()
+}
+
+// The number of whole samples in 'duration'
+fn samples_in_duration(duration: Duration, sample_rate: u32) -> u128 {
+    duration.as_nanos() * sample_rate as u128 / 1_000_000_000
+}
+
+// Silent samples, for padding
+fn silence(
+    channels: Channels,
+    len_samples: usize,
+) -> impl Iterator<Item = Result<SamplesByChannel<f32>>> {
+    let mut samples_by_channel = SamplesByChannel::new();
+    for position in channels.positions() {
+        samples_by_channel.set(position, 0.0);
+    }
+
+    iter::repeat(samples_by_channel).take(len_samples).map(Ok)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::wave_header::{SampleFormat, WavHeader};
+    use crate::wave_reader::{RandomAccessOpenWavReader, StreamOpenWavReader};
+    use crate::{read_wav_from_file_path, write_wav_to_file_path};
+
+    fn write_mono(path: &Path, samples: &[f32]) -> Result<()> {
+        let header = WavHeader {
+            sample_format: SampleFormat::Float,
+            channels: Channels::new().front_left(),
+            sample_rate: 48000,
+        };
+
+        let open_wav = write_wav_to_file_path(path, header)?;
+        open_wav.write_all_f32(
+            samples
+                .iter()
+                .map(|sample| Ok(SamplesByChannel::new().front_left(*sample))),
+        )
+    }
+
+    #[test]
+    fn merge_channels_sanity() {
+        let temp_dir = tempdir().unwrap();
+        let left_path = temp_dir.path().join("left.wav");
+        let right_path = temp_dir.path().join("right.wav");
+        let center_path = temp_dir.path().join("center.wav");
+        let merged_path = temp_dir.path().join("merged.wav");
+
+        write_mono(&left_path, &[0.1; 10]).unwrap();
+        write_mono(&right_path, &[0.2; 8]).unwrap();
+        write_mono(&center_path, &[0.3; 5]).unwrap();
+
+        let inputs = vec![
+            (
+                ChannelPosition::FrontLeft,
+                read_wav_from_file_path(&left_path)
+                    .unwrap()
+                    .get_stream_f32_reader()
+                    .unwrap(),
+            ),
+            (
+                ChannelPosition::FrontRight,
+                read_wav_from_file_path(&right_path)
+                    .unwrap()
+                    .get_stream_f32_reader()
+                    .unwrap(),
+            ),
+            (
+                ChannelPosition::FrontCenter,
+                read_wav_from_file_path(&center_path)
+                    .unwrap()
+                    .get_stream_f32_reader()
+                    .unwrap(),
+            ),
+        ];
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Float,
+            channels: Channels::new().front_left().front_right().front_center(),
+            sample_rate: 48000,
+        };
+        let out = write_wav_to_file_path(&merged_path, header).unwrap();
+        merge_channels(inputs, out).unwrap();
+
+        let open_wav = read_wav_from_file_path(&merged_path).unwrap();
+        assert_eq!(3, open_wav.num_channels());
+        assert_eq!(10, open_wav.len_samples(), "Wrong length of merged wav");
+
+        let mut reader = open_wav.get_random_access_f32_reader().unwrap();
+
+        let samples = reader.read_sample(0).unwrap();
+        assert_eq!(
+            SamplesByChannel::new()
+                .front_left(0.1)
+                .front_right(0.2)
+                .front_center(0.3),
+            samples
+        );
+
+        let samples = reader.read_sample(6).unwrap();
+        assert_eq!(
+            SamplesByChannel::new()
+                .front_left(0.1)
+                .front_right(0.2)
+                .front_center(0.0),
+            samples,
+            "Center should be padded with silence"
+        );
+
+        let samples = reader.read_sample(9).unwrap();
+        assert_eq!(
+            SamplesByChannel::new()
+                .front_left(0.1)
+                .front_right(0.0)
+                .front_center(0.0),
+            samples,
+            "Right and center should be padded with silence"
+        );
+    }
+
+    #[test]
+    fn merge_channels_layout_mismatch() {
+        let temp_dir = tempdir().unwrap();
+        let left_path = temp_dir.path().join("left.wav");
+        let merged_path = temp_dir.path().join("merged.wav");
+
+        write_mono(&left_path, &[0.1; 10]).unwrap();
+
+        let inputs = vec![(
+            ChannelPosition::FrontLeft,
+            read_wav_from_file_path(&left_path)
+                .unwrap()
+                .get_stream_f32_reader()
+                .unwrap(),
+        )];
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Float,
+            channels: Channels::new().front_left().front_right(),
+            sample_rate: 48000,
+        };
+        let out = write_wav_to_file_path(&merged_path, header).unwrap();
+        let err = merge_channels(inputs, out).expect_err("Output has an extra channel");
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+    }
+
+    #[test]
+    fn apply_channel_delays_sanity() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("stereo.wav");
+        let delayed_path = temp_dir.path().join("delayed.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Float,
+            channels: Channels::new().front_left().front_right(),
+            sample_rate: 48000,
+        };
+
+        let open_wav = write_wav_to_file_path(&path, header).unwrap();
+        open_wav
+            .write_all_f32((1..=100).map(|sample| {
+                let value = sample as f32 / 100.0;
+                Ok(SamplesByChannel::new()
+                    .front_left(value)
+                    .front_right(-value))
+            }))
+            .unwrap();
+
+        let reader = read_wav_from_file_path(&path)
+            .unwrap()
+            .get_random_access_f32_reader()
+            .unwrap();
+        let out = write_wav_to_file_path(&delayed_path, header).unwrap();
+        apply_channel_delays(reader, out, SamplesByChannel::new().front_right(10)).unwrap();
+
+        let open_wav = read_wav_from_file_path(&delayed_path).unwrap();
+        assert_eq!(110, open_wav.len_samples());
+
+        let mut reader = open_wav.get_random_access_f32_reader().unwrap();
+        for sample in 0..110usize {
+            let samples = reader.read_sample(sample).unwrap();
+
+            let expected_left = if sample < 100 {
+                (sample + 1) as f32 / 100.0
+            } else {
+                0.0
+            };
+            let expected_right = if sample < 10 {
+                0.0
+            } else {
+                -((sample - 9) as f32 / 100.0)
+            };
+
+            assert_eq!(
+                SamplesByChannel::new()
+                    .front_left(expected_left)
+                    .front_right(expected_right),
+                samples,
+                "Wrong samples at {sample}"
+            );
+        }
+    }
+
+    #[test]
+    fn apply_channel_delays_missing_channel() {
+        let temp_dir = tempdir().unwrap();
+        let left_path = temp_dir.path().join("left.wav");
+        let delayed_path = temp_dir.path().join("delayed.wav");
+
+        write_mono(&left_path, &[0.1; 10]).unwrap();
+
+        let reader = read_wav_from_file_path(&left_path)
+            .unwrap()
+            .get_random_access_f32_reader()
+            .unwrap();
+        let header = WavHeader {
+            sample_format: SampleFormat::Float,
+            channels: Channels::new().front_left(),
+            sample_rate: 48000,
+        };
+        let out = write_wav_to_file_path(&delayed_path, header).unwrap();
+        let err = apply_channel_delays(reader, out, SamplesByChannel::new().front_right(10))
+            .expect_err("front_right is not in the wav");
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+    }
+
+    #[test]
+    fn apply_gain_db_doubles() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("quiet.wav");
+        let loud_path = temp_dir.path().join("loud.wav");
+
+        let samples: Vec<f32> = (0..100)
+            .map(|sample| (sample as f32 - 50.0) / 200.0)
+            .collect();
+        write_mono(&path, &samples).unwrap();
+
+        let reader = read_wav_from_file_path(&path)
+            .unwrap()
+            .get_stream_f32_reader()
+            .unwrap();
+        let header = WavHeader {
+            sample_format: SampleFormat::Float,
+            channels: Channels::new().front_left(),
+            sample_rate: 48000,
+        };
+        let out = write_wav_to_file_path(&loud_path, header).unwrap();
+        apply_gain_db(reader, out, 6.02).unwrap();
+
+        let reader = read_wav_from_file_path(&loud_path)
+            .unwrap()
+            .get_stream_f32_reader()
+            .unwrap();
+        let mut samples_read = 0;
+        for (samples_result, sample) in reader.into_iter().zip(samples) {
+            let loud_sample = samples_result.unwrap().front_left.unwrap();
+            assert!(
+                (loud_sample - sample * 2.0).abs() < 0.001,
+                "Sample {samples_read}: {loud_sample} is not double {sample}"
+            );
+            samples_read += 1;
+        }
+        assert_eq!(100, samples_read);
+    }
+
+    #[test]
+    fn pad_to_duration_sanity() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("one_second.wav");
+        let padded_path = temp_dir.path().join("two_seconds.wav");
+
+        write_mono(&path, &[0.5; 48000]).unwrap();
+
+        let reader = read_wav_from_file_path(&path)
+            .unwrap()
+            .get_stream_f32_reader()
+            .unwrap();
+        let header = WavHeader {
+            sample_format: SampleFormat::Float,
+            channels: Channels::new().front_left(),
+            sample_rate: 48000,
+        };
+        let out = write_wav_to_file_path(&padded_path, header).unwrap();
+        pad_to_duration(reader, out, Duration::from_secs(2)).unwrap();
+
+        let open_wav = read_wav_from_file_path(&padded_path).unwrap();
+        assert_eq!(96000, open_wav.len_samples());
+
+        let mut reader = open_wav.get_random_access_f32_reader().unwrap();
+        assert_eq!(Some(0.5), reader.read_sample(47999).unwrap().front_left);
+        assert_eq!(Some(0.0), reader.read_sample(48000).unwrap().front_left);
+        assert_eq!(Some(0.0), reader.read_sample(95999).unwrap().front_left);
+
+        // The wav is already longer than half a second
+        let reader = read_wav_from_file_path(&path)
+            .unwrap()
+            .get_stream_f32_reader()
+            .unwrap();
+        let out = write_wav_to_file_path(&padded_path, header).unwrap();
+        let err = pad_to_duration(reader, out, Duration::from_millis(500))
+            .expect_err("The wav is longer than the target");
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+    }
+
+    #[test]
+    fn crossfade_equal_power() {
+        let temp_dir = tempdir().unwrap();
+        let a_path = temp_dir.path().join("a.wav");
+        let b_path = temp_dir.path().join("b.wav");
+        let crossfaded_path = temp_dir.path().join("crossfaded.wav");
+
+        write_mono(&a_path, &[0.5; 48000]).unwrap();
+        write_mono(&b_path, &[0.25; 24000]).unwrap();
+
+        let a = read_wav_from_file_path(&a_path)
+            .unwrap()
+            .get_random_access_f32_reader()
+            .unwrap();
+        let b = read_wav_from_file_path(&b_path)
+            .unwrap()
+            .get_random_access_f32_reader()
+            .unwrap();
+        let header = WavHeader {
+            sample_format: SampleFormat::Float,
+            channels: Channels::new().front_left(),
+            sample_rate: 48000,
+        };
+        let out = write_wav_to_file_path(&crossfaded_path, header).unwrap();
+
+        // 4800 samples of overlap
+        crossfade(a, b, out, Duration::from_millis(100)).unwrap();
+
+        let open_wav = read_wav_from_file_path(&crossfaded_path).unwrap();
+        assert_eq!(48000 + 24000 - 4800, open_wav.len_samples());
+
+        let mut reader = open_wav.get_random_access_f32_reader().unwrap();
+        let mut read = |sample| reader.read_sample(sample).unwrap().front_left.unwrap();
+
+        assert_eq!(0.5, read(0));
+        assert_eq!(0.5, read(43199));
+        // The fade starts with all of a
+        assert_eq!(0.5, read(43200));
+
+        // At the midpoint, both wavs are at -3dB
+        let midpoint = read(43200 + 2400);
+        let expected = (0.5 + 0.25) * std::f32::consts::FRAC_1_SQRT_2;
+        assert!((midpoint - expected).abs() < 0.0001, "{midpoint}");
+
+        assert_eq!(0.25, read(48000));
+        assert_eq!(0.25, read(48000 + 24000 - 4800 - 1));
+    }
+
+    #[test]
+    fn reinterpret_sample_rate_copies_samples() {
+        let temp_dir = tempdir().unwrap();
+        let in_path = temp_dir.path().join("in.wav");
+        let out_path = temp_dir.path().join("out.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Int16,
+            channels: Channels::new().front_left().front_right(),
+            sample_rate: 48000,
+        };
+
+        // More samples than are copied at a time
+        let open_wav = write_wav_to_file_path(&in_path, header).unwrap();
+        open_wav
+            .write_all_i16((0..10000).map(|sample| {
+                Ok(SamplesByChannel::new()
+                    .front_left(sample as i16)
+                    .front_right(-(sample as i16)))
+            }))
+            .unwrap();
+
+        let out_header = WavHeader {
+            sample_rate: 96000,
+            ..header
+        };
+        let out = write_wav_to_file_path(&out_path, out_header).unwrap();
+        reinterpret_sample_rate(read_wav_from_file_path(&in_path).unwrap(), 96000, out).unwrap();
+
+        let in_wav = read_wav_from_file_path(&in_path).unwrap();
+        let out_wav = read_wav_from_file_path(&out_path).unwrap();
+        assert_eq!(&out_header, out_wav.header());
+        assert_eq!(in_wav.len_samples(), out_wav.len_samples());
+
+        let in_frames: Vec<Vec<u8>> = in_wav.into_raw_frame_iter().map(Result::unwrap).collect();
+        let out_frames: Vec<Vec<u8>> = out_wav.into_raw_frame_iter().map(Result::unwrap).collect();
+        assert_eq!(in_frames, out_frames);
+
+        // The output's sample rate must be the new rate
+        let out = write_wav_to_file_path(&out_path, header).unwrap();
+        let err = reinterpret_sample_rate(read_wav_from_file_path(&in_path).unwrap(), 96000, out)
+            .expect_err("The output is 48000");
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+    }
+
+    #[test]
+    fn downmix_matrix_stereo_to_mono() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("stereo.wav");
+        let mono_path = temp_dir.path().join("mono.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Float,
+            channels: Channels::new().front_left().front_right(),
+            sample_rate: 48000,
+        };
+
+        let stereo: Vec<(f32, f32)> = (0..100)
+            .map(|sample| (sample as f32 / 100.0, (sample % 7) as f32 / -10.0))
+            .collect();
+
+        let open_wav = write_wav_to_file_path(&path, header).unwrap();
+        open_wav
+            .write_all_f32(stereo.iter().map(|(left, right)| {
+                Ok(SamplesByChannel::new()
+                    .front_left(*left)
+                    .front_right(*right))
+            }))
+            .unwrap();
+
+        let mono_header = WavHeader {
+            channels: Channels::new().front_left(),
+            ..header
+        };
+        let reader = read_wav_from_file_path(&path)
+            .unwrap()
+            .get_stream_f32_reader()
+            .unwrap();
+        let out = write_wav_to_file_path(&mono_path, mono_header).unwrap();
+        downmix_matrix(reader, out, &[&[0.5, 0.5]]).unwrap();
+
+        // The same fold-down as mono_compatibility, (L+R)/2
+        let mono: Vec<f32> = read_wav_from_file_path(&mono_path)
+            .unwrap()
+            .get_stream_f32_reader()
+            .unwrap()
+            .into_iter()
+            .map(|samples| samples.unwrap().front_left.unwrap())
+            .collect();
+        let expected: Vec<f32> = stereo
+            .iter()
+            .map(|(left, right)| (left + right) / 2.0)
+            .collect();
+        assert_eq!(expected, mono);
+
+        // The rows must have a coefficient for each input channel
+        let reader = read_wav_from_file_path(&path)
+            .unwrap()
+            .get_stream_f32_reader()
+            .unwrap();
+        let out = write_wav_to_file_path(&mono_path, mono_header).unwrap();
+        let err = downmix_matrix(reader, out, &[&[1.0]]).expect_err("The row is too short");
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+    }
+
+    #[test]
+    fn split_stereo_into_mono() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("stereo.wav");
+        let left_path = temp_dir.path().join("left.wav");
+        let right_path = temp_dir.path().join("right.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Float,
+            channels: Channels::new().front_left().front_right(),
+            sample_rate: 48000,
+        };
+
+        // More samples than are written at a time
+        let open_wav = write_wav_to_file_path(&path, header).unwrap();
+        open_wav
+            .write_all_f32((0..10000).map(|sample| {
+                Ok(SamplesByChannel::new()
+                    .front_left(sample as f32 / 10000.0)
+                    .front_right(-(sample as f32) / 20000.0))
+            }))
+            .unwrap();
+
+        let mono_header = WavHeader {
+            channels: Channels::new().front_left(),
+            ..header
+        };
+        let reader = read_wav_from_file_path(&path)
+            .unwrap()
+            .get_stream_f32_reader()
+            .unwrap();
+        split_stereo(
+            reader,
+            write_wav_to_file_path(&left_path, mono_header).unwrap(),
+            write_wav_to_file_path(&right_path, mono_header).unwrap(),
+        )
+        .unwrap();
+
+        let read_mono = |path: &Path| -> Vec<f32> {
+            read_wav_from_file_path(path)
+                .unwrap()
+                .get_stream_f32_reader()
+                .unwrap()
+                .into_iter()
+                .map(|samples| samples.unwrap().front_left.unwrap())
+                .collect()
+        };
+
+        let stereo: Vec<SamplesByChannel<f32>> = read_wav_from_file_path(&path)
+            .unwrap()
+            .get_stream_f32_reader()
+            .unwrap()
+            .into_iter()
+            .map(|samples| samples.unwrap())
+            .collect();
+        let expected_left: Vec<f32> = stereo.iter().map(|s| s.front_left.unwrap()).collect();
+        let expected_right: Vec<f32> = stereo.iter().map(|s| s.front_right.unwrap()).collect();
+        assert_eq!(expected_left, read_mono(&left_path));
+        assert_eq!(expected_right, read_mono(&right_path));
+
+        // The outputs must be mono
+        let reader = read_wav_from_file_path(&path)
+            .unwrap()
+            .get_stream_f32_reader()
+            .unwrap();
+        let err = split_stereo(
+            reader,
+            write_wav_to_file_path(&left_path, header).unwrap(),
+            write_wav_to_file_path(&right_path, mono_header).unwrap(),
+        )
+        .expect_err("The left output is stereo");
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+    }
+}
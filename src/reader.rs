@@ -1,7 +1,52 @@
-use std::io::{Error, ErrorKind, Read, Result};
+use std::io::{self, Error, ErrorKind, Read, Result};
 use std::str;
 
-use crate::upconvert::{i16_to_f32, i16_to_i24, i24_to_f32, i8_to_f32, i8_to_i16, i8_to_i24};
+use crate::upconvert::{
+    i16_to_f32, i16_to_i24, i24_to_f32, i32_to_i24, i64_to_f32, i64_to_f64, i8_to_f32, i8_to_i16,
+    i8_to_i24,
+};
+
+/// Compares a 4-character id, (IE, the RIFF form type, or a chunk id,) ignoring case and leading or trailing spaces
+/// and nulls. Some broken encoders write "wave" instead of "WAVE"
+///
+/// # Arguments
+///
+/// * 'actual' - The id that was read
+/// * 'expected' - The id that the wav should have. (IE, "WAVE")
+pub(crate) fn lenient_id_matches(actual: &[u8; 4], expected: &str) -> bool {
+    let trim = |c: char| c.is_ascii_whitespace() || c == '\0';
+    String::from_utf8_lossy(actual)
+        .trim_matches(trim)
+        .eq_ignore_ascii_case(expected.trim_matches(trim))
+}
+
+/// Keeps a copy of the bytes that are read, (IE, to keep a chunk exactly as it was read,) while they are parsed
+pub(crate) struct RecordingReader<'a, TReader: Read> {
+    inner: &'a mut TReader,
+    recorded: Vec<u8>,
+}
+
+impl<'a, TReader: Read> RecordingReader<'a, TReader> {
+    pub(crate) fn new(inner: &'a mut TReader) -> RecordingReader<'a, TReader> {
+        RecordingReader {
+            inner,
+            recorded: Vec::new(),
+        }
+    }
+
+    /// The bytes that were read
+    pub(crate) fn into_recorded(self) -> Vec<u8> {
+        self.recorded
+    }
+}
+
+impl<TReader: Read> Read for RecordingReader<'_, TReader> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let bytes_read = self.inner.read(buf)?;
+        self.recorded.extend_from_slice(&buf[..bytes_read]);
+        Ok(bytes_read)
+    }
+}
 
 /// Convenience methods for reading from a stream
 pub trait ReadEx: Read {
@@ -16,11 +61,16 @@ pub trait ReadEx: Read {
     fn read_i8_as_i16(&mut self) -> Result<i16>;
     fn read_u16(&mut self) -> Result<u16>;
     fn read_f32(&mut self) -> Result<f32>;
+    fn read_f32_be(&mut self) -> Result<f32>;
     fn read_i8(&mut self) -> Result<i8>;
+    fn read_u8(&mut self) -> Result<u8>;
     fn read_i24(&mut self) -> Result<i32>;
     fn read_i24_as_f32(&mut self) -> Result<f32>;
     fn read_i16_as_f32(&mut self) -> Result<f32>;
     fn read_i8_as_f32(&mut self) -> Result<f32>;
+    fn read_i64(&mut self) -> Result<i64>;
+    fn read_i64_as_f32(&mut self) -> Result<f32>;
+    fn read_i64_as_f64(&mut self) -> Result<f64>;
 }
 
 impl<T> ReadEx for T
@@ -28,20 +78,9 @@ where
     T: Read,
 {
     fn skip(&mut self, length: usize) -> Result<()> {
-        if length > 0 {
-            let mut buf = [0u8];
-            for _ in 0..length {
-                self.read_exact(&mut buf[..])?;
-            }
-        }
-
-        Ok(())
-    }
+        let skipped = io::copy(&mut self.by_ref().take(length as u64), &mut io::sink())?;
 
-    fn read_fixed_size(&mut self, buf: &mut [u8]) -> Result<()> {
-        let bytes_read = self.read(buf)?;
-
-        if bytes_read == buf.len() {
+        if skipped == length as u64 {
             Ok(())
         } else {
             Err(Error::new(
@@ -51,6 +90,26 @@ where
         }
     }
 
+    fn read_fixed_size(&mut self, buf: &mut [u8]) -> Result<()> {
+        // A single read can return fewer bytes than requested, IE, at the end of a buffer
+        let mut bytes_read = 0;
+        while bytes_read < buf.len() {
+            match self.read(&mut buf[bytes_read..]) {
+                Ok(0) => {
+                    return Err(Error::new(
+                        ErrorKind::UnexpectedEof,
+                        "Unexpected end of file",
+                    ))
+                }
+                Ok(read) => bytes_read += read,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(())
+    }
+
     fn read_str(&mut self, len: usize) -> Result<String> {
         let mut buf = vec![0u8; len];
         self.read_fixed_size(&mut buf[..])?;
@@ -114,6 +173,13 @@ where
         Ok(f32::from_le_bytes(buf))
     }
 
+    fn read_f32_be(&mut self) -> Result<f32> {
+        let mut buf = [0u8; 4];
+        self.read_fixed_size(&mut buf[..])?;
+
+        Ok(f32::from_be_bytes(buf))
+    }
+
     fn read_i8(&mut self) -> Result<i8> {
         let mut buf = [0u8; 1];
         self.read_fixed_size(&mut buf[..])?;
@@ -121,12 +187,19 @@ where
         Ok(i8::from_le_bytes(buf))
     }
 
+    fn read_u8(&mut self) -> Result<u8> {
+        let mut buf = [0u8; 1];
+        self.read_fixed_size(&mut buf[..])?;
+
+        Ok(buf[0])
+    }
+
     fn read_i24(&mut self) -> Result<i32> {
         let mut buf = [0u8; 3];
         self.read_fixed_size(&mut buf[..])?;
 
         let buf = [0, buf[0], buf[1], buf[2]];
-        Ok(i32::from_le_bytes(buf) >> 8)
+        i32_to_i24(i32::from_le_bytes(buf))
     }
 
     fn read_i24_as_f32(&mut self) -> Result<f32> {
@@ -143,4 +216,21 @@ where
         let sample_int_8 = self.read_i8()?;
         return i8_to_f32(sample_int_8);
     }
+
+    fn read_i64(&mut self) -> Result<i64> {
+        let mut buf = [0u8; 8];
+        self.read_fixed_size(&mut buf[..])?;
+
+        Ok(i64::from_le_bytes(buf))
+    }
+
+    fn read_i64_as_f32(&mut self) -> Result<f32> {
+        let sample_int_64 = self.read_i64()?;
+        i64_to_f32(sample_int_64)
+    }
+
+    fn read_i64_as_f64(&mut self) -> Result<f64> {
+        let sample_int_64 = self.read_i64()?;
+        i64_to_f64(sample_int_64)
+    }
 }
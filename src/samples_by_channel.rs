@@ -1,3 +1,7 @@
+use std::io::{Error, ErrorKind, Result};
+
+use crate::wave_header::{ChannelPosition, Channels};
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct SamplesByChannel<T> {
     pub front_left: Option<T>,
@@ -20,30 +24,7 @@ pub struct SamplesByChannel<T> {
     pub top_back_right: Option<T>,
 }
 
-impl<T: Copy> SamplesByChannel<T> {
-    pub fn new() -> SamplesByChannel<T> {
-        SamplesByChannel {
-            front_left: None,
-            front_right: None,
-            front_center: None,
-            low_frequency: None,
-            back_left: None,
-            back_right: None,
-            front_left_of_center: None,
-            front_right_of_center: None,
-            back_center: None,
-            side_left: None,
-            side_right: None,
-            top_center: None,
-            top_front_left: None,
-            top_front_center: None,
-            top_front_right: None,
-            top_back_left: None,
-            top_back_center: None,
-            top_back_right: None,
-        }
-    }
-
+impl<T> SamplesByChannel<T> {
     pub fn front_left(mut self, sample: T) -> SamplesByChannel<T> {
         self.front_left = Some(sample);
 
@@ -151,7 +132,9 @@ impl<T: Copy> SamplesByChannel<T> {
 
         self
     }
+}
 
+impl<T: Copy> SamplesByChannel<T> {
     pub fn to_vec(&self) -> Vec<T> {
         let mut vec = Vec::new();
 
@@ -248,3 +231,292 @@ impl<T: Copy> SamplesByChannel<T> {
         vec
     }
 }
+
+impl<T> Default for SamplesByChannel<T> {
+    fn default() -> Self {
+        SamplesByChannel::new()
+    }
+}
+
+impl<T> SamplesByChannel<T> {
+    pub fn new() -> SamplesByChannel<T> {
+        SamplesByChannel {
+            front_left: None,
+            front_right: None,
+            front_center: None,
+            low_frequency: None,
+            back_left: None,
+            back_right: None,
+            front_left_of_center: None,
+            front_right_of_center: None,
+            back_center: None,
+            side_left: None,
+            side_right: None,
+            top_center: None,
+            top_front_left: None,
+            top_front_center: None,
+            top_front_right: None,
+            top_back_left: None,
+            top_back_center: None,
+            top_back_right: None,
+        }
+    }
+
+    /// Starts building samples that must have exactly the given channels. Unlike setting the fields directly,
+    /// build() fails if a channel is missing, or if a channel is set that isn't in 'channels'
+    ///
+    /// # Arguments
+    ///
+    /// * 'channels' - The channels that must be present. (IE, the wav's channels)
+    pub fn builder(channels: &Channels) -> SamplesByChannelBuilder<T> {
+        SamplesByChannelBuilder {
+            channels: *channels,
+            samples: SamplesByChannel::new(),
+            extra_channel: None,
+        }
+    }
+
+    /// The sample for the channel at the given position, or None if the channel isn't present
+    pub fn get(&self, position: ChannelPosition) -> Option<&T> {
+        match position {
+            ChannelPosition::FrontLeft => self.front_left.as_ref(),
+            ChannelPosition::FrontRight => self.front_right.as_ref(),
+            ChannelPosition::FrontCenter => self.front_center.as_ref(),
+            ChannelPosition::LowFrequency => self.low_frequency.as_ref(),
+            ChannelPosition::BackLeft => self.back_left.as_ref(),
+            ChannelPosition::BackRight => self.back_right.as_ref(),
+            ChannelPosition::FrontLeftOfCenter => self.front_left_of_center.as_ref(),
+            ChannelPosition::FrontRightOfCenter => self.front_right_of_center.as_ref(),
+            ChannelPosition::BackCenter => self.back_center.as_ref(),
+            ChannelPosition::SideLeft => self.side_left.as_ref(),
+            ChannelPosition::SideRight => self.side_right.as_ref(),
+            ChannelPosition::TopCenter => self.top_center.as_ref(),
+            ChannelPosition::TopFrontLeft => self.top_front_left.as_ref(),
+            ChannelPosition::TopFrontCenter => self.top_front_center.as_ref(),
+            ChannelPosition::TopFrontRight => self.top_front_right.as_ref(),
+            ChannelPosition::TopBackLeft => self.top_back_left.as_ref(),
+            ChannelPosition::TopBackCenter => self.top_back_center.as_ref(),
+            ChannelPosition::TopBackRight => self.top_back_right.as_ref(),
+        }
+    }
+
+    /// The sample for the channel at the given position, for modification, or None if the channel isn't present
+    pub fn get_mut(&mut self, position: ChannelPosition) -> Option<&mut T> {
+        match position {
+            ChannelPosition::FrontLeft => self.front_left.as_mut(),
+            ChannelPosition::FrontRight => self.front_right.as_mut(),
+            ChannelPosition::FrontCenter => self.front_center.as_mut(),
+            ChannelPosition::LowFrequency => self.low_frequency.as_mut(),
+            ChannelPosition::BackLeft => self.back_left.as_mut(),
+            ChannelPosition::BackRight => self.back_right.as_mut(),
+            ChannelPosition::FrontLeftOfCenter => self.front_left_of_center.as_mut(),
+            ChannelPosition::FrontRightOfCenter => self.front_right_of_center.as_mut(),
+            ChannelPosition::BackCenter => self.back_center.as_mut(),
+            ChannelPosition::SideLeft => self.side_left.as_mut(),
+            ChannelPosition::SideRight => self.side_right.as_mut(),
+            ChannelPosition::TopCenter => self.top_center.as_mut(),
+            ChannelPosition::TopFrontLeft => self.top_front_left.as_mut(),
+            ChannelPosition::TopFrontCenter => self.top_front_center.as_mut(),
+            ChannelPosition::TopFrontRight => self.top_front_right.as_mut(),
+            ChannelPosition::TopBackLeft => self.top_back_left.as_mut(),
+            ChannelPosition::TopBackCenter => self.top_back_center.as_mut(),
+            ChannelPosition::TopBackRight => self.top_back_right.as_mut(),
+        }
+    }
+
+    /// Verifies that exactly the given channels are present. Returns an error that names the sample index and the
+    /// missing or extra channel otherwise
+    ///
+    /// # Arguments
+    ///
+    /// * 'channels' - The channels that must be present
+    /// * 'sample' - The index of the sample, used in the error
+    pub(crate) fn check_channels(&self, channels: &Channels, sample: usize) -> Result<()> {
+        for position in Channels::all_positions() {
+            match (channels.contains(position), self.get(position).is_some()) {
+                (true, false) => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Sample {sample}: {position:?} channel missing"),
+                    ))
+                }
+                (false, true) => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Sample {sample}: {position:?} channel is not in the wav"),
+                    ))
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sets the sample for the channel at the given position
+    pub fn set(&mut self, position: ChannelPosition, sample: T) {
+        match position {
+            ChannelPosition::FrontLeft => self.front_left = Some(sample),
+            ChannelPosition::FrontRight => self.front_right = Some(sample),
+            ChannelPosition::FrontCenter => self.front_center = Some(sample),
+            ChannelPosition::LowFrequency => self.low_frequency = Some(sample),
+            ChannelPosition::BackLeft => self.back_left = Some(sample),
+            ChannelPosition::BackRight => self.back_right = Some(sample),
+            ChannelPosition::FrontLeftOfCenter => self.front_left_of_center = Some(sample),
+            ChannelPosition::FrontRightOfCenter => self.front_right_of_center = Some(sample),
+            ChannelPosition::BackCenter => self.back_center = Some(sample),
+            ChannelPosition::SideLeft => self.side_left = Some(sample),
+            ChannelPosition::SideRight => self.side_right = Some(sample),
+            ChannelPosition::TopCenter => self.top_center = Some(sample),
+            ChannelPosition::TopFrontLeft => self.top_front_left = Some(sample),
+            ChannelPosition::TopFrontCenter => self.top_front_center = Some(sample),
+            ChannelPosition::TopFrontRight => self.top_front_right = Some(sample),
+            ChannelPosition::TopBackLeft => self.top_back_left = Some(sample),
+            ChannelPosition::TopBackCenter => self.top_back_center = Some(sample),
+            ChannelPosition::TopBackRight => self.top_back_right = Some(sample),
+        }
+    }
+}
+
+/// Builds a SamplesByChannel that is validated against a channel layout. Created with SamplesByChannel::builder()
+pub struct SamplesByChannelBuilder<T> {
+    channels: Channels,
+    samples: SamplesByChannel<T>,
+    // The first channel that was set, but isn't in the layout
+    extra_channel: Option<ChannelPosition>,
+}
+
+impl<T> SamplesByChannelBuilder<T> {
+    /// Sets the sample for the channel at the given position. If the channel isn't in the layout, build() fails
+    ///
+    /// # Arguments
+    ///
+    /// * 'position' - The channel to set
+    /// * 'sample' - The sample
+    pub fn set(mut self, position: ChannelPosition, sample: T) -> SamplesByChannelBuilder<T> {
+        if !self.channels.contains(position) && self.extra_channel.is_none() {
+            self.extra_channel = Some(position);
+        }
+
+        self.samples.set(position, sample);
+        self
+    }
+
+    /// Returns the samples, or an error if a channel in the layout wasn't set, or a channel that isn't in the
+    /// layout was set
+    pub fn build(self) -> Result<SamplesByChannel<T>> {
+        if let Some(position) = self.extra_channel {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{position:?} channel is not in the layout"),
+            ));
+        }
+
+        if let Some(position) = self
+            .channels
+            .positions()
+            .find(|position| self.samples.get(*position).is_none())
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{position:?} channel missing"),
+            ));
+        }
+
+        Ok(self.samples)
+    }
+}
+
+/// The level that silence is reported at, in dBFS
+pub const DBFS_FLOOR: f32 = -120.0;
+
+impl SamplesByChannel<f32> {
+    /// Converts each channel to decibels relative to full scale, (dBFS,) using the absolute value of the sample.
+    /// Silence is reported as DBFS_FLOOR
+    pub fn to_dbfs(&self) -> SamplesByChannel<f32> {
+        let mut dbfs = SamplesByChannel::new();
+
+        for position in Channels::all_positions() {
+            if let Some(sample) = self.get(position) {
+                let level = 20.0 * sample.abs().log10();
+                dbfs.set(position, level.max(DBFS_FLOOR));
+            }
+        }
+
+        dbfs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_has_no_samples() {
+        let samples: SamplesByChannel<f32> = SamplesByChannel::default();
+        assert_eq!(SamplesByChannel::new(), samples);
+        assert!(samples.to_vec().is_empty());
+    }
+
+    #[test]
+    fn builder_sets_all_channels() {
+        let channels = Channels::new().front_left().front_right().low_frequency();
+
+        let samples = SamplesByChannel::builder(&channels)
+            .set(ChannelPosition::FrontLeft, 1)
+            .set(ChannelPosition::FrontRight, 2)
+            .set(ChannelPosition::LowFrequency, 3)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            SamplesByChannel::new()
+                .front_left(1)
+                .front_right(2)
+                .low_frequency(3),
+            samples
+        );
+    }
+
+    #[test]
+    fn builder_missing_channel() {
+        let channels = Channels::new().front_left().front_right();
+
+        let err = SamplesByChannel::builder(&channels)
+            .set(ChannelPosition::FrontLeft, 1)
+            .build()
+            .expect_err("front_right is missing");
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+        assert!(err.to_string().contains("FrontRight"), "{err}");
+    }
+
+    #[test]
+    fn builder_extra_channel() {
+        let channels = Channels::new().front_left();
+
+        let err = SamplesByChannel::builder(&channels)
+            .set(ChannelPosition::FrontLeft, 1)
+            .set(ChannelPosition::SideLeft, 2)
+            .build()
+            .expect_err("side_left isn't in the layout");
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+        assert!(err.to_string().contains("SideLeft"), "{err}");
+    }
+
+    #[test]
+    fn to_dbfs_sanity() {
+        let dbfs = SamplesByChannel::new()
+            .front_left(1.0)
+            .front_right(0.5)
+            .low_frequency(0.0)
+            .to_dbfs();
+
+        assert_eq!(Some(0.0), dbfs.front_left);
+        assert!((dbfs.front_right.unwrap() + 6.0206).abs() < 0.001);
+        assert_eq!(Some(DBFS_FLOOR), dbfs.low_frequency);
+        assert_eq!(None, dbfs.front_center);
+
+        let dbfs = SamplesByChannel::new().front_left(-0.5f32).to_dbfs();
+        assert!((dbfs.front_left.unwrap() + 6.0206).abs() < 0.001);
+    }
+}
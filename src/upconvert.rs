@@ -8,6 +8,9 @@ pub const INT_24_DIVIDE_FOR_FLOAT: f32 = 8388607.5;
 pub const INT_16_ADD_FOR_FLOAT_ABS: f32 = 32768.0;
 pub const INT_16_DIVIDE_FOR_FLOAT: f32 = 32767.5;
 
+pub const INT_64_ADD_FOR_FLOAT_ABS: f64 = 9223372036854775808.0;
+pub const INT_64_DIVIDE_FOR_FLOAT: f64 = 9223372036854775807.5;
+
 pub const INT_8_ADD_FOR_FLOAT_ABS: f32 = 128.0;
 pub const INT_8_DIVIDE_FOR_FLOAT: f32 = 127.5;
 
@@ -19,6 +22,19 @@ pub fn i24_to_f32(sample_i24: i32) -> Result<f32> {
     Ok((sample_i24_abs / INT_24_DIVIDE_FOR_FLOAT) - 1.0)
 }
 
+// Converted through f64, because f32 can't hold INT_64_ADD_FOR_FLOAT_ABS precisely enough
+pub fn i64_to_f32(sample_i64: i64) -> Result<f32> {
+    let sample_i64_as_float = sample_i64 as f64;
+    let sample_i64_abs = sample_i64_as_float + INT_64_ADD_FOR_FLOAT_ABS;
+    Ok(((sample_i64_abs / INT_64_DIVIDE_FOR_FLOAT) - 1.0) as f32)
+}
+
+pub fn i64_to_f64(sample_i64: i64) -> Result<f64> {
+    let sample_i64_as_float = sample_i64 as f64;
+    let sample_i64_abs = sample_i64_as_float + INT_64_ADD_FOR_FLOAT_ABS;
+    Ok((sample_i64_abs / INT_64_DIVIDE_FOR_FLOAT) - 1.0)
+}
+
 pub fn i16_to_f32(sample_i16: i16) -> Result<f32> {
     let sample_i16_as_float = sample_i16 as f32;
     let sample_i16_abs = sample_i16_as_float + INT_16_ADD_FOR_FLOAT_ABS;
@@ -31,6 +47,19 @@ pub fn i8_to_f32(sample_i8: i8) -> Result<f32> {
     Ok((sample_i8_abs / INT_8_DIVIDE_FOR_FLOAT) - 1.0)
 }
 
+/// How integer samples are converted to a higher bit depth. (IE, when a 16-bit wav is read as 24-bit samples)
+/// Conversion to floating point is not affected
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum RoundingMode {
+    /// Positive samples are padded with ones and negative samples are padded with zeros, so that both the largest
+    /// and the smallest samples stay at full scale. (IE, 32767 becomes 8388607 when converting from 16-bit to 24-bit)
+    #[default]
+    FullScale,
+    /// All samples are padded with zeros, (IE, shifted,) which is how most other libraries convert. The largest
+    /// sample ends up slightly below full scale. (IE, 32767 becomes 8388352 when converting from 16-bit to 24-bit)
+    Shift,
+}
+
 pub fn i16_to_i24(sample_i16: i16) -> Result<i32> {
     let sample_i32 = sample_i16 as i32;
 
@@ -73,6 +102,31 @@ pub fn i8_to_i16(sample_i8: i8) -> Result<i16> {
     Ok(sample_i16 as i16)
 }
 
+pub fn i16_to_i24_shifted(sample_i16: i16) -> i32 {
+    (sample_i16 as i32) << 8
+}
+
+pub fn i8_to_i24_shifted(sample_i8: i8) -> i32 {
+    (sample_i8 as i32) << 16
+}
+
+pub fn i8_to_i16_shifted(sample_i8: i8) -> i16 {
+    (sample_i8 as i16) << 8
+}
+
+/// Moves a 24-bit sample, stored in the low bits of an i32, into the high bits so that it is a true i32 sample
+pub fn i24_to_i32(sample_i24: i32) -> i32 {
+    sample_i24 << 8
+}
+
+/// Moves a true i32 sample into the low bits of an i32, so that it is a 24-bit sample. The lowest 8 bits are discarded
+pub fn i32_to_i24(sample_i32: i32) -> Result<i32> {
+    let sample_i24 = sample_i32 >> 8;
+    assert_int_24(sample_i24)?;
+
+    Ok(sample_i24)
+}
+
 #[cfg(test)]
 mod tests {
     use test_case::test_case;
@@ -158,4 +212,58 @@ mod tests {
         let actual_sample_i16 = i8_to_i16(sample_i8).expect("Error converting sample to i16");
         assert_eq!(actual_sample_i16, expected_sample_i16);
     }
+
+    #[test_case(i16::MAX, MAX_INT_24, MAX_INT_24 - 255; "i16_i24_max")]
+    #[test_case(i16::MIN, MIN_INT_24, MIN_INT_24; "i16_i24_min")]
+    #[test_case(0, 255, 0; "i16_i24_zero")]
+    #[test_case(1, 511, 256; "i16_i24_smallest_positive")]
+    #[test_case(-1, -256, -256; "i16_i24_smallest_negative")]
+    fn i16_to_i24_rounding_modes(sample_i16: i16, full_scale: i32, shifted: i32) {
+        assert_eq!(full_scale, i16_to_i24(sample_i16).unwrap());
+        assert_eq!(shifted, i16_to_i24_shifted(sample_i16));
+    }
+
+    #[test_case(i8::MAX, MAX_INT_24, MAX_INT_24 - 65535, i16::MAX, i16::MAX - 255; "i8_max")]
+    #[test_case(i8::MIN, MIN_INT_24, MIN_INT_24, i16::MIN, i16::MIN; "i8_min")]
+    #[test_case(0, 65535, 0, 255, 0; "i8_zero")]
+    #[test_case(-1, -65536, -65536, -256, -256; "i8_smallest_negative")]
+    fn i8_rounding_modes(
+        sample_i8: i8,
+        full_scale_i24: i32,
+        shifted_i24: i32,
+        full_scale_i16: i16,
+        shifted_i16: i16,
+    ) {
+        assert_eq!(full_scale_i24, i8_to_i24(sample_i8).unwrap());
+        assert_eq!(shifted_i24, i8_to_i24_shifted(sample_i8));
+        assert_eq!(full_scale_i16, i8_to_i16(sample_i8).unwrap());
+        assert_eq!(shifted_i16, i8_to_i16_shifted(sample_i8));
+    }
+
+    #[test_case(MAX_INT_24, 0x7FFFFF00; "i24_i32_max")]
+    #[test_case(MIN_INT_24, i32::MIN; "i24_i32_min")]
+    #[test_case(MAX_INT_24 / 2, 0x3FFFFF00; "i24_i32_half")]
+    #[test_case(MIN_INT_24 / 2, i32::MIN / 2; "i24_i32_half_negative")]
+    #[test_case(0, 0; "i24_i32_zero")]
+    #[test_case(1, 256; "i24_i32_smallest_positive")]
+    #[test_case(-1, -256; "i24_i32_smallest_negative")]
+    fn i24_to_i32_test(sample_i24: i32, expected_sample_i32: i32) {
+        let actual_sample_i32 = i24_to_i32(sample_i24);
+        assert_eq!(actual_sample_i32, expected_sample_i32);
+
+        let round_trip_sample_i24 =
+            i32_to_i24(actual_sample_i32).expect("Error converting sample to i24");
+        assert_eq!(round_trip_sample_i24, sample_i24, "Not lossless");
+    }
+
+    #[test_case(i32::MAX, MAX_INT_24; "i32_i24_max")]
+    #[test_case(i32::MIN, MIN_INT_24; "i32_i24_min")]
+    #[test_case(i32::MAX / 2, MAX_INT_24 / 2; "i32_i24_half")]
+    #[test_case(i32::MIN / 2, MIN_INT_24 / 2; "i32_i24_half_negative")]
+    #[test_case(255, 0; "i32_i24_truncate_positive")]
+    #[test_case(-1, -1; "i32_i24_truncate_negative")]
+    fn i32_to_i24_test(sample_i32: i32, expected_sample_i24: i32) {
+        let actual_sample_i24 = i32_to_i24(sample_i32).expect("Error converting sample to i24");
+        assert_eq!(actual_sample_i24, expected_sample_i24);
+    }
 }
@@ -0,0 +1,121 @@
+//! A wav that is held entirely in memory
+//!
+//! Note: Wav loads every sample into memory, which is contrary to how the rest of wave_stream works. It's only
+//! intended as a convenience for small files. Use the streaming and random-access readers and writers for large
+//! files.
+
+use std::io::Result;
+use std::path::Path;
+
+use crate::open_wav::OpenWav;
+use crate::samples_by_channel::SamplesByChannel;
+use crate::wave_header::{SampleFormat, WavHeader};
+use crate::wave_reader::StreamOpenWavReader;
+use crate::{read_wav_from_file_path, write_wav_to_file_path};
+
+/// A wav whose samples are all held in memory as floats
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wav {
+    header: WavHeader,
+    samples: Vec<SamplesByChannel<f32>>,
+}
+
+impl Wav {
+    /// Creates a wav from samples that are already in memory
+    ///
+    /// # Arguments
+    ///
+    /// * 'header' - The wav's header. (save() always writes floats, regardless of the sample format)
+    /// * 'samples' - The samples. Each sample must have exactly the channels in the header
+    pub fn new(header: WavHeader, samples: Vec<SamplesByChannel<f32>>) -> Wav {
+        Wav { header, samples }
+    }
+
+    /// Reads all of a wav's samples into memory
+    ///
+    /// # Arguments
+    ///
+    /// * 'file_path' - The path to the wav file to read
+    pub fn load(file_path: &Path) -> Result<Wav> {
+        let open_wav = read_wav_from_file_path(file_path)?;
+        let header = *open_wav.header();
+
+        let mut samples = Vec::with_capacity(open_wav.len_samples());
+        for samples_result in open_wav.get_stream_f32_reader()? {
+            samples.push(samples_result?);
+        }
+
+        Ok(Wav { header, samples })
+    }
+
+    /// Writes the wav. Samples are always written as floats, because converting to a lower bit depth would
+    /// require dithering
+    ///
+    /// # Arguments
+    ///
+    /// * 'file_path' - The path to where the wav will be written
+    pub fn save(&self, file_path: &Path) -> Result<()> {
+        let header = WavHeader {
+            sample_format: SampleFormat::Float,
+            ..self.header
+        };
+
+        let open_wav = write_wav_to_file_path(file_path, header)?;
+        open_wav.write_all_f32(self.samples.iter().map(|samples| Ok(*samples)))
+    }
+
+    /// The wav's header. The sample format is the format that the wav was loaded from
+    pub fn header(&self) -> &WavHeader {
+        &self.header
+    }
+
+    /// The wav's samples
+    pub fn samples(&self) -> &[SamplesByChannel<f32>] {
+        &self.samples
+    }
+
+    /// The wav's samples, for modification
+    pub fn samples_mut(&mut self) -> &mut Vec<SamplesByChannel<f32>> {
+        &mut self.samples
+    }
+
+    /// The number of samples in the wav
+    pub fn len_samples(&self) -> usize {
+        self.samples.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::wave_header::Channels;
+
+    #[test]
+    fn load_16() {
+        let wav = Wav::load(Path::new("test_data/short_16.wav")).unwrap();
+        assert_eq!(SampleFormat::Int16, wav.header().sample_format);
+        assert_eq!(Channels::new().front_left(), wav.header().channels);
+        assert_eq!(
+            read_wav_from_file_path(Path::new("test_data/short_16.wav"))
+                .unwrap()
+                .len_samples(),
+            wav.len_samples()
+        );
+    }
+
+    #[test]
+    fn modify_and_save() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("modified.wav");
+
+        let mut wav = Wav::load(Path::new("test_data/short_float.wav")).unwrap();
+        wav.samples_mut()[10] = SamplesByChannel::new().front_left(0.75);
+        wav.save(&path).unwrap();
+
+        let saved = Wav::load(&path).unwrap();
+        assert_eq!(wav, saved);
+        assert_eq!(Some(0.75), saved.samples()[10].front_left);
+    }
+}
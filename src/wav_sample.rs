@@ -0,0 +1,519 @@
+//! The types of samples that can be read from, and written into, a wav
+
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+use crate::reader::ReadEx;
+use crate::upconvert::{
+    i16_to_f32, i16_to_i24, i16_to_i24_shifted, i24_to_f32, i24_to_i32, i32_to_i24, i64_to_f32,
+    i64_to_f64, i8_to_f32, i8_to_i16, i8_to_i16_shifted, i8_to_i24, i8_to_i24_shifted,
+    RoundingMode,
+};
+use crate::wave_header::SampleFormat;
+use crate::writer::WriteEx;
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for i8 {}
+    impl Sealed for i16 {}
+    impl Sealed for i32 {}
+    impl Sealed for i64 {}
+    impl Sealed for f32 {}
+    impl Sealed for f64 {}
+}
+
+/// Reads a single sample from a stream
+pub type ReadSampleFn<T> = fn(&mut dyn Read) -> Result<T>;
+
+/// Writes a single sample into a stream
+pub type WriteSampleFn<T> = fn(&mut dyn Write, T) -> Result<()>;
+
+/// A type of sample: i8, i16, i32, (24-bit,) i64, f32, or f64. (f64 is written as f32.) Used to select how samples are read and written from the type
+/// of the samples. (IE, open_wav.get_random_access_reader::<f32>())
+pub trait WavSample: sealed::Sealed + Copy + 'static {
+    /// The sample format that stores this type without conversion
+    const SAMPLE_FORMAT: SampleFormat;
+
+    /// Selects how samples that are stored in 'sample_format' are read as this type. Returns an error if the
+    /// conversion requires downsampling
+    ///
+    /// # Arguments
+    ///
+    /// * 'sample_format' - The sample format in the wav
+    fn read_from(sample_format: SampleFormat) -> Result<ReadSampleFn<Self>>;
+
+    /// Like read_from, but integer samples that are converted to a higher bit depth are converted with
+    /// 'rounding_mode'. (read_from always uses RoundingMode::FullScale)
+    ///
+    /// # Arguments
+    ///
+    /// * 'sample_format' - The sample format in the wav
+    /// * 'rounding_mode' - How integer samples are converted to a higher bit depth
+    fn read_from_with_rounding(
+        sample_format: SampleFormat,
+        _rounding_mode: RoundingMode,
+    ) -> Result<ReadSampleFn<Self>> {
+        Self::read_from(sample_format)
+    }
+
+    /// Selects how samples of this type are written into a wav that stores 'sample_format'. Returns an error if the
+    /// conversion requires downsampling
+    ///
+    /// # Arguments
+    ///
+    /// * 'sample_format' - The sample format in the wav
+    fn write_to(sample_format: SampleFormat) -> Result<WriteSampleFn<Self>>;
+
+    /// Decodes whole samples from 'bytes', which hold little-endian samples that are stored in 'sample_format', into
+    /// 'out'. Unlike read_from, the sample format is matched once for all of 'bytes', instead of calling a
+    /// ReadSampleFn for each sample. Returns an error if the conversion requires downsampling
+    #[doc(hidden)]
+    fn decode_into(
+        sample_format: SampleFormat,
+        rounding_mode: RoundingMode,
+        bytes: &[u8],
+        out: &mut Vec<Self>,
+    ) -> Result<()>;
+
+    /// Returns true if samples that are stored in 'sample_format' can be read as this type
+    ///
+    /// # Arguments
+    ///
+    /// * 'sample_format' - The sample format in the wav
+    fn upconvert_from(sample_format: SampleFormat) -> bool {
+        Self::read_from(sample_format).is_ok()
+    }
+}
+
+impl WavSample for i8 {
+    const SAMPLE_FORMAT: SampleFormat = SampleFormat::Int8;
+
+    fn read_from(sample_format: SampleFormat) -> Result<ReadSampleFn<i8>> {
+        match sample_format {
+            SampleFormat::Int8 => Ok(|mut reader: &mut dyn Read| reader.read_i8()),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                "Converting to 8-bit unsupported",
+            )),
+        }
+    }
+
+    fn write_to(sample_format: SampleFormat) -> Result<WriteSampleFn<i8>> {
+        match sample_format {
+            SampleFormat::Int8 => {
+                Ok(|mut writer: &mut dyn Write, value: i8| writer.write_i8(value))
+            }
+            SampleFormat::Int16 => {
+                Ok(|mut writer: &mut dyn Write, value: i8| writer.write_i8_as_i16(value))
+            }
+            SampleFormat::Int24 => {
+                Ok(|mut writer: &mut dyn Write, value: i8| writer.write_i8_as_i24(value))
+            }
+            SampleFormat::Float => {
+                Ok(|mut writer: &mut dyn Write, value: i8| writer.write_i8_as_f32(value))
+            }
+            SampleFormat::ImaAdpcm { .. } => Err(Error::new(
+                ErrorKind::Unsupported,
+                "Writing IMA ADPCM is unsupported",
+            )),
+            SampleFormat::Int64 => Err(Error::new(
+                ErrorKind::InvalidData,
+                "Converting to 64-bit int unsupported",
+            )),
+        }
+    }
+
+    fn decode_into(
+        sample_format: SampleFormat,
+        _rounding_mode: RoundingMode,
+        bytes: &[u8],
+        out: &mut Vec<i8>,
+    ) -> Result<()> {
+        match sample_format {
+            SampleFormat::Int8 => decode_each(bytes, out, |b: [u8; 1]| Ok(b[0] as i8)),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                "Converting to 8-bit unsupported",
+            )),
+        }
+    }
+}
+
+impl WavSample for i16 {
+    const SAMPLE_FORMAT: SampleFormat = SampleFormat::Int16;
+
+    fn read_from(sample_format: SampleFormat) -> Result<ReadSampleFn<i16>> {
+        match sample_format {
+            SampleFormat::Int8 => Ok(|mut reader: &mut dyn Read| reader.read_i8_as_i16()),
+            // IMA ADPCM is decoded to 16-bit before it's read
+            SampleFormat::Int16 | SampleFormat::ImaAdpcm { .. } => {
+                Ok(|mut reader: &mut dyn Read| reader.read_i16())
+            }
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                "Converting to 16-bit unsupported",
+            )),
+        }
+    }
+
+    fn read_from_with_rounding(
+        sample_format: SampleFormat,
+        rounding_mode: RoundingMode,
+    ) -> Result<ReadSampleFn<i16>> {
+        match (sample_format, rounding_mode) {
+            (SampleFormat::Int8, RoundingMode::Shift) => {
+                Ok(|mut reader: &mut dyn Read| Ok(i8_to_i16_shifted(reader.read_i8()?)))
+            }
+            _ => Self::read_from(sample_format),
+        }
+    }
+
+    fn write_to(sample_format: SampleFormat) -> Result<WriteSampleFn<i16>> {
+        match sample_format {
+            SampleFormat::Int16 => {
+                Ok(|mut writer: &mut dyn Write, value: i16| writer.write_i16(value))
+            }
+            SampleFormat::Int24 => {
+                Ok(|mut writer: &mut dyn Write, value: i16| writer.write_i16_as_i24(value))
+            }
+            SampleFormat::Float => {
+                Ok(|mut writer: &mut dyn Write, value: i16| writer.write_i16_as_f32(value))
+            }
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                "Converting to 16-bit int unsupported",
+            )),
+        }
+    }
+
+    fn decode_into(
+        sample_format: SampleFormat,
+        rounding_mode: RoundingMode,
+        bytes: &[u8],
+        out: &mut Vec<i16>,
+    ) -> Result<()> {
+        match (sample_format, rounding_mode) {
+            (SampleFormat::Int8, RoundingMode::FullScale) => {
+                decode_each(bytes, out, |b: [u8; 1]| i8_to_i16(b[0] as i8))
+            }
+            (SampleFormat::Int8, RoundingMode::Shift) => {
+                decode_each(bytes, out, |b: [u8; 1]| Ok(i8_to_i16_shifted(b[0] as i8)))
+            }
+            (SampleFormat::Int16 | SampleFormat::ImaAdpcm { .. }, _) => {
+                decode_each(bytes, out, |b: [u8; 2]| Ok(i16::from_le_bytes(b)))
+            }
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                "Converting to 16-bit unsupported",
+            )),
+        }
+    }
+}
+
+impl WavSample for i32 {
+    const SAMPLE_FORMAT: SampleFormat = SampleFormat::Int24;
+
+    fn read_from(sample_format: SampleFormat) -> Result<ReadSampleFn<i32>> {
+        match sample_format {
+            SampleFormat::Int8 => Ok(|mut reader: &mut dyn Read| reader.read_i8_as_i24()),
+            SampleFormat::Int16 | SampleFormat::ImaAdpcm { .. } => {
+                Ok(|mut reader: &mut dyn Read| reader.read_i16_as_i24())
+            }
+            SampleFormat::Int24 => Ok(|mut reader: &mut dyn Read| reader.read_i24()),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                "Converting to 24-bit unsupported",
+            )),
+        }
+    }
+
+    fn read_from_with_rounding(
+        sample_format: SampleFormat,
+        rounding_mode: RoundingMode,
+    ) -> Result<ReadSampleFn<i32>> {
+        match (sample_format, rounding_mode) {
+            (SampleFormat::Int8, RoundingMode::Shift) => {
+                Ok(|mut reader: &mut dyn Read| Ok(i8_to_i24_shifted(reader.read_i8()?)))
+            }
+            (SampleFormat::Int16 | SampleFormat::ImaAdpcm { .. }, RoundingMode::Shift) => {
+                Ok(|mut reader: &mut dyn Read| Ok(i16_to_i24_shifted(reader.read_i16()?)))
+            }
+            _ => Self::read_from(sample_format),
+        }
+    }
+
+    fn write_to(sample_format: SampleFormat) -> Result<WriteSampleFn<i32>> {
+        match sample_format {
+            SampleFormat::Int24 => {
+                Ok(|mut writer: &mut dyn Write, value: i32| writer.write_i24(value))
+            }
+            SampleFormat::Float => {
+                Ok(|mut writer: &mut dyn Write, value: i32| writer.write_i24_as_f32(value))
+            }
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                "Converting to 24-bit int unsupported",
+            )),
+        }
+    }
+
+    fn decode_into(
+        sample_format: SampleFormat,
+        rounding_mode: RoundingMode,
+        bytes: &[u8],
+        out: &mut Vec<i32>,
+    ) -> Result<()> {
+        match (sample_format, rounding_mode) {
+            (SampleFormat::Int8, RoundingMode::FullScale) => {
+                decode_each(bytes, out, |b: [u8; 1]| i8_to_i24(b[0] as i8))
+            }
+            (SampleFormat::Int8, RoundingMode::Shift) => {
+                decode_each(bytes, out, |b: [u8; 1]| Ok(i8_to_i24_shifted(b[0] as i8)))
+            }
+            (SampleFormat::Int16 | SampleFormat::ImaAdpcm { .. }, RoundingMode::FullScale) => {
+                decode_each(bytes, out, |b: [u8; 2]| i16_to_i24(i16::from_le_bytes(b)))
+            }
+            (SampleFormat::Int16 | SampleFormat::ImaAdpcm { .. }, RoundingMode::Shift) => {
+                decode_each(bytes, out, |b: [u8; 2]| {
+                    Ok(i16_to_i24_shifted(i16::from_le_bytes(b)))
+                })
+            }
+            (SampleFormat::Int24, _) => decode_each(bytes, out, i24_from_le_bytes),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                "Converting to 24-bit unsupported",
+            )),
+        }
+    }
+}
+
+/// Selects how samples that are stored in 'sample_format' are read as full-scale 32-bit integers. Unlike reading as
+/// i32, (which is 24-bit,) the samples are shifted so that they span the whole range of i32. Floating point samples
+/// can not be read as integers
+///
+/// # Arguments
+///
+/// * 'sample_format' - The sample format in the wav
+/// * 'rounding_mode' - How 8-bit and 16-bit samples are converted
+pub(crate) fn read_full_scale_i32_from(
+    sample_format: SampleFormat,
+    rounding_mode: RoundingMode,
+) -> Result<ReadSampleFn<i32>> {
+    match (sample_format, rounding_mode) {
+        (SampleFormat::Int8, RoundingMode::FullScale) => {
+            Ok(|mut reader: &mut dyn Read| Ok(i24_to_i32(reader.read_i8_as_i24()?)))
+        }
+        (SampleFormat::Int8, RoundingMode::Shift) => {
+            Ok(|mut reader: &mut dyn Read| Ok(i24_to_i32(i8_to_i24_shifted(reader.read_i8()?))))
+        }
+        (SampleFormat::Int16 | SampleFormat::ImaAdpcm { .. }, RoundingMode::FullScale) => {
+            Ok(|mut reader: &mut dyn Read| Ok(i24_to_i32(reader.read_i16_as_i24()?)))
+        }
+        (SampleFormat::Int16 | SampleFormat::ImaAdpcm { .. }, RoundingMode::Shift) => {
+            Ok(|mut reader: &mut dyn Read| Ok(i24_to_i32(i16_to_i24_shifted(reader.read_i16()?))))
+        }
+        (SampleFormat::Int24, _) => {
+            Ok(|mut reader: &mut dyn Read| Ok(i24_to_i32(reader.read_i24()?)))
+        }
+        (SampleFormat::Float | SampleFormat::Int64, _) => Err(Error::new(
+            ErrorKind::InvalidData,
+            "Converting to 32-bit int unsupported",
+        )),
+    }
+}
+
+impl WavSample for f32 {
+    const SAMPLE_FORMAT: SampleFormat = SampleFormat::Float;
+
+    fn read_from(sample_format: SampleFormat) -> Result<ReadSampleFn<f32>> {
+        match sample_format {
+            SampleFormat::Int8 => Ok(|mut reader: &mut dyn Read| reader.read_i8_as_f32()),
+            SampleFormat::Int16 | SampleFormat::ImaAdpcm { .. } => {
+                Ok(|mut reader: &mut dyn Read| reader.read_i16_as_f32())
+            }
+            SampleFormat::Int24 => Ok(|mut reader: &mut dyn Read| reader.read_i24_as_f32()),
+            SampleFormat::Float => Ok(|mut reader: &mut dyn Read| reader.read_f32()),
+            SampleFormat::Int64 => Ok(|mut reader: &mut dyn Read| reader.read_i64_as_f32()),
+        }
+    }
+
+    fn write_to(sample_format: SampleFormat) -> Result<WriteSampleFn<f32>> {
+        match sample_format {
+            SampleFormat::Float => {
+                Ok(|mut writer: &mut dyn Write, value: f32| writer.write_f32(value))
+            }
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                "Converting to 32-bit float unsupported",
+            )),
+        }
+    }
+
+    fn decode_into(
+        sample_format: SampleFormat,
+        _rounding_mode: RoundingMode,
+        bytes: &[u8],
+        out: &mut Vec<f32>,
+    ) -> Result<()> {
+        match sample_format {
+            SampleFormat::Int8 => decode_each(bytes, out, |b: [u8; 1]| i8_to_f32(b[0] as i8)),
+            SampleFormat::Int16 | SampleFormat::ImaAdpcm { .. } => {
+                decode_each(bytes, out, |b: [u8; 2]| i16_to_f32(i16::from_le_bytes(b)))
+            }
+            SampleFormat::Int24 => {
+                decode_each(bytes, out, |b: [u8; 3]| i24_to_f32(i24_from_le_bytes(b)?))
+            }
+            SampleFormat::Float => decode_each(bytes, out, |b: [u8; 4]| Ok(f32::from_le_bytes(b))),
+            SampleFormat::Int64 => {
+                decode_each(bytes, out, |b: [u8; 8]| i64_to_f32(i64::from_le_bytes(b)))
+            }
+        }
+    }
+}
+
+impl WavSample for i64 {
+    const SAMPLE_FORMAT: SampleFormat = SampleFormat::Int64;
+
+    fn read_from(sample_format: SampleFormat) -> Result<ReadSampleFn<i64>> {
+        match sample_format {
+            SampleFormat::Int64 => Ok(|mut reader: &mut dyn Read| reader.read_i64()),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                "Converting to 64-bit unsupported",
+            )),
+        }
+    }
+
+    fn write_to(sample_format: SampleFormat) -> Result<WriteSampleFn<i64>> {
+        match sample_format {
+            SampleFormat::Int64 => {
+                Ok(|mut writer: &mut dyn Write, value: i64| writer.write_i64(value))
+            }
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                "Converting to 64-bit int unsupported",
+            )),
+        }
+    }
+
+    fn decode_into(
+        sample_format: SampleFormat,
+        _rounding_mode: RoundingMode,
+        bytes: &[u8],
+        out: &mut Vec<i64>,
+    ) -> Result<()> {
+        match sample_format {
+            SampleFormat::Int64 => decode_each(bytes, out, |b: [u8; 8]| Ok(i64::from_le_bytes(b))),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                "Converting to 64-bit unsupported",
+            )),
+        }
+    }
+}
+
+// There is no 64-bit float sample format, so f64 is read without losing the precision of i64, and written as f32
+impl WavSample for f64 {
+    const SAMPLE_FORMAT: SampleFormat = SampleFormat::Float;
+
+    fn read_from(sample_format: SampleFormat) -> Result<ReadSampleFn<f64>> {
+        match sample_format {
+            SampleFormat::Int8 => {
+                Ok(|mut reader: &mut dyn Read| Ok(reader.read_i8_as_f32()? as f64))
+            }
+            SampleFormat::Int16 | SampleFormat::ImaAdpcm { .. } => {
+                Ok(|mut reader: &mut dyn Read| Ok(reader.read_i16_as_f32()? as f64))
+            }
+            SampleFormat::Int24 => {
+                Ok(|mut reader: &mut dyn Read| Ok(reader.read_i24_as_f32()? as f64))
+            }
+            SampleFormat::Float => Ok(|mut reader: &mut dyn Read| Ok(reader.read_f32()? as f64)),
+            SampleFormat::Int64 => Ok(|mut reader: &mut dyn Read| reader.read_i64_as_f64()),
+        }
+    }
+
+    fn write_to(sample_format: SampleFormat) -> Result<WriteSampleFn<f64>> {
+        match sample_format {
+            SampleFormat::Float => {
+                Ok(|mut writer: &mut dyn Write, value: f64| writer.write_f32(value as f32))
+            }
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                "Converting 64-bit float to integers unsupported",
+            )),
+        }
+    }
+
+    fn decode_into(
+        sample_format: SampleFormat,
+        _rounding_mode: RoundingMode,
+        bytes: &[u8],
+        out: &mut Vec<f64>,
+    ) -> Result<()> {
+        match sample_format {
+            SampleFormat::Int8 => {
+                decode_each(bytes, out, |b: [u8; 1]| Ok(i8_to_f32(b[0] as i8)? as f64))
+            }
+            SampleFormat::Int16 | SampleFormat::ImaAdpcm { .. } => {
+                decode_each(bytes, out, |b: [u8; 2]| {
+                    Ok(i16_to_f32(i16::from_le_bytes(b))? as f64)
+                })
+            }
+            SampleFormat::Int24 => decode_each(bytes, out, |b: [u8; 3]| {
+                Ok(i24_to_f32(i24_from_le_bytes(b)?)? as f64)
+            }),
+            SampleFormat::Float => {
+                decode_each(bytes, out, |b: [u8; 4]| Ok(f32::from_le_bytes(b) as f64))
+            }
+            SampleFormat::Int64 => {
+                decode_each(bytes, out, |b: [u8; 8]| i64_to_f64(i64::from_le_bytes(b)))
+            }
+        }
+    }
+}
+
+// Decodes each N-byte sample in 'bytes' with 'convert'. 'convert' is generic, instead of a fn pointer, so that it
+// can be inlined
+fn decode_each<const N: usize, T>(
+    bytes: &[u8],
+    out: &mut Vec<T>,
+    convert: impl Fn([u8; N]) -> Result<T>,
+) -> Result<()> {
+    out.clear();
+    for sample in bytes.chunks_exact(N) {
+        let mut buf = [0u8; N];
+        buf.copy_from_slice(sample);
+        out.push(convert(buf)?);
+    }
+
+    Ok(())
+}
+
+fn i24_from_le_bytes(bytes: [u8; 3]) -> Result<i32> {
+    i32_to_i24(i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upconvert_from_sanity() {
+        assert!(i8::upconvert_from(SampleFormat::Int8));
+        assert!(!i8::upconvert_from(SampleFormat::Int16));
+
+        assert!(i16::upconvert_from(SampleFormat::Int8));
+        assert!(!i16::upconvert_from(SampleFormat::Int24));
+
+        assert!(i32::upconvert_from(SampleFormat::Int16));
+        assert!(!i32::upconvert_from(SampleFormat::Float));
+
+        assert!(f32::upconvert_from(SampleFormat::Int8));
+        assert!(f32::upconvert_from(SampleFormat::Float));
+
+        assert!(f64::upconvert_from(SampleFormat::Int64));
+        assert!(f64::upconvert_from(SampleFormat::Float));
+        assert!(f64::write_to(SampleFormat::Float).is_ok());
+        assert!(f64::write_to(SampleFormat::Int64).is_err());
+    }
+}
@@ -2,10 +2,17 @@
 
 use std::io::{Error, ErrorKind, Read, Result, Write};
 
+use crate::adpcm::samples_in_block;
+use crate::constants::{MAX_INT_24, MIN_INT_24};
+use crate::upconvert::{
+    INT_16_DIVIDE_FOR_FLOAT, INT_24_DIVIDE_FOR_FLOAT, INT_64_DIVIDE_FOR_FLOAT,
+    INT_8_DIVIDE_FOR_FLOAT,
+};
 use crate::{ReadEx, WriteEx};
 
-/// Sample Format, sample bit depth
+/// Sample Format, sample bit depth. New formats may be added, so matches need a wildcard arm
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum SampleFormat {
     /// 8-bit. Audio quality equivalent to a cassette without noise reduction. Noise shaping and/or dithering is needed
     /// for acceptable audio quality.
@@ -17,6 +24,16 @@ pub enum SampleFormat {
     Int24,
     /// Floating point. Generally exceeds the range of human hearing. Recommended when additional processing is anticipated
     Float,
+    /// 64-bit. Rare; some scientific instruments write it. Can be read as i64, or converted to f32
+    Int64,
+    /// 4-bit IMA ADPCM, common in older games and voice recordings. Can only be read, and only as a stream. Samples
+    /// are decoded to 16-bit, so sizes are reported as if the wav were 16-bit
+    ImaAdpcm {
+        /// The size of each block, in bytes
+        block_align: u16,
+        /// The number of samples, per channel, in each block
+        samples_per_block: u16,
+    },
 }
 
 pub trait SampleFormatSize {
@@ -34,10 +51,152 @@ impl SampleFormatSize for SampleFormat {
 
     fn bytes_per_sample(&self) -> u16 {
         match self {
+            SampleFormat::Int64 => 8,
             SampleFormat::Float => 4,
             SampleFormat::Int24 => 3,
             SampleFormat::Int16 => 2,
             SampleFormat::Int8 => 1,
+            SampleFormat::ImaAdpcm { .. } => 2,
+        }
+    }
+}
+
+impl SampleFormat {
+    /// The value that integer samples are divided by, after being offset to be positive, to convert them to floats.
+    /// (IE, 32767.5 for 16-bit.) Floats are already full scale at 1.0
+    pub fn full_scale(&self) -> f32 {
+        match self {
+            SampleFormat::Float => 1.0,
+            SampleFormat::Int64 => INT_64_DIVIDE_FOR_FLOAT as f32,
+            SampleFormat::Int24 => INT_24_DIVIDE_FOR_FLOAT,
+            SampleFormat::Int16 | SampleFormat::ImaAdpcm { .. } => INT_16_DIVIDE_FOR_FLOAT,
+            SampleFormat::Int8 => INT_8_DIVIDE_FOR_FLOAT,
+        }
+    }
+
+    /// The largest integer sample, or None for floats
+    pub fn max_int_value(&self) -> Option<i64> {
+        match self {
+            SampleFormat::Float => None,
+            SampleFormat::Int64 => Some(i64::MAX),
+            SampleFormat::Int24 => Some(MAX_INT_24 as i64),
+            SampleFormat::Int16 | SampleFormat::ImaAdpcm { .. } => Some(i16::MAX as i64),
+            SampleFormat::Int8 => Some(i8::MAX as i64),
+        }
+    }
+
+    /// The smallest integer sample, or None for floats
+    pub fn min_int_value(&self) -> Option<i64> {
+        match self {
+            SampleFormat::Float => None,
+            SampleFormat::Int64 => Some(i64::MIN),
+            SampleFormat::Int24 => Some(MIN_INT_24 as i64),
+            SampleFormat::Int16 | SampleFormat::ImaAdpcm { .. } => Some(i16::MIN as i64),
+            SampleFormat::Int8 => Some(i8::MIN as i64),
+        }
+    }
+
+    /// The wFormatTag that identifies the sample format in a classic fmt chunk. (IE, 1 for PCM, 3 for floating point)
+    pub fn format_tag(&self) -> u16 {
+        match self {
+            SampleFormat::Float => WAVE_FORMAT_IEEE_FLOAT,
+            SampleFormat::Int8
+            | SampleFormat::Int16
+            | SampleFormat::Int24
+            | SampleFormat::Int64 => WAVE_FORMAT_PCM,
+            SampleFormat::ImaAdpcm { .. } => WAVE_FORMAT_IMA_ADPCM,
+        }
+    }
+
+    /// The SubFormat GUID that identifies the sample format in an extensible fmt chunk, as it's written in the wav.
+    /// (IE, KSDATAFORMAT_SUBTYPE_PCM or KSDATAFORMAT_SUBTYPE_IEEE_FLOAT)
+    pub fn subformat_guid(&self) -> [u8; 16] {
+        // The GUID starts with the format tag. The rest is the same for all formats
+        let mut guid = [
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38,
+            0x9B, 0x71,
+        ];
+        guid[0..2].copy_from_slice(&self.format_tag().to_le_bytes());
+        guid
+    }
+}
+
+// 64-bit samples are only supported as integers
+fn sample_format_64(format_tag: u16) -> Result<SampleFormat> {
+    if format_tag == WAVE_FORMAT_PCM {
+        Ok(SampleFormat::Int64)
+    } else {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "64-bit float unsupported",
+        ))
+    }
+}
+
+// wFormatTag values, https://www.mmsp.ece.mcgill.ca/Documents/AudioFormats/WAVE/WAVE.html
+const WAVE_FORMAT_PCM: u16 = 0x0001;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 0x0003;
+const WAVE_FORMAT_IMA_ADPCM: u16 = 0x0011;
+
+// The size of the original WAVEFORMAT fmt chunk, which doesn't have wBitsPerSample
+const LEGACY_FMT_SIZE: usize = 14;
+
+/// The layout of the fmt chunk that is written into a wav
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum FmtChunkStyle {
+    /// WAVE_FORMAT_EXTENSIBLE, with a channel mask. Supports all channel layouts
+    #[default]
+    Extensible,
+    /// The original WAVEFORMAT layout, without a channel mask. Some older tools only read this layout. Channels
+    /// must be in wav order, starting with front_left, (IE, mono, stereo, 5.1,) because there is no channel mask
+    Classic,
+}
+
+/// The position of a single channel. Positions are declared in the order that they are written in a wav
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ChannelPosition {
+    FrontLeft,
+    FrontRight,
+    FrontCenter,
+    LowFrequency,
+    BackLeft,
+    BackRight,
+    FrontLeftOfCenter,
+    FrontRightOfCenter,
+    BackCenter,
+    SideLeft,
+    SideRight,
+    TopCenter,
+    TopFrontLeft,
+    TopFrontCenter,
+    TopFrontRight,
+    TopBackLeft,
+    TopBackCenter,
+    TopBackRight,
+}
+
+impl ChannelPosition {
+    /// The position's bit in a WAVE_FORMAT_EXTENSIBLE channel mask. (IE, 0x1 for front left)
+    pub fn mask_bit(&self) -> u32 {
+        match self {
+            ChannelPosition::FrontLeft => 0x1,
+            ChannelPosition::FrontRight => 0x2,
+            ChannelPosition::FrontCenter => 0x4,
+            ChannelPosition::LowFrequency => 0x8,
+            ChannelPosition::BackLeft => 0x10,
+            ChannelPosition::BackRight => 0x20,
+            ChannelPosition::FrontLeftOfCenter => 0x40,
+            ChannelPosition::FrontRightOfCenter => 0x80,
+            ChannelPosition::BackCenter => 0x100,
+            ChannelPosition::SideLeft => 0x200,
+            ChannelPosition::SideRight => 0x400,
+            ChannelPosition::TopCenter => 0x800,
+            ChannelPosition::TopFrontLeft => 0x1000,
+            ChannelPosition::TopFrontCenter => 0x2000,
+            ChannelPosition::TopFrontRight => 0x4000,
+            ChannelPosition::TopBackLeft => 0x8000,
+            ChannelPosition::TopBackCenter => 0x10000,
+            ChannelPosition::TopBackRight => 0x20000,
         }
     }
 }
@@ -65,6 +224,12 @@ pub struct Channels {
     pub top_back_right: bool,
 }
 
+impl Default for Channels {
+    fn default() -> Self {
+        Channels::new()
+    }
+}
+
 impl Channels {
     pub fn new() -> Channels {
         Channels {
@@ -196,8 +361,119 @@ impl Channels {
 
         self
     }
+
+    /// Returns true if there is a low frequency effects (LFE) channel
+    pub fn has_lfe(&self) -> bool {
+        self.low_frequency
+    }
+
+    /// The number of channels, excluding the low frequency effects (LFE) channel. (The LFE channel is excluded
+    /// when measuring loudness)
+    pub fn full_range_count(&self) -> u16 {
+        self.positions()
+            .filter(|position| *position != ChannelPosition::LowFrequency)
+            .count() as u16
+    }
+
+    /// All channel positions, in the order that they are written in a wav
+    pub fn all_positions() -> [ChannelPosition; 18] {
+        [
+            ChannelPosition::FrontLeft,
+            ChannelPosition::FrontRight,
+            ChannelPosition::FrontCenter,
+            ChannelPosition::LowFrequency,
+            ChannelPosition::BackLeft,
+            ChannelPosition::BackRight,
+            ChannelPosition::FrontLeftOfCenter,
+            ChannelPosition::FrontRightOfCenter,
+            ChannelPosition::BackCenter,
+            ChannelPosition::SideLeft,
+            ChannelPosition::SideRight,
+            ChannelPosition::TopCenter,
+            ChannelPosition::TopFrontLeft,
+            ChannelPosition::TopFrontCenter,
+            ChannelPosition::TopFrontRight,
+            ChannelPosition::TopBackLeft,
+            ChannelPosition::TopBackCenter,
+            ChannelPosition::TopBackRight,
+        ]
+    }
+
+    /// The positions of the channels that are present, in the order that WAVE_FORMAT_EXTENSIBLE requires them to
+    /// appear in the samples: Ascending by their bit in the channel mask. wave_stream always reads and writes
+    /// channels in this order, (the same order as positions(),) so samples that are interleaved in this order can be
+    /// written as-is
+    pub fn canonical_order(&self) -> Vec<ChannelPosition> {
+        let mut positions: Vec<ChannelPosition> = self.positions().collect();
+        positions.sort_by_key(|position| position.mask_bit());
+
+        positions
+    }
+
+    /// The positions of the channels that are present, in the order that they are written in a wav
+    pub fn positions(&self) -> impl Iterator<Item = ChannelPosition> {
+        let channels = *self;
+        Channels::all_positions()
+            .into_iter()
+            .filter(move |position| channels.contains(*position))
+    }
+
+    /// Returns true if the channel at the given position is present
+    pub fn contains(&self, position: ChannelPosition) -> bool {
+        match position {
+            ChannelPosition::FrontLeft => self.front_left,
+            ChannelPosition::FrontRight => self.front_right,
+            ChannelPosition::FrontCenter => self.front_center,
+            ChannelPosition::LowFrequency => self.low_frequency,
+            ChannelPosition::BackLeft => self.back_left,
+            ChannelPosition::BackRight => self.back_right,
+            ChannelPosition::FrontLeftOfCenter => self.front_left_of_center,
+            ChannelPosition::FrontRightOfCenter => self.front_right_of_center,
+            ChannelPosition::BackCenter => self.back_center,
+            ChannelPosition::SideLeft => self.side_left,
+            ChannelPosition::SideRight => self.side_right,
+            ChannelPosition::TopCenter => self.top_center,
+            ChannelPosition::TopFrontLeft => self.top_front_left,
+            ChannelPosition::TopFrontCenter => self.top_front_center,
+            ChannelPosition::TopFrontRight => self.top_front_right,
+            ChannelPosition::TopBackLeft => self.top_back_left,
+            ChannelPosition::TopBackCenter => self.top_back_center,
+            ChannelPosition::TopBackRight => self.top_back_right,
+        }
+    }
+
+    /// Adds the channel at the given position
+    pub fn with_position(mut self, position: ChannelPosition) -> Channels {
+        match position {
+            ChannelPosition::FrontLeft => self.front_left = true,
+            ChannelPosition::FrontRight => self.front_right = true,
+            ChannelPosition::FrontCenter => self.front_center = true,
+            ChannelPosition::LowFrequency => self.low_frequency = true,
+            ChannelPosition::BackLeft => self.back_left = true,
+            ChannelPosition::BackRight => self.back_right = true,
+            ChannelPosition::FrontLeftOfCenter => self.front_left_of_center = true,
+            ChannelPosition::FrontRightOfCenter => self.front_right_of_center = true,
+            ChannelPosition::BackCenter => self.back_center = true,
+            ChannelPosition::SideLeft => self.side_left = true,
+            ChannelPosition::SideRight => self.side_right = true,
+            ChannelPosition::TopCenter => self.top_center = true,
+            ChannelPosition::TopFrontLeft => self.top_front_left = true,
+            ChannelPosition::TopFrontCenter => self.top_front_center = true,
+            ChannelPosition::TopFrontRight => self.top_front_right = true,
+            ChannelPosition::TopBackLeft => self.top_back_left = true,
+            ChannelPosition::TopBackCenter => self.top_back_center = true,
+            ChannelPosition::TopBackRight => self.top_back_right = true,
+        }
+
+        self
+    }
 }
 
+/// The lowest sample rate that WavHeader::validate_sample_rate() accepts
+pub const MIN_SAMPLE_RATE: u32 = 1;
+/// The highest sample rate that WavHeader::validate_sample_rate() accepts
+pub const MAX_SAMPLE_RATE: u32 = 768000;
+
 // Wav file header. Used to specify wav parameters when creating a wav, or to query wav parameters when reading a wav
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct WavHeader {
@@ -221,13 +497,59 @@ impl WavHeader {
     /// * 'subchunk_size' - Out value, set to the size of the header, or undefined if there is an IO error
     pub fn from_reader(reader: &mut impl Read, subchunk_size: &mut usize) -> Result<WavHeader> {
         reader.assert_str("fmt ", ErrorKind::Unsupported, "Not a WAVE file")?;
+        Self::from_reader_after_id(reader, subchunk_size)
+    }
+
+    // Reads a header from a fmt chunk's contents, excluding its id and length
+    pub(crate) fn from_raw_fmt_chunk(raw_fmt_chunk: &[u8]) -> Result<WavHeader> {
+        let mut fmt_chunk = Vec::with_capacity(4 + raw_fmt_chunk.len());
+        fmt_chunk.write_u32(raw_fmt_chunk.len() as u32)?;
+        fmt_chunk.extend_from_slice(raw_fmt_chunk);
 
+        let mut subchunk_size = 0usize;
+        Self::from_reader_after_id(&mut &fmt_chunk[..], &mut subchunk_size)
+    }
+
+    // Reads a header whose "fmt " id was already read
+    pub(crate) fn from_reader_after_id(
+        reader: &mut impl Read,
+        subchunk_size: &mut usize,
+    ) -> Result<WavHeader> {
+        Self::from_fmt_chunk(reader, subchunk_size, false).map(|(header, _)| header)
+    }
+
+    // Like from_reader_after_id, but also accepts channel counts and channel masks that Channels can't represent.
+    // Returns the header and the number of channels in the fmt chunk. The header's channels are only meaningful when
+    // there are that many of them
+    pub(crate) fn from_reader_after_id_any_channels(
+        reader: &mut impl Read,
+        subchunk_size: &mut usize,
+    ) -> Result<(WavHeader, u16)> {
+        Self::from_fmt_chunk(reader, subchunk_size, true)
+    }
+
+    fn from_fmt_chunk(
+        reader: &mut impl Read,
+        subchunk_size: &mut usize,
+        any_channels: bool,
+    ) -> Result<(WavHeader, u16)> {
         *subchunk_size = reader.read_u32()? as usize;
-        if *subchunk_size < 16 {
+        if *subchunk_size < LEGACY_FMT_SIZE {
             return Err(Error::new(
                 ErrorKind::Unsupported,
                 format!(
-                    "Invalid header. fmt header must be size 16 or larger, actual value: {}",
+                    "Invalid header. fmt header must be size 14 or larger, actual value: {}",
+                    subchunk_size
+                ),
+            ));
+        }
+
+        // Only the original WAVEFORMAT is shorter than 16 bytes. A 15-byte fmt chunk has part of wBitsPerSample
+        if *subchunk_size > LEGACY_FMT_SIZE && *subchunk_size < 16 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Invalid header. fmt header must be size 14 or 16 or larger, actual value: {}",
                     subchunk_size
                 ),
             ));
@@ -235,11 +557,24 @@ impl WavHeader {
 
         let audio_format = reader.read_u16()?; // 2
 
-        if audio_format == 1 || audio_format == 3 {
-            Self::from_reader_classic(reader, subchunk_size)
+        // The original WAVEFORMAT, used by some very old 8-bit wavs, is only 14 bytes and only supports PCM
+        if *subchunk_size < 16 && audio_format != WAVE_FORMAT_PCM {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                format!(
+                    "Invalid header. Only PCM can use the 14-byte WAVEFORMAT fmt header, actual format: {}",
+                    audio_format
+                ),
+            ));
+        }
+
+        if audio_format == WAVE_FORMAT_PCM || audio_format == WAVE_FORMAT_IEEE_FLOAT {
+            Self::from_reader_classic(reader, subchunk_size, audio_format, any_channels)
+        } else if audio_format == WAVE_FORMAT_IMA_ADPCM {
+            Self::from_reader_ima_adpcm(reader, subchunk_size, any_channels)
         // wFormatTag: WAVE_FORMAT_EXTENSIBLE, https://www.mmsp.ece.mcgill.ca/Documents/AudioFormats/WAVE/WAVE.html
         } else if audio_format == 0xFFFE {
-            Self::from_reader_extensible(reader, subchunk_size)
+            Self::from_reader_extensible(reader, subchunk_size, any_channels)
         } else {
             Err(Error::new(
                 ErrorKind::Unsupported,
@@ -248,19 +583,145 @@ impl WavHeader {
         }
     }
 
-    fn from_reader_classic(reader: &mut impl Read, subchunk_size: &mut usize) -> Result<WavHeader> {
+    /// Reads a header from a Read struct, and returns an error if the header's values are absurd. (IE, a corrupt
+    /// header with a sample rate of 0.) from_reader() accepts these headers
+    ///
+    /// # Arguments
+    ///
+    /// * 'reader' - A Read struct. It is strongly recommended that this struct implement some form of buffering, such as via a BufReader
+    /// * 'subchunk_size' - Out value, set to the size of the header, or undefined if there is an IO error
+    pub fn from_reader_strict(
+        reader: &mut impl Read,
+        subchunk_size: &mut usize,
+    ) -> Result<WavHeader> {
+        let header = Self::from_reader(reader, subchunk_size)?;
+        header.validate_sample_rate()?;
+        Ok(header)
+    }
+
+    /// Returns an error, naming the first difference, unless both headers have the same sample rate and channels.
+    /// (IE, when wavs are concatenated or mixed together)
+    ///
+    /// # Arguments
+    ///
+    /// * 'other' - The header to compare with
+    pub fn compatible_with(&self, other: &WavHeader) -> Result<()> {
+        if self.sample_rate != other.sample_rate {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Sample rate {} does not match sample rate {}",
+                    self.sample_rate, other.sample_rate
+                ),
+            ));
+        }
+
+        if self.channels != other.channels {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Channels {:?} do not match channels {:?}",
+                    self.channels.positions().collect::<Vec<_>>(),
+                    other.channels.positions().collect::<Vec<_>>()
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// The same as compatible_with(), but the sample formats must also match
+    ///
+    /// # Arguments
+    ///
+    /// * 'other' - The header to compare with
+    pub fn compatible_with_sample_format(&self, other: &WavHeader) -> Result<()> {
+        self.compatible_with(other)?;
+
+        if self.sample_format != other.sample_format {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Sample format {:?} does not match sample format {:?}",
+                    self.sample_format, other.sample_format
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Returns an error if the sample rate is not between MIN_SAMPLE_RATE and MAX_SAMPLE_RATE
+    pub fn validate_sample_rate(&self) -> Result<()> {
+        if (MIN_SAMPLE_RATE..=MAX_SAMPLE_RATE).contains(&self.sample_rate) {
+            Ok(())
+        } else {
+            Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Invalid sample rate: {}. The sample rate must be between {} and {}",
+                    self.sample_rate, MIN_SAMPLE_RATE, MAX_SAMPLE_RATE
+                ),
+            ))
+        }
+    }
+
+    fn from_reader_classic(
+        reader: &mut impl Read,
+        subchunk_size: &mut usize,
+        audio_format: u16,
+        any_channels: bool,
+    ) -> Result<(WavHeader, u16)> {
         let num_channels = reader.read_u16()?; // 4
+        if !any_channels {
+            check_num_channels(num_channels)?;
+        }
         let sample_rate = reader.read_u32()?; // 8
 
         let _bytes_per_sec = reader.read_u32()?; // 12
-        let _data_block_size = reader.read_u16()?; // 14
+        let data_block_size = reader.read_u16()?; // 14
 
         // This supports oddball situations, like 12-bit, or 20-bit
         // Normally, those are rounded up with least-significant-bit 0ed out
         // (12-bit written as 16-bit, 20-bit written as 24-bit)
-        let bits_per_sample = reader.read_u16()?; // 16
+        let bits_per_sample = if *subchunk_size == LEGACY_FMT_SIZE {
+            // WAVEFORMAT doesn't have wBitsPerSample, so it's derived from the size of each sample
+            if num_channels == 0 {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Invalid header. The wav has no channels",
+                ));
+            }
+
+            // A sample can't be split across channels
+            if data_block_size == 0 || data_block_size % num_channels != 0 {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Invalid header. The block align, {}, isn't a whole number of bytes for each of the {} channels",
+                        data_block_size, num_channels
+                    ),
+                ));
+            }
+
+            (u32::from(data_block_size) / u32::from(num_channels))
+                .checked_mul(8)
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "Invalid header. The block align, {}, is too large",
+                            data_block_size
+                        ),
+                    )
+                })?
+        } else {
+            u32::from(reader.read_u16()?) // 16
+        };
         let sample_format = if bits_per_sample == 32 {
             SampleFormat::Float
+        } else if bits_per_sample == 64 {
+            sample_format_64(audio_format)?
         } else if bits_per_sample <= 8 {
             SampleFormat::Int8
         } else if bits_per_sample <= 16 {
@@ -275,42 +736,91 @@ impl WavHeader {
         };
 
         // Skip additional ignored headers
-        // (By now we're read 16 bytes)
-        reader.skip((*subchunk_size - 16) as usize)?;
+        // (By now we're read 16 bytes, or 14 bytes for WAVEFORMAT)
+        reader.skip(subchunk_size.saturating_sub(16))?;
+
+        let channels = Channels::classic(num_channels);
+
+        Ok((
+            WavHeader {
+                sample_format,
+                channels,
+                sample_rate,
+            },
+            num_channels,
+        ))
+    }
 
-        let channels = Channels {
-            front_left: num_channels >= 1,
-            front_right: num_channels >= 2,
-            front_center: num_channels >= 3,
-            low_frequency: num_channels >= 4,
-            back_left: num_channels >= 5,
-            back_right: num_channels >= 6,
-            front_left_of_center: num_channels >= 7,
-            front_right_of_center: num_channels >= 8,
-            back_center: num_channels >= 9,
-            side_left: num_channels >= 10,
-            side_right: num_channels >= 11,
-            top_center: num_channels >= 12,
-            top_front_left: num_channels >= 13,
-            top_front_center: num_channels >= 14,
-            top_front_right: num_channels >= 15,
-            top_back_left: num_channels >= 16,
-            top_back_center: num_channels >= 17,
-            top_back_right: num_channels >= 18,
-        };
+    fn from_reader_ima_adpcm(
+        reader: &mut impl Read,
+        subchunk_size: &mut usize,
+        any_channels: bool,
+    ) -> Result<(WavHeader, u16)> {
+        let num_channels = reader.read_u16()?; // 4
+        if !any_channels {
+            check_num_channels(num_channels)?;
+        }
+        let sample_rate = reader.read_u32()?; // 8
 
-        Ok(WavHeader {
-            sample_format,
-            channels,
-            sample_rate,
-        })
+        let _bytes_per_sec = reader.read_u32()?; // 12
+        let block_align = reader.read_u16()?; // 14
+
+        let bits_per_sample = reader.read_u16()?; // 16
+        if bits_per_sample != 4 {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                format!("{} bits per sample IMA ADPCM unsupported", bits_per_sample),
+            ));
+        }
+
+        if *subchunk_size < 20 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "The IMA ADPCM fmt chunk is missing wSamplesPerBlock",
+            ));
+        }
+
+        let _cb_size = reader.read_u16()?; // 18
+        let samples_per_block = reader.read_u16()?; // 20
+
+        if samples_per_block as usize
+            != samples_in_block(block_align as usize, num_channels as usize)
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "{} samples can not fit in an IMA ADPCM block of {} bytes",
+                    samples_per_block, block_align
+                ),
+            ));
+        }
+
+        // Skip additional ignored headers
+        // (By now we're read 20 bytes)
+        reader.skip(*subchunk_size - 20)?;
+
+        Ok((
+            WavHeader {
+                sample_format: SampleFormat::ImaAdpcm {
+                    block_align,
+                    samples_per_block,
+                },
+                channels: Channels::classic(num_channels),
+                sample_rate,
+            },
+            num_channels,
+        ))
     }
 
     fn from_reader_extensible(
         reader: &mut impl Read,
         subchunk_size: &mut usize,
-    ) -> Result<WavHeader> {
+        any_channels: bool,
+    ) -> Result<(WavHeader, u16)> {
         let num_channels = reader.read_u16()?; // 4
+        if !any_channels {
+            check_num_channels(num_channels)?;
+        }
         let sample_rate = reader.read_u32()?; // 8
 
         let _bytes_per_sec = reader.read_u32()?; // 12
@@ -322,6 +832,8 @@ impl WavHeader {
         let bits_per_sample = reader.read_u16()?; // 16
         let sample_format = if bits_per_sample == 32 {
             SampleFormat::Float
+        } else if bits_per_sample == 64 {
+            SampleFormat::Int64
         } else if bits_per_sample <= 8 {
             SampleFormat::Int8
         } else if bits_per_sample <= 16 {
@@ -343,9 +855,20 @@ impl WavHeader {
 
         let channel_mask = reader.read_u32()?;
 
+        // The SubFormat GUID starts with the format tag
+        let sub_format_tag = if *subchunk_size >= 26 {
+            Some(reader.read_u16()?) // 26
+        } else {
+            None
+        };
+
+        if let (SampleFormat::Int64, Some(sub_format_tag)) = (sample_format, sub_format_tag) {
+            sample_format_64(sub_format_tag)?;
+        }
+
         // Skip additional ignored headers
-        // (By now we're read 24 bytes)
-        reader.skip((*subchunk_size - 24) as usize)?;
+        // (By now we're read 24 bytes, or 26 bytes with the start of the SubFormat)
+        reader.skip(*subchunk_size - if sub_format_tag.is_some() { 26 } else { 24 })?;
 
         let channels = Channels {
             front_left: channel_mask & 0x1 == 0x1,
@@ -368,18 +891,21 @@ impl WavHeader {
             top_back_right: channel_mask & 0x20000 == 0x20000,
         };
 
-        if num_channels != channels.count() {
+        if num_channels != channels.count() && !any_channels {
             return Err(Error::new(
                 ErrorKind::Unsupported,
                 "Mismatch between number of channels specified in the header, and channel mask",
             ));
         }
 
-        Ok(WavHeader {
-            sample_format,
-            channels,
-            sample_rate,
-        })
+        Ok((
+            WavHeader {
+                sample_format,
+                channels,
+                sample_rate,
+            },
+            num_channels,
+        ))
     }
 
     /// Writes a header to a Write stuct
@@ -388,10 +914,88 @@ impl WavHeader {
     ///
     /// * 'writer' - The Write struct to write the wav header into
     pub fn to_writer(writer: &mut impl Write, header: &WavHeader) -> Result<()> {
+        Self::to_writer_with_style(writer, header, FmtChunkStyle::Extensible)
+    }
+
+    /// Writes a header to a Write stuct, using the given fmt chunk layout
+    ///
+    /// # Arguments
+    ///
+    /// * 'writer' - The Write struct to write the wav header into
+    /// * 'fmt_style' - The layout of the fmt chunk
+    pub fn to_writer_with_style(
+        writer: &mut impl Write,
+        header: &WavHeader,
+        fmt_style: FmtChunkStyle,
+    ) -> Result<()> {
+        if let SampleFormat::ImaAdpcm { .. } = header.sample_format {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "Writing IMA ADPCM is unsupported",
+            ));
+        }
+
+        match fmt_style {
+            FmtChunkStyle::Extensible => Self::to_writer_extensible(writer, header),
+            FmtChunkStyle::Classic => Self::to_writer_classic(writer, header),
+        }
+    }
+
+    fn to_writer_classic(writer: &mut impl Write, header: &WavHeader) -> Result<()> {
+        let num_channels = header.channels.count();
+
+        if header.channels != Channels::classic(num_channels) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "A classic fmt chunk can only hold channels in wav order, starting with front_left. Use an extensible fmt chunk instead",
+            ));
+        }
+
+        let bytes_per_sample = header.sample_format.bytes_per_sample();
+
+        // Float requires cbSize, PCM doesn't
+        let audio_format = header.sample_format.format_tag();
+        let subchunk_size: u32 = match header.sample_format {
+            SampleFormat::Float => 18,
+            _ => 16,
+        };
+
+        // Write WAVEFORMAT
+        writer.write_all(b"fmt ")?;
+        writer.write_u32(subchunk_size)?;
+
+        // wFormatTag
+        writer.write_u16(audio_format)?;
+        // nChannels
+        writer.write_u16(num_channels)?;
+        // nSamplesPerSec
+        writer.write_u32(header.sample_rate)?;
+        // nAvgBytesPerSec
+        writer.write_u32(header.sample_rate * ((num_channels * bytes_per_sample) as u32))?;
+        // nBlockAlign
+        writer.write_u16(num_channels * bytes_per_sample)?;
+        // wBitsPerSample
+        writer.write_u16(header.sample_format.bits_per_sample())?;
+
+        if subchunk_size == 18 {
+            // cbSize
+            writer.write_u16(0)?;
+        }
+
+        Ok(())
+    }
+
+    fn to_writer_extensible(writer: &mut impl Write, header: &WavHeader) -> Result<()> {
         let num_channels = header.channels.count();
 
+        // The samples are written in the order of positions(), which must match the channel mask's order
+        debug_assert_eq!(
+            header.channels.canonical_order(),
+            header.channels.positions().collect::<Vec<_>>()
+        );
+
         // Write WAVEFORMATEX
-        writer.write(b"fmt ")?;
+        writer.write_all(b"fmt ")?;
         writer.write_u32(18 + 22)?;
 
         // wFormatTag: WAVE_FORMAT_EXTENSIBLE, https://www.mmsp.ece.mcgill.ca/Documents/AudioFormats/WAVE/WAVE.html
@@ -401,12 +1005,7 @@ impl WavHeader {
         // nSamplesPerSec
         writer.write_u32(header.sample_rate)?;
 
-        let bytes_per_sample: u16 = match header.sample_format {
-            SampleFormat::Float => 4,
-            SampleFormat::Int24 => 3,
-            SampleFormat::Int16 => 2,
-            SampleFormat::Int8 => 1,
-        };
+        let bytes_per_sample = header.sample_format.bytes_per_sample();
 
         // nAvgBytesPerSec
         let bytes_per_sec: u32 = header.sample_rate * ((num_channels * bytes_per_sample) as u32);
@@ -429,14 +1028,8 @@ impl WavHeader {
         // dwChannelMask
         writer.write_u32(header.channels.channel_mask())?;
 
-        let audio_format: u16 = match header.sample_format {
-            SampleFormat::Float => 3,
-            _ => 1,
-        };
-
         // SubFormat (See Extensible Format in https://www.mmsp.ece.mcgill.ca/Documents/AudioFormats/WAVE/WAVE.html)
-        writer.write_u16(audio_format)?;
-        writer.write(b"\x00\x00\x00\x00\x10\x00\x80\x00\x00\xAA\x00\x38\x9B\x71")?;
+        writer.write_all(&header.sample_format.subformat_guid())?;
 
         Ok(())
     }
@@ -446,6 +1039,22 @@ impl WavHeader {
     }
 }
 
+// Returns an error if there are more channels than there are named positions
+fn check_num_channels(num_channels: u16) -> Result<()> {
+    if num_channels as usize > Channels::all_positions().len() {
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            format!(
+                "{} channels exceed the {} named channel positions",
+                num_channels,
+                Channels::all_positions().len()
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
 pub fn calculate_max_samples(channels: &Channels, sample_format: SampleFormat) -> usize {
     let channels_count = channels.count() as u32;
     let bytes_per_sample = sample_format.bytes_per_sample() as u32;
@@ -455,168 +1064,144 @@ pub fn calculate_max_samples(channels: &Channels, sample_format: SampleFormat) -
 }
 
 impl Channels {
-    pub fn count(&self) -> u16 {
-        let mut count = 0;
-
-        if self.front_left {
-            count += 1;
-        }
-
-        if self.front_right {
-            count += 1;
-        }
-
-        if self.front_center {
-            count += 1;
-        }
-
-        if self.low_frequency {
-            count += 1;
-        }
-
-        if self.back_left {
-            count += 1;
-        }
-
-        if self.back_right {
-            count += 1;
-        }
-
-        if self.front_left_of_center {
-            count += 1;
-        }
-
-        if self.front_right_of_center {
-            count += 1;
-        }
-
-        if self.back_center {
-            count += 1;
-        }
-
-        if self.side_left {
-            count += 1;
-        }
-
-        if self.side_right {
-            count += 1;
-        }
-
-        if self.top_center {
-            count += 1;
-        }
-
-        if self.top_front_left {
-            count += 1;
-        }
-
-        if self.top_front_center {
-            count += 1;
-        }
-
-        if self.top_front_right {
-            count += 1;
-        }
-
-        if self.top_back_left {
-            count += 1;
-        }
-
-        if self.top_back_center {
-            count += 1;
-        }
-
-        if self.top_back_right {
-            count += 1;
+    /// The channels in a wav with a classic fmt chunk, which has no channel mask. Channels are assigned in
+    /// wav order, starting with front_left
+    pub(crate) fn classic(num_channels: u16) -> Channels {
+        Channels {
+            front_left: num_channels >= 1,
+            front_right: num_channels >= 2,
+            front_center: num_channels >= 3,
+            low_frequency: num_channels >= 4,
+            back_left: num_channels >= 5,
+            back_right: num_channels >= 6,
+            front_left_of_center: num_channels >= 7,
+            front_right_of_center: num_channels >= 8,
+            back_center: num_channels >= 9,
+            side_left: num_channels >= 10,
+            side_right: num_channels >= 11,
+            top_center: num_channels >= 12,
+            top_front_left: num_channels >= 13,
+            top_front_center: num_channels >= 14,
+            top_front_right: num_channels >= 15,
+            top_back_left: num_channels >= 16,
+            top_back_center: num_channels >= 17,
+            top_back_right: num_channels >= 18,
         }
+    }
 
-        count
+    pub fn count(&self) -> u16 {
+        self.positions().count() as u16
     }
 
     pub fn channel_mask(&self) -> u32 {
-        let mut channel_mask = 0;
-
-        if self.front_left {
-            channel_mask |= 0x1;
-        }
-
-        if self.front_right {
-            channel_mask |= 0x2;
-        }
-
-        if self.front_center {
-            channel_mask |= 0x4;
-        }
-
-        if self.low_frequency {
-            channel_mask |= 0x8;
-        }
-
-        if self.back_left {
-            channel_mask |= 0x10;
-        }
-
-        if self.back_right {
-            channel_mask |= 0x20;
-        }
-
-        if self.front_left_of_center {
-            channel_mask |= 0x40;
-        }
-
-        if self.front_right_of_center {
-            channel_mask |= 0x80;
-        }
-
-        if self.back_center {
-            channel_mask |= 0x100;
-        }
-
-        if self.side_left {
-            channel_mask |= 0x200;
-        }
-
-        if self.side_right {
-            channel_mask |= 0x400;
-        }
+        self.positions().fold(0, |channel_mask, position| {
+            channel_mask | position.mask_bit()
+        })
+    }
 
-        if self.top_center {
-            channel_mask |= 0x800;
+    /// The name of a standard channel layout, (IE, "5.1",) or None if the channels aren't a standard layout
+    pub fn layout_name(&self) -> Option<&'static str> {
+        match self.channel_mask() {
+            0x4 | 0x1 => Some("mono"),
+            0x3 => Some("stereo"),
+            0x33 => Some("quad"),
+            0x3F | 0x60F => Some("5.1"),
+            0x63F => Some("7.1"),
+            _ => None,
         }
+    }
+}
 
-        if self.top_front_left {
-            channel_mask |= 0x1000;
-        }
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, ErrorKind};
 
-        if self.top_front_center {
-            channel_mask |= 0x2000;
-        }
+    use super::{calculate_max_samples, ChannelPosition, FmtChunkStyle, WavHeader};
+    use crate::constants::{MAX_INT_24, MIN_INT_24};
+    use crate::upconvert::{
+        INT_16_DIVIDE_FOR_FLOAT, INT_24_DIVIDE_FOR_FLOAT, INT_8_DIVIDE_FOR_FLOAT,
+    };
+    use crate::Channels;
+    use crate::SampleFormat;
 
-        if self.top_front_right {
-            channel_mask |= 0x4000;
-        }
+    #[test]
+    fn default_has_no_channels() {
+        assert_eq!(Channels::new(), Channels::default());
+        assert_eq!(0, Channels::default().count());
+    }
 
-        if self.top_back_left {
-            channel_mask |= 0x8000;
-        }
+    #[test]
+    fn positions_5_1() {
+        let channels = Channels::new()
+            .front_left()
+            .front_right()
+            .front_center()
+            .low_frequency()
+            .back_left()
+            .back_right();
+
+        let positions: Vec<ChannelPosition> = channels.positions().collect();
+        assert_eq!(
+            vec![
+                ChannelPosition::FrontLeft,
+                ChannelPosition::FrontRight,
+                ChannelPosition::FrontCenter,
+                ChannelPosition::LowFrequency,
+                ChannelPosition::BackLeft,
+                ChannelPosition::BackRight,
+            ],
+            positions
+        );
+        assert_eq!(6, channels.count());
+    }
 
-        if self.top_back_center {
-            channel_mask |= 0x10000;
-        }
+    #[test]
+    fn full_range_count_5_1() {
+        let channels = Channels::new()
+            .front_left()
+            .front_right()
+            .front_center()
+            .low_frequency()
+            .back_left()
+            .back_right();
+
+        assert!(channels.has_lfe());
+        assert_eq!(5, channels.full_range_count());
+    }
 
-        if self.top_back_right {
-            channel_mask |= 0x20000;
-        }
+    #[test]
+    fn full_range_count_stereo() {
+        let channels = Channels::new().front_left().front_right();
 
-        channel_mask
+        assert!(!channels.has_lfe());
+        assert_eq!(2, channels.full_range_count());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::calculate_max_samples;
-    use crate::Channels;
-    use crate::SampleFormat;
+    #[test]
+    fn layout_names() {
+        assert_eq!(Some("mono"), Channels::new().front_left().layout_name());
+        assert_eq!(
+            Some("stereo"),
+            Channels::new().front_left().front_right().layout_name()
+        );
+        assert_eq!(
+            Some("7.1"),
+            Channels::new()
+                .front_left()
+                .front_right()
+                .front_center()
+                .low_frequency()
+                .back_left()
+                .back_right()
+                .side_left()
+                .side_right()
+                .layout_name()
+        );
+        assert_eq!(
+            None,
+            Channels::new().front_left().top_center().layout_name()
+        );
+    }
 
     #[test]
     fn calculate_max_samples_sanity() {
@@ -649,4 +1234,194 @@ mod tests {
         // 59652323
         assert_eq!(59652323, max_samples);
     }
+
+    #[test]
+    fn zero_sample_rate() {
+        let header = WavHeader {
+            sample_format: SampleFormat::Int16,
+            channels: Channels::new().front_left(),
+            sample_rate: 0,
+        };
+
+        let mut cursor = Cursor::new(Vec::new());
+        WavHeader::to_writer(&mut cursor, &header).unwrap();
+
+        let mut subchunk_size = 0usize;
+        cursor.set_position(0);
+        let err = WavHeader::from_reader_strict(&mut cursor, &mut subchunk_size)
+            .expect_err("Strict mode must reject a sample rate of 0");
+        assert_eq!(ErrorKind::InvalidData, err.kind());
+
+        cursor.set_position(0);
+        let lenient_header = WavHeader::from_reader(&mut cursor, &mut subchunk_size).unwrap();
+        assert_eq!(header, lenient_header);
+    }
+
+    #[test]
+    fn read_20_channels() {
+        let header = WavHeader {
+            sample_format: SampleFormat::Int16,
+            channels: Channels::classic(18),
+            sample_rate: 48000,
+        };
+
+        let mut header_bytes = Vec::new();
+        WavHeader::to_writer(&mut header_bytes, &header).unwrap();
+
+        // nChannels is after "fmt ", the chunk size, and wFormatTag
+        header_bytes[10..12].copy_from_slice(&20u16.to_le_bytes());
+
+        let mut subchunk_size = 0usize;
+        let err = WavHeader::from_reader(&mut Cursor::new(header_bytes), &mut subchunk_size)
+            .expect_err("20 channels can not be read");
+        assert_eq!(ErrorKind::Unsupported, err.kind());
+        assert_eq!(
+            "20 channels exceed the 18 named channel positions",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn read_64_bit_float() {
+        let header = WavHeader {
+            sample_format: SampleFormat::Int64,
+            channels: Channels::new().front_left(),
+            sample_rate: 48000,
+        };
+
+        let mut header_bytes = Vec::new();
+        WavHeader::to_writer_with_style(&mut header_bytes, &header, FmtChunkStyle::Classic)
+            .unwrap();
+
+        let mut subchunk_size = 0usize;
+        assert_eq!(
+            header,
+            WavHeader::from_reader(&mut Cursor::new(header_bytes.clone()), &mut subchunk_size)
+                .unwrap()
+        );
+
+        // wFormatTag is after "fmt " and the chunk size
+        header_bytes[8..10].copy_from_slice(&3u16.to_le_bytes());
+        let err = WavHeader::from_reader(&mut Cursor::new(header_bytes), &mut subchunk_size)
+            .expect_err("64-bit float can not be read");
+        assert_eq!(ErrorKind::Unsupported, err.kind());
+
+        let mut header_bytes = Vec::new();
+        WavHeader::to_writer_with_style(&mut header_bytes, &header, FmtChunkStyle::Extensible)
+            .unwrap();
+
+        // The SubFormat GUID is after "fmt ", the chunk size, and 24 bytes of the fmt chunk
+        header_bytes[32..34].copy_from_slice(&3u16.to_le_bytes());
+        let err = WavHeader::from_reader(&mut Cursor::new(header_bytes), &mut subchunk_size)
+            .expect_err("64-bit float can not be read");
+        assert_eq!(ErrorKind::Unsupported, err.kind());
+    }
+
+    fn stereo_header() -> WavHeader {
+        WavHeader {
+            sample_format: SampleFormat::Int16,
+            channels: Channels::new().front_left().front_right(),
+            sample_rate: 48000,
+        }
+    }
+
+    #[test]
+    fn compatible_with_matching() {
+        let header = stereo_header();
+        header.compatible_with(&header).unwrap();
+        header.compatible_with_sample_format(&header).unwrap();
+
+        let float_header = WavHeader {
+            sample_format: SampleFormat::Float,
+            ..header
+        };
+        header.compatible_with(&float_header).unwrap();
+        let err = header
+            .compatible_with_sample_format(&float_header)
+            .expect_err("Sample formats differ");
+        assert_eq!(
+            "Sample format Int16 does not match sample format Float",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn compatible_with_sample_rate_mismatch() {
+        let header = stereo_header();
+        let other = WavHeader {
+            sample_rate: 44100,
+            channels: Channels::new().front_left(),
+            ..header
+        };
+
+        let err = header
+            .compatible_with(&other)
+            .expect_err("Sample rates differ");
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+        assert_eq!(
+            "Sample rate 48000 does not match sample rate 44100",
+            err.to_string(),
+            "The sample rate is the first difference"
+        );
+    }
+
+    #[test]
+    fn compatible_with_layout_mismatch() {
+        let header = stereo_header();
+        let other = WavHeader {
+            channels: Channels::new().front_left().front_center(),
+            ..header
+        };
+
+        let err = header.compatible_with(&other).expect_err("Channels differ");
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+        assert_eq!(
+            "Channels [FrontLeft, FrontRight] do not match channels [FrontLeft, FrontCenter]",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn full_scale_matches_upconvert() {
+        assert_eq!(INT_8_DIVIDE_FOR_FLOAT, SampleFormat::Int8.full_scale());
+        assert_eq!(INT_16_DIVIDE_FOR_FLOAT, SampleFormat::Int16.full_scale());
+        assert_eq!(INT_24_DIVIDE_FOR_FLOAT, SampleFormat::Int24.full_scale());
+        assert_eq!(1.0, SampleFormat::Float.full_scale());
+    }
+
+    #[test]
+    fn format_tags_and_guids() {
+        assert_eq!(1, SampleFormat::Int16.format_tag());
+        assert_eq!(3, SampleFormat::Float.format_tag());
+
+        // KSDATAFORMAT_SUBTYPE_IEEE_FLOAT, 00000003-0000-0010-8000-00aa00389b71
+        assert_eq!(
+            [
+                0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38,
+                0x9B, 0x71
+            ],
+            SampleFormat::Float.subformat_guid()
+        );
+
+        // KSDATAFORMAT_SUBTYPE_PCM, 00000001-0000-0010-8000-00aa00389b71
+        assert_eq!(
+            [
+                0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38,
+                0x9B, 0x71
+            ],
+            SampleFormat::Int24.subformat_guid()
+        );
+    }
+
+    #[test]
+    fn int_values() {
+        assert_eq!(Some(127), SampleFormat::Int8.max_int_value());
+        assert_eq!(Some(-128), SampleFormat::Int8.min_int_value());
+        assert_eq!(Some(32767), SampleFormat::Int16.max_int_value());
+        assert_eq!(Some(-32768), SampleFormat::Int16.min_int_value());
+        assert_eq!(Some(MAX_INT_24 as i64), SampleFormat::Int24.max_int_value());
+        assert_eq!(Some(MIN_INT_24 as i64), SampleFormat::Int24.min_int_value());
+        assert_eq!(None, SampleFormat::Float.max_int_value());
+        assert_eq!(None, SampleFormat::Float.min_int_value());
+    }
 }
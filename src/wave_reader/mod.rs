@@ -1,17 +1,59 @@
-use std::io::{Read, Result};
+use std::fs::File;
+use std::io::{BufReader, Error, ErrorKind, Read, Result, Seek, SeekFrom};
+use std::iter;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
+use crate::adpcm::{samples_in_block, AdpcmDecoder};
+use crate::byte_counter::ByteCounter;
+use crate::chunks::{
+    read_display_title, AcidChunk, BroadcastExtension, CartChunk, ChunkInfo, PeakChunk,
+};
+use crate::crc32::Crc32;
 use crate::open_wav::OpenWav;
-use crate::wave_header::Channels;
+use crate::reader::lenient_id_matches;
+use crate::samples_by_channel::SamplesByChannel;
+use crate::upconvert::RoundingMode;
+use crate::wav_sample::WavSample;
+use crate::wave_header::{ChannelPosition, Channels};
+use crate::wave_writer::ByteOrder;
 use crate::ReadEx;
 use crate::SampleFormat;
+use crate::SampleFormatSize;
 use crate::WavHeader;
 
+/// Chunks up to this size, (IE, most metadata,) are kept in memory when a wav is opened. Larger chunks are skipped,
+/// so that a corrupt or hostile chunk size can't exhaust memory
+pub const MAX_BUFFERED_CHUNK_SIZE: usize = 64 * 1024;
+
+// A chunk between the fmt and data chunks. Its contents are only kept if it's no larger than MAX_BUFFERED_CHUNK_SIZE
+struct StoredChunk {
+    info: ChunkInfo,
+    contents: Option<Vec<u8>>,
+}
+
 /// Represents an open wav file
 pub struct OpenWavReader<TReader: Read> {
-    reader: TReader,
+    reader: SampleReader<TReader>,
     header: WavHeader,
     data_length: usize,
     data_start: usize,
+    acid_chunk: Option<AcidChunk>,
+    cart_chunk: Option<CartChunk>,
+    peak_chunk: Option<PeakChunk>,
+    display_title: Option<String>,
+    broadcast_extension: Option<BroadcastExtension>,
+    // Shared with reopened readers, so that the chunks aren't copied
+    chunks: Arc<Vec<StoredChunk>>,
+    trailing_bytes: usize,
+    // The path that the wav is read from, if it's read from a file
+    path: Option<PathBuf>,
+    rounding_mode: RoundingMode,
+    // Problems that were corrected when a wav was read leniently
+    warnings: Vec<String>,
+    // The fmt chunk's contents, exactly as they were read
+    raw_fmt_chunk: Vec<u8>,
 }
 
 impl<TReader: Read> OpenWav for OpenWavReader<TReader> {
@@ -36,18 +78,25 @@ impl<TReader: Read> OpenWav for OpenWavReader<TReader> {
     }
 
     fn bytes_per_sample(&self) -> u16 {
-        match self.header.sample_format {
-            SampleFormat::Float => 4,
-            SampleFormat::Int24 => 3,
-            SampleFormat::Int16 => 2,
-            SampleFormat::Int8 => 1,
-        }
+        self.header.sample_format.bytes_per_sample()
     }
 
     fn len_samples(&self) -> usize {
-        self.data_length
-            / (self.bytes_per_sample() as usize)
-            / (self.header.channels.count() as usize)
+        len_samples_in_data(
+            self.header.sample_format,
+            self.header.channels.count() as usize,
+            self.data_length,
+        )
+    }
+
+    fn len_samples_u64(&self) -> u64 {
+        if let SampleFormat::ImaAdpcm { .. } = self.header.sample_format {
+            return self.len_samples() as u64;
+        }
+
+        ((self.data_length as u64) / (self.bytes_per_sample() as u64))
+            .checked_div(self.header.channels.count() as u64)
+            .unwrap_or(0)
     }
 }
 
@@ -60,70 +109,679 @@ impl<TReader: 'static + Read> OpenWavReader<TReader> {
     /// * 'header' - The header that represents the sample rate and bit depth of the wav
     /// * 'position' - The current position of the reader
     pub fn new(
+        reader: TReader,
+        header: WavHeader,
+        position: usize,
+    ) -> Result<OpenWavReader<TReader>> {
+        Self::new_with_warnings(reader, header, position, false, Vec::new())
+    }
+
+    // Finds the data chunk. When 'lenient' is true, a data chunk whose id has the wrong case or spacing is accepted,
+    // and recorded in 'warnings'
+    pub(crate) fn new_with_warnings(
         mut reader: TReader,
         header: WavHeader,
         position: usize,
+        lenient: bool,
+        mut warnings: Vec<String>,
     ) -> Result<OpenWavReader<TReader>> {
+        if header.channels.count() == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "The wav has no channels",
+            ));
+        }
+
         let mut data_start = position;
+        let mut acid_chunk = None;
+        let mut cart_chunk = None;
+        let mut peak_chunk = None;
+        let mut display_title = None;
+        let mut broadcast_extension = None;
+        let mut chunks = Vec::new();
         'find_data_chunk: loop {
-            let chunk_name = reader.read_str(4)?;
+            let offset = data_start;
+            let mut chunk_id = [0u8; 4];
+            reader.read_fixed_size(&mut chunk_id)?;
             data_start += 8;
 
-            if chunk_name.eq("data") {
+            if &chunk_id == b"data" {
+                break 'find_data_chunk;
+            }
+
+            if lenient && lenient_id_matches(&chunk_id, "data") {
+                warnings.push(format!(
+                    "Read the \"{}\" chunk as the data chunk",
+                    String::from_utf8_lossy(&chunk_id)
+                ));
                 break 'find_data_chunk;
             }
 
             let chunk_size = reader.read_u32()? as usize;
             data_start += chunk_size;
-            reader.skip(chunk_size as usize)?;
+
+            // The size is read from the file, so it's only trusted for small chunks
+            let contents = if chunk_size <= MAX_BUFFERED_CHUNK_SIZE {
+                let mut chunk = vec![0u8; chunk_size];
+                reader.read_fixed_size(&mut chunk)?;
+                Some(chunk)
+            } else {
+                reader.skip(chunk_size)?;
+                None
+            };
+
+            if let Some(chunk) = &contents {
+                match &chunk_id {
+                    b"acid" => acid_chunk = AcidChunk::from_reader(&mut &chunk[..], chunk_size)?,
+                    b"cart" => cart_chunk = CartChunk::from_reader(&mut &chunk[..], chunk_size)?,
+                    b"PEAK" => peak_chunk = PeakChunk::from_reader(&mut &chunk[..], chunk_size)?,
+                    b"DISP" => display_title = read_display_title(&mut &chunk[..], chunk_size)?,
+                    b"bext" => {
+                        broadcast_extension =
+                            BroadcastExtension::from_reader(&mut &chunk[..], chunk_size)?
+                    }
+                    _ => {}
+                }
+            }
+
+            chunks.push(StoredChunk {
+                info: ChunkInfo {
+                    id: chunk_id,
+                    offset,
+                    size: chunk_size,
+                },
+                contents,
+            });
+
+            // Chunks are word-aligned
+            if chunk_size % 2 == 1 {
+                data_start += 1;
+                reader.skip(1)?;
+            }
         }
 
         let data_length = reader.read_u32()? as usize;
 
+        // A partial sample at the end of the data chunk can't be read, so it's excluded from data_length
+        let trailing_bytes = trailing_bytes_in_data(
+            header.sample_format,
+            header.channels.count() as usize,
+            data_length,
+        );
+        let data_length = data_length - trailing_bytes;
+
         Ok(OpenWavReader {
-            reader,
+            reader: SampleReader::new(
+                reader,
+                header.sample_format,
+                header.channels.count() as usize,
+                data_length,
+                data_start,
+            ),
+            header,
+            data_length,
+            data_start,
+            acid_chunk,
+            cart_chunk,
+            peak_chunk,
+            display_title,
+            broadcast_extension,
+            chunks: Arc::new(chunks),
+            trailing_bytes,
+            path: None,
+            rounding_mode: RoundingMode::default(),
+            warnings,
+            raw_fmt_chunk: Vec::new(),
+        })
+    }
+}
+
+impl<TReader: Read> OpenWavReader<TReader> {
+    /// Creates an OpenWavReader from a wav whose layout is already known, without parsing any headers. Useful for
+    /// custom container formats that embed a wav's samples at a known offset
+    ///
+    /// # Arguments
+    ///
+    /// * 'reader' - A Read struct. It must be positioned at the first byte of the samples
+    /// * 'header' - The header that represents the sample rate, bit depth, and channels of the samples
+    /// * 'data_start' - The position of the first byte of the samples. When reading via random access, this is the
+    ///   position that is seeked to
+    /// * 'data_length' - The length of the samples, in bytes
+    pub fn from_parts(
+        reader: TReader,
+        header: WavHeader,
+        data_start: usize,
+        data_length: usize,
+    ) -> Result<OpenWavReader<TReader>> {
+        if header.channels.count() == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "The wav has no channels",
+            ));
+        }
+
+        // A partial sample at the end of the samples can't be read, so it's excluded from data_length
+        let trailing_bytes = trailing_bytes_in_data(
+            header.sample_format,
+            header.channels.count() as usize,
+            data_length,
+        );
+        let data_length = data_length - trailing_bytes;
+
+        Ok(OpenWavReader {
+            reader: SampleReader::new(
+                reader,
+                header.sample_format,
+                header.channels.count() as usize,
+                data_length,
+                data_start,
+            ),
             header,
             data_length,
             data_start,
+            acid_chunk: None,
+            cart_chunk: None,
+            peak_chunk: None,
+            display_title: None,
+            broadcast_extension: None,
+            chunks: Arc::new(Vec::new()),
+            trailing_bytes,
+            path: None,
+            rounding_mode: RoundingMode::default(),
+            warnings: Vec::new(),
+            raw_fmt_chunk: Vec::new(),
+        })
+    }
+
+    /// The wav's header
+    pub fn header(&self) -> &WavHeader {
+        &self.header
+    }
+
+    /// Sets how integer samples are converted when they're read at a higher bit depth. (IE, reading a 16-bit wav
+    /// with get_stream_i24_reader()) Must be set before a reader is created
+    ///
+    /// # Arguments
+    ///
+    /// * 'rounding_mode' - How integer samples are converted. Defaults to RoundingMode::FullScale
+    pub fn set_rounding_mode(&mut self, rounding_mode: RoundingMode) {
+        self.rounding_mode = rounding_mode;
+    }
+
+    /// Sets the byte order that floating point samples are read in. Use ByteOrder::Big to read wavs that were
+    /// written with OpenWavWriter::set_byte_order(ByteOrder::Big). Must be set before a reader is created
+    ///
+    /// # Arguments
+    ///
+    /// * 'byte_order' - The byte order of the samples. Defaults to ByteOrder::Little
+    pub fn set_byte_order(&mut self, byte_order: ByteOrder) -> Result<()> {
+        if byte_order == ByteOrder::Big && self.header.sample_format != SampleFormat::Float {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Only floating point samples can be read big-endian",
+            ));
+        }
+
+        self.reader.byte_order = byte_order;
+
+        Ok(())
+    }
+
+    // Remembers the file that the wav is read from, so that it can be reopened
+    pub(crate) fn set_path(&mut self, path: PathBuf) {
+        self.path = Some(path);
+    }
+
+    // Remembers the fmt chunk exactly as it was read
+    pub(crate) fn set_raw_fmt_chunk(&mut self, raw_fmt_chunk: Vec<u8>) {
+        self.raw_fmt_chunk = raw_fmt_chunk;
+    }
+
+    /// Opens the wav's file again, with its own file handle, so that the wav can be read from two places at once.
+    /// (IE, processing different ranges on different threads) The headers and chunks are copied instead of read
+    /// again. Only wavs that were read from a file path can be reopened
+    pub fn reopen(&self) -> Result<OpenWavReader<BufReader<File>>> {
+        let path = match &self.path {
+            Some(path) => path.clone(),
+            None => {
+                return Err(Error::new(
+                    ErrorKind::Unsupported,
+                    "Only wavs that are read from a file path can be reopened",
+                ))
+            }
+        };
+
+        let mut reader = BufReader::new(File::open(&path)?);
+        reader.seek(SeekFrom::Start(self.data_start as u64))?;
+
+        let mut reader = SampleReader::new(
+            reader,
+            self.header.sample_format,
+            self.header.channels.count() as usize,
+            self.data_length,
+            self.data_start,
+        );
+        reader.byte_order = self.reader.byte_order;
+
+        Ok(OpenWavReader {
+            reader,
+            header: self.header,
+            data_length: self.data_length,
+            data_start: self.data_start,
+            acid_chunk: self.acid_chunk,
+            cart_chunk: self.cart_chunk.clone(),
+            peak_chunk: self.peak_chunk.clone(),
+            display_title: self.display_title.clone(),
+            broadcast_extension: self.broadcast_extension.clone(),
+            chunks: self.chunks.clone(),
+            trailing_bytes: self.trailing_bytes,
+            path: Some(path),
+            rounding_mode: self.rounding_mode,
+            warnings: self.warnings.clone(),
+            raw_fmt_chunk: self.raw_fmt_chunk.clone(),
+        })
+    }
+
+    /// The number of bytes at the end of the data chunk that are ignored because they aren't a whole sample. (This
+    /// is usually caused by corruption.) Non-zero values mean that the wav may be damaged
+    pub fn trailing_bytes(&self) -> usize {
+        self.trailing_bytes
+    }
+
+    /// The number of bytes that have been read from the underlying stream, including the headers before the samples.
+    /// (IE, for showing progress while a wav is read over a network) Seeking doesn't change this
+    pub fn bytes_read(&self) -> usize {
+        self.reader.inner.count()
+    }
+
+    /// The fmt chunk's contents, (excluding its id and length,) exactly as they were read. The fmt chunk that
+    /// wave_stream writes may have a different layout; use write_wav_preserving_fmt() to write this fmt chunk
+    /// instead. Empty if the wav was created with new() or from_parts()
+    pub fn raw_fmt_chunk(&self) -> &[u8] {
+        &self.raw_fmt_chunk
+    }
+
+    /// The problems that read_wav_lenient() corrected when the wav was read. (IE, a RIFF form type of "wave"
+    /// instead of "WAVE") Always empty for wavs that are read strictly
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// The contents of a chunk, (excluding its id and length,) by its id. Any chunk between the fmt and data chunks
+    /// can be read, including proprietary chunks that wave_stream doesn't otherwise understand. Returns None if the
+    /// wav doesn't have the chunk, or if the chunk is larger than MAX_BUFFERED_CHUNK_SIZE. (Use read_chunk() for
+    /// larger chunks.) If the chunk appears more than once, the first is returned
+    ///
+    /// # Arguments
+    ///
+    /// * 'id' - The chunk's 4-character id. (IE, b"LGWV")
+    pub fn chunk(&self, id: &[u8; 4]) -> Option<&[u8]> {
+        self.find_chunk(id)?.contents.as_deref()
+    }
+
+    /// The ids of the chunks between the fmt and data chunks, in the order that they appear in the wav
+    pub fn chunk_ids(&self) -> Vec<[u8; 4]> {
+        self.chunks.iter().map(|chunk| chunk.info.id).collect()
+    }
+
+    /// The locations of the chunks between the fmt and data chunks, in the order that they appear in the wav.
+    /// Offsets are from the start of the reader
+    pub fn chunk_infos(&self) -> Vec<ChunkInfo> {
+        self.chunks.iter().map(|chunk| chunk.info).collect()
+    }
+
+    fn find_chunk(&self, id: &[u8; 4]) -> Option<&StoredChunk> {
+        self.chunks.iter().find(|chunk| &chunk.info.id == id)
+    }
+
+    /// The loop and tempo metadata, if the wav has an acid chunk
+    pub fn acid_chunk(&self) -> Option<&AcidChunk> {
+        self.acid_chunk.as_ref()
+    }
+
+    /// The radio automation metadata, if the wav has a cart chunk
+    pub fn cart_chunk(&self) -> Option<&CartChunk> {
+        self.cart_chunk.as_ref()
+    }
+
+    /// The loudest sample in each channel, if the wav has a PEAK chunk
+    pub fn peak_chunk(&self) -> Option<&PeakChunk> {
+        self.peak_chunk.as_ref()
+    }
+
+    /// The title that Windows displays for the sound, if the wav has a DISP chunk with text
+    pub fn display_title(&self) -> Option<&str> {
+        self.display_title.as_deref()
+    }
+
+    /// The date that the wav was created, (yyyy-mm-dd,) if the wav has a bext chunk
+    pub fn origination_date(&self) -> Option<&str> {
+        self.broadcast_extension
+            .as_ref()
+            .map(|broadcast_extension| broadcast_extension.origination_date.as_str())
+    }
+
+    /// The broadcast extension, if the wav has a bext chunk
+    pub fn broadcast_extension(&self) -> Option<&BroadcastExtension> {
+        self.broadcast_extension.as_ref()
+    }
+
+    /// The time that the wav was created, (hh:mm:ss,) if the wav has a bext chunk
+    pub fn origination_time(&self) -> Option<&str> {
+        self.broadcast_extension
+            .as_ref()
+            .map(|broadcast_extension| broadcast_extension.origination_time.as_str())
+    }
+
+    /// Reads the raw, little-endian bytes of each sample, without converting them. Each Vec contains
+    /// frame_bytes() bytes
+    pub fn into_raw_frame_iter(mut self) -> impl Iterator<Item = Result<Vec<u8>>> {
+        let frame_bytes = self.frame_bytes();
+        let len_samples = self.len_samples();
+        let mut current_sample = 0usize;
+
+        iter::from_fn(move || {
+            if current_sample >= len_samples {
+                return None;
+            }
+
+            current_sample += 1;
+
+            let mut frame = vec![0u8; frame_bytes];
+            match self.reader.read_exact(&mut frame) {
+                Ok(()) => Some(Ok(frame)),
+                Err(err) => Some(Err(err)),
+            }
         })
     }
 }
 
+impl<TReader: Read + Seek> OpenWavReader<TReader> {
+    /// Reads the contents of a chunk, (excluding its id and length,) by its id, including chunks that are too large
+    /// to be kept in memory when the wav is opened. Returns None if the wav doesn't have the chunk. Samples continue
+    /// to be read from where they left off
+    ///
+    /// # Arguments
+    ///
+    /// * 'id' - The chunk's 4-character id. (IE, b"iXML")
+    pub fn read_chunk(&mut self, id: &[u8; 4]) -> Result<Option<Vec<u8>>> {
+        let info = match self.find_chunk(id) {
+            Some(StoredChunk {
+                contents: Some(contents),
+                ..
+            }) => return Ok(Some(contents.clone())),
+            Some(chunk) => chunk.info,
+            None => return Ok(None),
+        };
+
+        let position = self.reader.inner.stream_position()?;
+        self.reader
+            .inner
+            .seek(SeekFrom::Start(info.offset as u64 + 8))?;
+        let mut contents = vec![0u8; info.size];
+        let read_result = self.reader.inner.read_fixed_size(&mut contents);
+        self.reader.inner.seek(SeekFrom::Start(position))?;
+
+        read_result?;
+        Ok(Some(contents))
+    }
+
+    /// Computes the CRC-32 of the data chunk's bytes, for checking that a wav's samples were transported intact.
+    /// The reader is returned to the start of the data chunk afterwards
+    pub fn data_crc32(&mut self) -> Result<u32> {
+        self.reader
+            .inner
+            .seek(SeekFrom::Start(self.data_start as u64))?;
+
+        let mut crc32 = Crc32::new();
+        let mut buffer = [0u8; 4096];
+        let mut bytes_remaining = self.data_length;
+        while bytes_remaining > 0 {
+            let bytes_to_read = bytes_remaining.min(buffer.len());
+            self.reader.inner.read_exact(&mut buffer[..bytes_to_read])?;
+            crc32.update(&buffer[..bytes_to_read]);
+            bytes_remaining -= bytes_to_read;
+        }
+
+        self.reader
+            .inner
+            .seek(SeekFrom::Start(self.data_start as u64))?;
+        self.reader.restart();
+
+        Ok(crc32.finish())
+    }
+
+    /// Reads the samples from 'start' up to, (but not including,) 'end'. (IE, for a preview while scrubbing.) The
+    /// range is clamped to the length of the wav
+    ///
+    /// # Arguments
+    ///
+    /// * 'start' - The time of the first sample to read
+    /// * 'end' - The time after the last sample to read
+    pub fn read_time_range_f32(
+        self,
+        start: Duration,
+        end: Duration,
+    ) -> Result<Vec<SamplesByChannel<f32>>>
+    where
+        TReader: 'static,
+    {
+        if start > end {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("The start, {:?}, is after the end, {:?}", start, end),
+            ));
+        }
+
+        let len_samples = self.len_samples();
+        let to_sample = |time: Duration| {
+            let sample = time.as_nanos() * self.sample_rate() as u128 / 1_000_000_000;
+            sample.min(len_samples as u128) as usize
+        };
+        let range = to_sample(start)..to_sample(end);
+
+        self.get_random_access_f32_reader()?.read_range(range)
+    }
+}
+
+// Reads the wav's bytes. Compressed samples are decoded, and big-endian floats are swapped, as they are read
+struct SampleReader<TReader: Read> {
+    inner: ByteCounter<TReader>,
+    adpcm_decoder: Option<AdpcmDecoder>,
+    byte_order: ByteOrder,
+}
+
+impl<TReader: Read> SampleReader<TReader> {
+    // 'data_start' is the number of bytes that were read before the samples
+    fn new(
+        inner: TReader,
+        sample_format: SampleFormat,
+        num_channels: usize,
+        data_length: usize,
+        data_start: usize,
+    ) -> SampleReader<TReader> {
+        let adpcm_decoder = match sample_format {
+            SampleFormat::ImaAdpcm { block_align, .. } => Some(AdpcmDecoder::new(
+                num_channels,
+                block_align as usize,
+                data_length,
+            )),
+            _ => None,
+        };
+
+        SampleReader {
+            inner: ByteCounter::new(inner, data_start),
+            adpcm_decoder,
+            byte_order: ByteOrder::Little,
+        }
+    }
+
+    // Called after the inner reader is returned to the start of the data chunk
+    fn restart(&mut self) {
+        if let Some(adpcm_decoder) = self.adpcm_decoder.as_mut() {
+            adpcm_decoder.restart();
+        }
+    }
+}
+
+impl<TReader: Read> Read for SampleReader<TReader> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if let Some(adpcm_decoder) = self.adpcm_decoder.as_mut() {
+            return adpcm_decoder.read(&mut self.inner, buf);
+        }
+
+        if self.byte_order == ByteOrder::Little {
+            return self.inner.read(buf);
+        }
+
+        // Only whole floats can be swapped
+        let buf_len = buf.len() - buf.len() % 4;
+        if buf_len == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Big-endian samples must be read 4 bytes at a time",
+            ));
+        }
+
+        let mut bytes_read = self.inner.read(&mut buf[..buf_len])?;
+        let partial_bytes = bytes_read % 4;
+        if partial_bytes != 0 {
+            self.inner
+                .read_fixed_size(&mut buf[bytes_read..bytes_read + 4 - partial_bytes])?;
+            bytes_read += 4 - partial_bytes;
+        }
+
+        for sample in buf[..bytes_read].chunks_exact_mut(4) {
+            sample.reverse();
+        }
+
+        Ok(bytes_read)
+    }
+}
+
+// The number of samples in an IMA ADPCM data chunk, including a partial block at the end
+fn adpcm_len_samples(
+    data_length: usize,
+    block_align: usize,
+    samples_per_block: usize,
+    num_channels: usize,
+) -> usize {
+    let full_blocks = data_length.checked_div(block_align).unwrap_or(0);
+    let partial_block = data_length.checked_rem(block_align).unwrap_or(0);
+
+    full_blocks * samples_per_block + samples_in_block(partial_block, num_channels)
+}
+
 type ReadSampleFromStream<T> = fn(&mut dyn Read) -> Result<T>;
 
+// The number of samples in a data chunk of 'data_length' bytes
+// The number of bytes after the last whole sample. (IMA ADPCM is block-based, so partial blocks are handled when
+// decoding)
+fn trailing_bytes_in_data(
+    sample_format: SampleFormat,
+    num_channels: usize,
+    data_length: usize,
+) -> usize {
+    if let SampleFormat::ImaAdpcm { .. } = sample_format {
+        return 0;
+    }
+
+    let frame_bytes = num_channels * (sample_format.bytes_per_sample() as usize);
+    data_length - len_samples_in_data(sample_format, num_channels, data_length) * frame_bytes
+}
+
+fn len_samples_in_data(
+    sample_format: SampleFormat,
+    num_channels: usize,
+    data_length: usize,
+) -> usize {
+    if let SampleFormat::ImaAdpcm {
+        block_align,
+        samples_per_block,
+    } = sample_format
+    {
+        return adpcm_len_samples(
+            data_length,
+            block_align as usize,
+            samples_per_block as usize,
+            num_channels,
+        );
+    }
+
+    // A malformed header with no channels has no samples, instead of dividing by zero
+    (data_length / (sample_format.bytes_per_sample() as usize))
+        .checked_div(num_channels)
+        .unwrap_or(0)
+}
+
+// Adds the sample and channel to an error from reading a sample, so that the damage in a corrupt wav can be located
+fn sample_error(err: Error, sample: usize, position: ChannelPosition) -> Error {
+    Error::new(
+        err.kind(),
+        format!("Sample {sample}, {position:?} channel: {err}"),
+    )
+}
+
+// Adds the sample to an error from reading a whole sample's bytes, across all channels
+fn frame_error(err: Error, sample: usize) -> Error {
+    Error::new(err.kind(), format!("Sample {sample}: {err}"))
+}
+
 mod private_parts {
     use std::io::{Read, Seek};
 
     pub trait POpenWavReader: super::OpenWav {
         fn data_start(&self) -> usize;
+        fn bytes_read(&self) -> usize;
         fn reader(&mut self) -> &mut (dyn Read);
     }
 
     pub trait PRandomAccessOpenWavReader: POpenWavReader {
         fn seeker(&mut self) -> &mut (dyn Seek);
+        fn refresh_data_length(&mut self) -> std::io::Result<()>;
     }
 }
 
 /// An open streaming wav reader. Samples must be read in a sequential manner
 pub trait StreamOpenWavReader: private_parts::POpenWavReader {
+    /// Reads the wav as samples of type T. (IE, get_stream_reader::<f32>()) Note that downsampling is not supported
+    fn get_stream_reader<T: WavSample>(self) -> Result<StreamWavReader<T>>
+    where
+        Self: Sized;
     /// Reads the wav as 8-bit samples. (Note that downsampling to 8-bit is not supported)
     fn get_stream_i8_reader(self) -> Result<StreamWavReader<i8>>;
+    /// Reads an 8-bit wav as the unsigned bytes that are stored in the file, without any conversion
+    fn get_stream_u8_reader(self) -> Result<StreamWavReader<u8>>;
     /// Reads the wav as 16-bit samples. (Note that downsampling to 16-bit is not supported)
     fn get_stream_i16_reader(self) -> Result<StreamWavReader<i16>>;
     /// Reads the wav as 24-bit samples. (Note that downsampling to 24-bit is not supported)
     fn get_stream_i24_reader(self) -> Result<StreamWavReader<i32>>;
+    /// Reads the wav as full-scale 32-bit samples: 8-bit, 16-bit, and 24-bit samples are shifted to span the whole
+    /// range of i32. (Unlike get_stream_i24_reader, which also reads as i32.) Floating point wavs are not supported
+    fn get_stream_i32_reader(self) -> Result<StreamWavReader<i32>>;
     /// Reads the wav as floating point samples. All sample formats can be read as floats
     fn get_stream_f32_reader(self) -> Result<StreamWavReader<f32>>;
 }
 
 /// An open random-access wav reader. Samples may be read in a random-access manner
 pub trait RandomAccessOpenWavReader: private_parts::PRandomAccessOpenWavReader {
+    /// Reads the wav as samples of type T. (IE, get_random_access_reader::<f32>()) Note that downsampling is not
+    /// supported
+    fn get_random_access_reader<T: WavSample>(self) -> Result<RandomAccessWavReader<T>>
+    where
+        Self: Sized;
     /// Reads the wav as 8-bit samples. (Note that downsampling to 8-bit is not supported)
     fn get_random_access_i8_reader(self) -> Result<RandomAccessWavReader<i8>>;
+    /// Reads an 8-bit wav as the unsigned bytes that are stored in the file, without any conversion
+    fn get_random_access_u8_reader(self) -> Result<RandomAccessWavReader<u8>>;
     /// Reads the wav as 16-bit samples. (Note that downsampling to 16-bit is not supported)
     fn get_random_access_i16_reader(self) -> Result<RandomAccessWavReader<i16>>;
     /// Reads the wav as 24-bit samples. (Note that downsampling to 24-bit is not supported)
     fn get_random_access_i24_reader(self) -> Result<RandomAccessWavReader<i32>>;
+    /// Reads the wav as full-scale 32-bit samples: 8-bit, 16-bit, and 24-bit samples are shifted to span the whole
+    /// range of i32. (Unlike get_random_access_i24_reader, which also reads as i32.) Floating point wavs are not
+    /// supported
+    fn get_random_access_i32_reader(self) -> Result<RandomAccessWavReader<i32>>;
     /// Reads the wav as floating point samples. All sample formats can be read as floats
     fn get_random_access_f32_reader(self) -> Result<RandomAccessWavReader<f32>>;
 }
@@ -134,6 +792,18 @@ pub struct RandomAccessWavReader<T> {
     read_sample_from_stream: Box<ReadSampleFromStream<T>>,
 }
 
+/// Iterates over a wav's samples from the last sample to the first, reading a block of samples at a time. Created
+/// with RandomAccessWavReader::into_reverse_chunks()
+pub struct ReverseChunksIterator<T> {
+    reader: RandomAccessWavReader<T>,
+    block: usize,
+    // The samples in the current block, in forward order
+    samples: Vec<SamplesByChannel<T>>,
+    // The first sample in the current block
+    block_start: usize,
+    stopped: bool,
+}
+
 // An open streaming wav reader. Samples must be read in a sequential manner
 pub struct StreamWavReader<T> {
     open_wav: Box<dyn StreamOpenWavReader>,
@@ -145,7 +815,58 @@ pub struct StreamWavReaderIterator<T> {
     open_wav: Box<dyn StreamOpenWavReader>,
     read_sample_from_stream: Box<ReadSampleFromStream<T>>,
     current_sample: usize,
+    stopped: bool,
+}
+
+/// What a lenient stream iterator does when a frame can not be read
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Returns a silent frame in place of the frame that could not be read, and continues reading
+    SkipFrame,
+    /// Ends iteration
+    Stop,
+    /// Returns the error. (This is the same as the normal stream iterator)
+    Propagate,
+}
+
+// A streaming wav iterator that can recover from frames that can not be read
+pub struct LenientStreamWavReaderIterator<T> {
+    open_wav: Box<dyn StreamOpenWavReader>,
+    read_sample_from_stream: Box<ReadSampleFromStream<T>>,
+    current_sample: usize,
+    on_error: ErrorPolicy,
+    stopped: bool,
+}
+
+/// A streaming wav reader for when the reader and sample types are known at compile time. Unlike StreamWavReader,
+/// it doesn't use trait objects or function pointers: each sample's bytes are read in a single read, and decoded
+/// with a single match on the sample format, so it is faster for large wavs. Created with
+/// OpenWavReader::get_typed_stream_reader()
+pub struct TypedStreamReader<TReader: Read, T> {
+    open_wav: OpenWavReader<TReader>,
+    positions: Vec<ChannelPosition>,
+    frame: Vec<u8>,
+    // Reused for each frame's decoded samples
+    values: Vec<T>,
+    current_sample: usize,
+    stopped: bool,
+}
+
+/// A streaming wav reader that reads each sample as a Vec of floats, with one float per channel in the order that
+/// the channels are interleaved. Unlike the other readers, it doesn't use SamplesByChannel, so it can read wavs whose
+/// channels Channels can't represent. Created with OpenWavReader::get_raw_stream_f32(), or read_wav_raw_f32()
+pub struct RawStreamReader<TReader: Read> {
+    reader: SampleReader<TReader>,
+    // The channels are only meaningful when there are num_channels of them
+    header: WavHeader,
+    num_channels: u16,
+    len_samples: usize,
+    frame: Vec<u8>,
+    current_sample: usize,
+    stopped: bool,
 }
 
 mod random;
+mod raw;
 mod stream;
+mod typed;
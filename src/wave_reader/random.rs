@@ -1,19 +1,29 @@
 use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom};
+use std::iter::FusedIterator;
+use std::ops::Range;
 
+use crate::offsets::{ByteOffset, SampleIndex};
+use crate::open_wav::OpenWav;
 use crate::samples_by_channel::SamplesByChannel;
+use crate::wav_sample::{read_full_scale_i32_from, WavSample};
 use crate::OpenWavReader;
 use crate::RandomAccessOpenWavReader;
 use crate::RandomAccessWavReader;
 use crate::ReadEx;
+use crate::ReverseChunksIterator;
 use crate::SampleFormat;
 
-use super::private_parts;
+use super::{private_parts, sample_error};
 
 impl<TReader: Read> private_parts::POpenWavReader for OpenWavReader<TReader> {
     fn data_start(&self) -> usize {
         self.data_start
     }
 
+    fn bytes_read(&self) -> usize {
+        OpenWavReader::bytes_read(self)
+    }
+
     fn reader(&mut self) -> &mut (dyn Read) {
         &mut self.reader as &mut (dyn Read)
     }
@@ -21,93 +31,87 @@ impl<TReader: Read> private_parts::POpenWavReader for OpenWavReader<TReader> {
 
 impl<TReader: Read + Seek> private_parts::PRandomAccessOpenWavReader for OpenWavReader<TReader> {
     fn seeker(&mut self) -> &mut (dyn Seek) {
-        &mut self.reader as &mut (dyn Seek)
+        &mut self.reader.inner as &mut (dyn Seek)
+    }
+
+    // Reads the data chunk's size again, because the wav may still be written to. (The data chunk's size is
+    // immediately before the data)
+    fn refresh_data_length(&mut self) -> Result<()> {
+        self.reader
+            .inner
+            .seek(SeekFrom::Start((self.data_start - 4) as u64))?;
+        let data_length = self.reader.inner.read_u32()? as usize;
+
+        let frame_bytes = self.frame_bytes();
+        self.trailing_bytes = data_length % frame_bytes;
+        self.data_length = data_length - self.trailing_bytes;
+
+        Ok(())
     }
 }
 
 impl<TReader: 'static + Read + Seek> RandomAccessOpenWavReader for OpenWavReader<TReader> {
+    fn get_random_access_reader<T: WavSample>(self) -> Result<RandomAccessWavReader<T>> {
+        if let SampleFormat::ImaAdpcm { .. } = self.header.sample_format {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "IMA ADPCM wavs can only be read as a stream",
+            ));
+        }
+
+        let read_sample_from_stream =
+            T::read_from_with_rounding(self.header.sample_format, self.rounding_mode)?;
+
+        Ok(RandomAccessWavReader {
+            open_wav: Box::new(self),
+            read_sample_from_stream: Box::new(read_sample_from_stream),
+        })
+    }
+
     fn get_random_access_i8_reader(self) -> Result<RandomAccessWavReader<i8>> {
+        self.get_random_access_reader()
+    }
+
+    fn get_random_access_u8_reader(self) -> Result<RandomAccessWavReader<u8>> {
         match self.header.sample_format {
             SampleFormat::Int8 => Ok(RandomAccessWavReader {
                 open_wav: Box::new(self),
-                read_sample_from_stream: Box::new(|mut reader: &mut dyn Read| reader.read_i8()),
+                read_sample_from_stream: Box::new(|mut reader: &mut dyn Read| reader.read_u8()),
             }),
             _ => Err(Error::new(
                 ErrorKind::InvalidData,
-                "Converting to 8-bit unsupported",
+                "Only 8-bit wavs can be read as unsigned 8-bit",
             )),
         }
     }
 
     fn get_random_access_i16_reader(self) -> Result<RandomAccessWavReader<i16>> {
-        match self.header.sample_format {
-            SampleFormat::Int8 => Ok(RandomAccessWavReader {
-                open_wav: Box::new(self),
-                read_sample_from_stream: Box::new(|mut reader: &mut dyn Read| {
-                    reader.read_i8_as_i16()
-                }),
-            }),
-            SampleFormat::Int16 => Ok(RandomAccessWavReader {
-                open_wav: Box::new(self),
-                read_sample_from_stream: Box::new(|mut reader: &mut dyn Read| reader.read_i16()),
-            }),
-            _ => Err(Error::new(
-                ErrorKind::InvalidData,
-                "Converting to 16-bit unsupported",
-            )),
-        }
+        self.get_random_access_reader()
     }
 
     fn get_random_access_i24_reader(self) -> Result<RandomAccessWavReader<i32>> {
-        match self.header.sample_format {
-            SampleFormat::Int8 => Ok(RandomAccessWavReader {
-                open_wav: Box::new(self),
-                read_sample_from_stream: Box::new(|mut reader: &mut dyn Read| {
-                    reader.read_i8_as_i24()
-                }),
-            }),
-            SampleFormat::Int16 => Ok(RandomAccessWavReader {
-                open_wav: Box::new(self),
-                read_sample_from_stream: Box::new(|mut reader: &mut dyn Read| {
-                    reader.read_i16_as_i24()
-                }),
-            }),
-            SampleFormat::Int24 => Ok(RandomAccessWavReader {
-                open_wav: Box::new(self),
-                read_sample_from_stream: Box::new(|mut reader: &mut dyn Read| reader.read_i24()),
-            }),
-            _ => Err(Error::new(
-                ErrorKind::InvalidData,
-                "Converting to 24-bit unsupported",
-            )),
+        self.get_random_access_reader()
+    }
+
+    fn get_random_access_i32_reader(self) -> Result<RandomAccessWavReader<i32>> {
+        if let SampleFormat::ImaAdpcm { .. } = self.header.sample_format {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "IMA ADPCM wavs can only be read as a stream",
+            ));
         }
+
+        let read_sample_from_stream =
+            read_full_scale_i32_from(self.header.sample_format, self.rounding_mode)?;
+
+        Ok(RandomAccessWavReader {
+            open_wav: Box::new(self),
+            read_sample_from_stream: Box::new(read_sample_from_stream),
+        })
     }
 
     fn get_random_access_f32_reader(self) -> Result<RandomAccessWavReader<f32>> {
-        match self.header.sample_format {
-            SampleFormat::Int8 => Ok(RandomAccessWavReader {
-                open_wav: Box::new(self),
-                read_sample_from_stream: Box::new(|mut reader: &mut dyn Read| {
-                    reader.read_i8_as_f32()
-                }),
-            }),
-            SampleFormat::Int16 => Ok(RandomAccessWavReader {
-                open_wav: Box::new(self),
-                read_sample_from_stream: Box::new(|mut reader: &mut dyn Read| {
-                    reader.read_i16_as_f32()
-                }),
-            }),
-            SampleFormat::Int24 => Ok(RandomAccessWavReader {
-                open_wav: Box::new(self),
-                read_sample_from_stream: Box::new(|mut reader: &mut dyn Read| {
-                    reader.read_i24_as_f32()
-                }),
-            }),
-            SampleFormat::Float => Ok(RandomAccessWavReader {
-                open_wav: Box::new(self),
-                read_sample_from_stream: Box::new(|mut reader: &mut dyn Read| reader.read_f32()),
-            }),
-        }
+        self.get_random_access_reader()
     }
 }
 
@@ -116,150 +120,153 @@ impl<T> RandomAccessWavReader<T> {
         &self.open_wav
     }
 
+    /// The number of bytes that have been read from the underlying stream, including the headers before the samples.
+    /// Seeking doesn't change this, so samples that are read more than once are counted each time
+    pub fn bytes_read(&self) -> usize {
+        self.open_wav.bytes_read()
+    }
+
+    /// Reads the length of the samples again, and returns the new number of samples. This allows "tailing" a wav
+    /// while it's written: Samples that the writer flushed after the reader was opened become readable. (The writer
+    /// updates the length each time it flushes, unless it was built with an open-ended size)
+    pub fn refresh_len(&mut self) -> Result<usize> {
+        self.open_wav.refresh_data_length()?;
+        Ok(self.open_wav.len_samples())
+    }
+
     pub fn read_sample(&mut self, sample: usize) -> Result<SamplesByChannel<T>> {
+        let mut samples_by_channel = SamplesByChannel::new();
+        self.read_frame_into(sample, &mut samples_by_channel)?;
+        Ok(samples_by_channel)
+    }
+
+    /// Reads a sample into an existing SamplesByChannel, instead of constructing a new one. This avoids
+    /// constructing a SamplesByChannel for every sample in tight loops
+    ///
+    /// # Arguments
+    ///
+    /// * 'sample' - The index of the sample to read
+    /// * 'out' - Overwritten with the sample. Channels that aren't in the wav are set to None
+    pub fn read_frame_into(&mut self, sample: usize, out: &mut SamplesByChannel<T>) -> Result<()> {
+        self.seek_to_sample(sample)?;
+
+        // Channels are copied, because otherwise it holds an immutable borrow of self
+        let channels = *self.open_wav.channels();
+
+        *out = SamplesByChannel::new();
+        for position in channels.positions() {
+            let value = (*self.read_sample_from_stream)(&mut self.open_wav.reader())
+                .map_err(|err| sample_error(err, sample, position))?;
+            out.set(position, value);
+        }
+
+        Ok(())
+    }
+
+    /// Reads consecutive samples. Only seeks once, so it's faster than calling read_sample for each sample
+    ///
+    /// # Arguments
+    ///
+    /// * 'range' - The indexes of the samples to read
+    pub fn read_range(&mut self, range: Range<usize>) -> Result<Vec<SamplesByChannel<T>>>
+    where
+        T: Copy,
+    {
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        if range.end > self.open_wav.len_samples() {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "Sample out of range"));
+        }
+
+        self.seek_to_sample(range.start)?;
+
+        let channels = *self.open_wav.channels();
+        let mut samples = Vec::with_capacity(range.len());
+        for sample in range {
+            let mut samples_by_channel = SamplesByChannel::new();
+            for position in channels.positions() {
+                let value = (*self.read_sample_from_stream)(&mut self.open_wav.reader())
+                    .map_err(|err| sample_error(err, sample, position))?;
+                samples_by_channel.set(position, value);
+            }
+
+            samples.push(samples_by_channel);
+        }
+
+        Ok(samples)
+    }
+
+    /// Iterates over the samples from the last sample to the first. Each block of samples is read forward, with a
+    /// single seek, and then returned in reverse. This is much faster than reading each sample with read_sample in
+    /// reverse order
+    ///
+    /// # Arguments
+    ///
+    /// * 'block' - The number of samples to read at a time. Must be at least 1
+    pub fn into_reverse_chunks(self, block: usize) -> Result<ReverseChunksIterator<T>> {
+        if block == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Must read at least 1 sample at a time",
+            ));
+        }
+
+        let block_start = self.open_wav.len_samples();
+
+        Ok(ReverseChunksIterator {
+            reader: self,
+            block,
+            samples: Vec::new(),
+            block_start,
+            stopped: false,
+        })
+    }
+
+    fn seek_to_sample(&mut self, sample: usize) -> Result<()> {
         if sample >= self.open_wav.len_samples() {
             return Err(Error::new(ErrorKind::UnexpectedEof, "Sample out of range"));
         }
 
-        let sample_in_channels = sample * self.open_wav.num_channels() as usize;
-        let sample_in_bytes = sample_in_channels * self.open_wav.bytes_per_sample() as usize;
-        let position = self.open_wav.data_start() + sample_in_bytes;
+        let position = ByteOffset(self.open_wav.data_start())
+            + SampleIndex(sample).to_byte_offset(self.open_wav.frame_bytes());
 
         let seeker = self.open_wav.seeker();
-        seeker.seek(SeekFrom::Start(position as u64))?;
-
-        // Channels are cloned, because otherwise it holds an immutable borrow of self
-        let channels = self.open_wav.channels().clone();
-
-        Ok(SamplesByChannel {
-            front_left: if channels.front_left {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            front_right: if channels.front_right {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            front_center: if channels.front_center {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            low_frequency: if channels.low_frequency {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            back_left: if channels.back_left {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            back_right: if channels.back_right {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            front_left_of_center: if channels.front_left_of_center {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            front_right_of_center: if channels.front_right_of_center {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            back_center: if channels.back_center {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            side_left: if channels.side_left {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            side_right: if channels.side_right {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            top_center: if channels.top_center {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            top_front_left: if channels.top_front_left {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            top_front_center: if channels.top_front_center {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            top_front_right: if channels.top_front_right {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            top_back_left: if channels.top_back_left {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            top_back_center: if channels.top_back_center {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            top_back_right: if channels.top_back_right {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-        })
+        seeker.seek(SeekFrom::Start(position.into()))?;
+
+        Ok(())
     }
 }
 
 unsafe impl<T> Send for RandomAccessWavReader<T> {}
+
+impl<T: Copy> Iterator for ReverseChunksIterator<T> {
+    type Item = Result<SamplesByChannel<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(samples_by_channel) = self.samples.pop() {
+            return Some(Ok(samples_by_channel));
+        }
+
+        if self.stopped || self.block_start == 0 {
+            return None;
+        }
+
+        let block_end = self.block_start;
+        self.block_start = block_end.saturating_sub(self.block);
+
+        match self.reader.read_range(self.block_start..block_end) {
+            Ok(samples) => {
+                self.samples = samples;
+                self.samples.pop().map(Ok)
+            }
+            Err(err) => {
+                self.stopped = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl<T: Copy> FusedIterator for ReverseChunksIterator<T> {}
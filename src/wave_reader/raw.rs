@@ -0,0 +1,171 @@
+use std::io::{Error, ErrorKind, Read, Result};
+use std::iter::FusedIterator;
+
+use crate::open_wav::OpenWav;
+use crate::upconvert::RoundingMode;
+use crate::wav_sample::WavSample;
+use crate::wave_header::Channels;
+use crate::OpenWavReader;
+use crate::RawStreamReader;
+use crate::ReadEx;
+use crate::SampleFormat;
+use crate::SampleFormatSize;
+use crate::WavHeader;
+
+use super::{frame_error, len_samples_in_data, SampleReader};
+
+impl<TReader: Read> OpenWavReader<TReader> {
+    /// Reads the wav as interleaved floats, with num_channels() floats per sample, without mapping them to channel
+    /// positions. (Use read_wav_raw_f32() for wavs whose channels can't be represented by Channels)
+    pub fn get_raw_stream_f32(self) -> Result<RawStreamReader<TReader>> {
+        let num_channels = self.num_channels();
+        let len_samples = self.len_samples();
+        RawStreamReader::from_sample_reader(self.reader, self.header, num_channels, len_samples)
+    }
+}
+
+impl<TReader: Read> RawStreamReader<TReader> {
+    // Finds the data chunk in a wav whose fmt chunk was already read. 'num_channels' is the number of channels in the
+    // fmt chunk, and 'position' is the current position of the reader
+    pub(crate) fn new(
+        mut reader: TReader,
+        header: WavHeader,
+        num_channels: u16,
+        position: usize,
+    ) -> Result<RawStreamReader<TReader>> {
+        if num_channels == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "The wav has no channels",
+            ));
+        }
+
+        let mut data_start = position;
+        loop {
+            let mut chunk_id = [0u8; 4];
+            reader.read_fixed_size(&mut chunk_id)?;
+            data_start += 8;
+
+            if &chunk_id == b"data" {
+                break;
+            }
+
+            // Chunks are word-aligned
+            let chunk_size = reader.read_u32()? as usize;
+            let padded_size = chunk_size + chunk_size % 2;
+            reader.skip(padded_size)?;
+            data_start += padded_size;
+        }
+
+        let data_length = reader.read_u32()? as usize;
+        let len_samples =
+            len_samples_in_data(header.sample_format, num_channels as usize, data_length);
+
+        let reader = SampleReader::new(
+            reader,
+            header.sample_format,
+            num_channels as usize,
+            data_length,
+            data_start,
+        );
+        Self::from_sample_reader(reader, header, num_channels, len_samples)
+    }
+
+    fn from_sample_reader(
+        reader: SampleReader<TReader>,
+        header: WavHeader,
+        num_channels: u16,
+        len_samples: usize,
+    ) -> Result<RawStreamReader<TReader>> {
+        // Returns an error if the samples can't be read as floats
+        f32::decode_into(
+            header.sample_format,
+            RoundingMode::default(),
+            &[],
+            &mut Vec::new(),
+        )?;
+
+        let frame =
+            vec![0u8; (num_channels as usize) * (header.sample_format.bytes_per_sample() as usize)];
+
+        Ok(RawStreamReader {
+            reader,
+            header,
+            num_channels,
+            len_samples,
+            frame,
+            current_sample: 0,
+            stopped: false,
+        })
+    }
+
+    /// The number of floats in each sample
+    pub fn num_channels(&self) -> u16 {
+        self.num_channels
+    }
+
+    /// The channels, or None if the wav's channels can't be represented by Channels. (IE, more than 18 channels, or
+    /// a channel mask that doesn't match the number of channels)
+    pub fn channels(&self) -> Option<&Channels> {
+        if self.header.channels.count() == self.num_channels {
+            Some(&self.header.channels)
+        } else {
+            None
+        }
+    }
+
+    /// The sample format
+    pub fn sample_format(&self) -> SampleFormat {
+        self.header.sample_format
+    }
+
+    /// The samples per second
+    pub fn sample_rate(&self) -> u32 {
+        self.header.sample_rate
+    }
+
+    /// The total number of samples in the wav file
+    pub fn len_samples(&self) -> usize {
+        self.len_samples
+    }
+
+    fn read_samples(&mut self) -> Result<Vec<f32>> {
+        let sample = self.current_sample;
+        self.reader
+            .read_exact(&mut self.frame)
+            .map_err(|err| frame_error(err, sample))?;
+        self.current_sample += 1;
+
+        let mut values = Vec::with_capacity(self.num_channels as usize);
+        f32::decode_into(
+            self.header.sample_format,
+            RoundingMode::default(),
+            &self.frame,
+            &mut values,
+        )
+        .map_err(|err| frame_error(err, sample))?;
+
+        Ok(values)
+    }
+}
+
+impl<TReader: Read> Iterator for RawStreamReader<TReader> {
+    type Item = Result<Vec<f32>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.stopped || self.current_sample >= self.len_samples {
+            return None;
+        }
+
+        let samples_result = self.read_samples();
+
+        // After an error, the reader is no longer aligned on a sample
+        if samples_result.is_err() {
+            self.stopped = true;
+        }
+
+        Some(samples_result)
+    }
+}
+
+impl<TReader: Read> FusedIterator for RawStreamReader<TReader> {}
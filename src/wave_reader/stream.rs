@@ -1,7 +1,15 @@
-use std::io::{Error, ErrorKind, Read, Result};
-use std::iter::IntoIterator;
+use std::collections::VecDeque;
+use std::io::{Cursor, Error, ErrorKind, Read, Result};
+use std::iter::{self, FusedIterator, IntoIterator};
 
+use crate::offsets::{ByteOffset, SampleIndex};
+#[cfg(feature = "ndarray")]
+use crate::open_wav::OpenWav;
 use crate::samples_by_channel::SamplesByChannel;
+use crate::wav_sample::{read_full_scale_i32_from, WavSample};
+use crate::wave_header::Channels;
+use crate::ErrorPolicy;
+use crate::LenientStreamWavReaderIterator;
 use crate::OpenWavReader;
 use crate::ReadEx;
 use crate::SampleFormat;
@@ -9,89 +17,75 @@ use crate::StreamOpenWavReader;
 use crate::StreamWavReader;
 use crate::StreamWavReaderIterator;
 
+use super::{frame_error, sample_error};
+
 impl<TReader: 'static + Read> StreamOpenWavReader for OpenWavReader<TReader> {
+    fn get_stream_reader<T: WavSample>(self) -> Result<StreamWavReader<T>> {
+        let read_sample_from_stream =
+            T::read_from_with_rounding(self.header.sample_format, self.rounding_mode)?;
+
+        Ok(StreamWavReader {
+            open_wav: Box::new(self),
+            read_sample_from_stream: Box::new(read_sample_from_stream),
+        })
+    }
+
     fn get_stream_i8_reader(self) -> Result<StreamWavReader<i8>> {
+        self.get_stream_reader()
+    }
+
+    fn get_stream_u8_reader(self) -> Result<StreamWavReader<u8>> {
         match self.header.sample_format {
             SampleFormat::Int8 => Ok(StreamWavReader {
                 open_wav: Box::new(self),
-                read_sample_from_stream: Box::new(|mut reader: &mut dyn Read| reader.read_i8()),
+                read_sample_from_stream: Box::new(|mut reader: &mut dyn Read| reader.read_u8()),
             }),
             _ => Err(Error::new(
                 ErrorKind::InvalidData,
-                "Converting to 8-bit unsupported",
+                "Only 8-bit wavs can be read as unsigned 8-bit",
             )),
         }
     }
 
     fn get_stream_i16_reader(self) -> Result<StreamWavReader<i16>> {
-        match self.header.sample_format {
-            SampleFormat::Int8 => Ok(StreamWavReader {
-                open_wav: Box::new(self),
-                read_sample_from_stream: Box::new(|mut reader: &mut dyn Read| {
-                    reader.read_i8_as_i16()
-                }),
-            }),
-            SampleFormat::Int16 => Ok(StreamWavReader {
-                open_wav: Box::new(self),
-                read_sample_from_stream: Box::new(|mut reader: &mut dyn Read| reader.read_i16()),
-            }),
-            _ => Err(Error::new(
-                ErrorKind::InvalidData,
-                "Converting to 16-bit unsupported",
-            )),
-        }
+        self.get_stream_reader()
     }
 
     fn get_stream_i24_reader(self) -> Result<StreamWavReader<i32>> {
-        match self.header.sample_format {
-            SampleFormat::Int8 => Ok(StreamWavReader {
-                open_wav: Box::new(self),
-                read_sample_from_stream: Box::new(|mut reader: &mut dyn Read| {
-                    reader.read_i8_as_i24()
-                }),
-            }),
-            SampleFormat::Int16 => Ok(StreamWavReader {
-                open_wav: Box::new(self),
-                read_sample_from_stream: Box::new(|mut reader: &mut dyn Read| {
-                    reader.read_i16_as_i24()
-                }),
-            }),
-            SampleFormat::Int24 => Ok(StreamWavReader {
-                open_wav: Box::new(self),
-                read_sample_from_stream: Box::new(|mut reader: &mut dyn Read| reader.read_i24()),
-            }),
-            _ => Err(Error::new(
-                ErrorKind::InvalidData,
-                "Converting to 24-bit unsupported",
-            )),
-        }
+        self.get_stream_reader()
+    }
+
+    fn get_stream_i32_reader(self) -> Result<StreamWavReader<i32>> {
+        let read_sample_from_stream =
+            read_full_scale_i32_from(self.header.sample_format, self.rounding_mode)?;
+
+        Ok(StreamWavReader {
+            open_wav: Box::new(self),
+            read_sample_from_stream: Box::new(read_sample_from_stream),
+        })
     }
 
     fn get_stream_f32_reader(self) -> Result<StreamWavReader<f32>> {
-        match self.header.sample_format {
-            SampleFormat::Int8 => Ok(StreamWavReader {
-                open_wav: Box::new(self),
-                read_sample_from_stream: Box::new(|mut reader: &mut dyn Read| {
-                    reader.read_i8_as_f32()
-                }),
-            }),
-            SampleFormat::Int16 => Ok(StreamWavReader {
-                open_wav: Box::new(self),
-                read_sample_from_stream: Box::new(|mut reader: &mut dyn Read| {
-                    reader.read_i16_as_f32()
-                }),
-            }),
-            SampleFormat::Int24 => Ok(StreamWavReader {
-                open_wav: Box::new(self),
-                read_sample_from_stream: Box::new(|mut reader: &mut dyn Read| {
-                    reader.read_i24_as_f32()
-                }),
-            }),
-            SampleFormat::Float => Ok(StreamWavReader {
-                open_wav: Box::new(self),
-                read_sample_from_stream: Box::new(|mut reader: &mut dyn Read| reader.read_f32()),
-            }),
+        self.get_stream_reader()
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl<TReader: 'static + Read> OpenWavReader<TReader> {
+    /// Reads the whole wav into a 2D array of floats, where each row is a sample and each column is a channel. (IE,
+    /// for scientific tools.) Columns are in the order that the wav's channels are written. Requires the ndarray
+    /// feature
+    pub fn read_ndarray_f32(self) -> Result<ndarray::Array2<f32>> {
+        let num_channels = self.num_channels() as usize;
+
+        let mut data = Vec::with_capacity(self.len_samples() * num_channels);
+        for samples_result in self.get_stream_f32_reader()? {
+            data.extend(samples_result?.to_vec());
         }
+
+        let len_samples = data.len() / num_channels;
+        ndarray::Array2::from_shape_vec((len_samples, num_channels), data)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err))
     }
 }
 
@@ -99,6 +93,138 @@ impl<T> StreamWavReader<T> {
     pub fn info(&self) -> &Box<dyn StreamOpenWavReader> {
         &self.open_wav
     }
+
+    /// Iterates through the samples, along with the position of each sample's first byte, from the start of the
+    /// file. The positions are calculated, not read, so they can be used to build an index for seeking. (They aren't
+    /// meaningful for compressed sample formats, like IMA ADPCM)
+    pub fn into_iter_with_offsets(
+        self,
+    ) -> impl Iterator<Item = Result<(usize, SamplesByChannel<T>)>> {
+        let data_start = ByteOffset(self.open_wav.data_start());
+        let frame_bytes = self.open_wav.frame_bytes();
+
+        self.into_iter()
+            .enumerate()
+            .map(move |(sample, samples_result)| {
+                let offset = data_start + SampleIndex(sample).to_byte_offset(frame_bytes);
+                samples_result.map(|samples| (offset.into(), samples))
+            })
+    }
+}
+
+impl<T: WavSample + Default> StreamWavReader<T> {
+    /// Iterates through the samples, but recovers from frames that can not be read. (IE, a bad sector in a long
+    /// archival recording.) A frame that can not be read is still skipped over in its entirety, so that the frames
+    /// after it stay aligned
+    ///
+    /// # Arguments
+    ///
+    /// * 'on_error' - What to do when a frame can not be read
+    pub fn into_lenient_iter(self, on_error: ErrorPolicy) -> LenientStreamWavReaderIterator<T> {
+        LenientStreamWavReaderIterator {
+            open_wav: self.open_wav,
+            read_sample_from_stream: self.read_sample_from_stream,
+            current_sample: 0,
+            on_error,
+            stopped: false,
+        }
+    }
+}
+
+impl StreamWavReader<f32> {
+    /// Copies each sample of a mono wav into every channel of 'target'. Useful for generating test material
+    ///
+    /// # Arguments
+    ///
+    /// * 'target' - The channels to copy the mono sample into
+    pub fn broadcast_to(
+        self,
+        target: Channels,
+    ) -> Result<impl Iterator<Item = Result<SamplesByChannel<f32>>>> {
+        if self.open_wav.num_channels() != 1 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Only mono wavs can be broadcast to multiple channels",
+            ));
+        }
+
+        Ok(self.into_iter().map(move |samples_result| {
+            let sample = samples_result?.to_vec()[0];
+
+            let mut samples_by_channel = SamplesByChannel::new();
+            for position in target.positions() {
+                samples_by_channel.set(position, sample);
+            }
+
+            Ok(samples_by_channel)
+        }))
+    }
+
+    /// Iterates through the samples with a window of upcoming samples. (IE, for FIR filtering or onset detection.)
+    /// Each window starts with the current sample, followed by the next 'lookahead' samples. Windows near the end of
+    /// the wav are padded with silence
+    ///
+    /// # Arguments
+    ///
+    /// * 'lookahead' - The number of upcoming samples in each window
+    pub fn windowed(
+        self,
+        lookahead: usize,
+    ) -> impl Iterator<Item = Result<Vec<SamplesByChannel<f32>>>> {
+        let mut silence = SamplesByChannel::new();
+        for position in self.open_wav.channels().positions() {
+            silence.set(position, 0.0);
+        }
+
+        let mut samples_remaining = self.open_wav.len_samples();
+        let mut samples_itr = self.into_iter();
+        let mut window = VecDeque::with_capacity(lookahead + 1);
+
+        iter::from_fn(move || {
+            if samples_remaining == 0 {
+                return None;
+            }
+
+            while window.len() <= lookahead {
+                match samples_itr.next() {
+                    Some(Ok(samples)) => window.push_back(samples),
+                    Some(Err(err)) => {
+                        samples_remaining = 0;
+                        return Some(Err(err));
+                    }
+                    None => window.push_back(silence),
+                }
+            }
+
+            let samples_window = window.iter().copied().collect();
+            window.pop_front();
+            samples_remaining -= 1;
+
+            Some(Ok(samples_window))
+        })
+    }
+
+    /// Multiplies every sample by 'gain'
+    ///
+    /// # Arguments
+    ///
+    /// * 'gain' - The linear gain. (IE, 0.5 halves the level of the wav)
+    pub fn scaled(self, gain: f32) -> impl Iterator<Item = Result<SamplesByChannel<f32>>> {
+        let channels = *self.open_wav.channels();
+
+        self.into_iter().map(move |samples_result| {
+            let samples = samples_result?;
+
+            let mut scaled_samples = SamplesByChannel::new();
+            for position in channels.positions() {
+                if let Some(sample) = samples.get(position) {
+                    scaled_samples.set(position, sample * gain);
+                }
+            }
+
+            Ok(scaled_samples)
+        })
+    }
 }
 
 impl<T> IntoIterator for StreamWavReader<T> {
@@ -110,145 +236,32 @@ impl<T> IntoIterator for StreamWavReader<T> {
             open_wav: self.open_wav,
             read_sample_from_stream: self.read_sample_from_stream,
             current_sample: 0,
+            stopped: false,
         }
     }
 }
 
 impl<T> StreamWavReaderIterator<T> {
+    /// The number of bytes that have been read from the underlying stream, including the headers before the samples
+    pub fn bytes_read(&self) -> usize {
+        self.open_wav.bytes_read()
+    }
+
     fn read_samples(&mut self) -> Result<SamplesByChannel<T>> {
-        // Channels are cloned, because otherwise it holds an immutable borrow of self
-        let channels = self.open_wav.channels().clone();
+        // Channels are copied, because otherwise it holds an immutable borrow of self
+        let channels = *self.open_wav.channels();
 
+        let sample = self.current_sample;
         self.current_sample += 1;
 
-        Ok(SamplesByChannel {
-            front_left: if channels.front_left {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            front_right: if channels.front_right {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            front_center: if channels.front_center {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            low_frequency: if channels.low_frequency {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            back_left: if channels.back_left {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            back_right: if channels.back_right {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            front_left_of_center: if channels.front_left_of_center {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            front_right_of_center: if channels.front_right_of_center {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            back_center: if channels.back_center {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            side_left: if channels.side_left {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            side_right: if channels.side_right {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            top_center: if channels.top_center {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            top_front_left: if channels.top_front_left {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            top_front_center: if channels.top_front_center {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            top_front_right: if channels.top_front_right {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            top_back_left: if channels.top_back_left {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            top_back_center: if channels.top_back_center {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-            top_back_right: if channels.top_back_right {
-                Some((*self.read_sample_from_stream)(
-                    &mut self.open_wav.reader(),
-                )?)
-            } else {
-                None
-            },
-        })
+        let mut samples_by_channel = SamplesByChannel::new();
+        for position in channels.positions() {
+            let value = (*self.read_sample_from_stream)(&mut self.open_wav.reader())
+                .map_err(|err| sample_error(err, sample, position))?;
+            samples_by_channel.set(position, value);
+        }
+
+        Ok(samples_by_channel)
     }
 }
 
@@ -256,10 +269,114 @@ impl<T> Iterator for StreamWavReaderIterator<T> {
     type Item = Result<SamplesByChannel<T>>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.current_sample >= self.open_wav.len_samples() {
-            None
-        } else {
-            Some(self.read_samples())
+        if self.stopped || self.current_sample >= self.open_wav.len_samples() {
+            return None;
+        }
+
+        let samples_result = self.read_samples();
+
+        // After an error, the reader is no longer aligned on a sample
+        if samples_result.is_err() {
+            self.stopped = true;
+        }
+
+        Some(samples_result)
+    }
+}
+
+impl<T> FusedIterator for StreamWavReaderIterator<T> {}
+
+impl<T: Copy + Default> LenientStreamWavReaderIterator<T> {
+    // Reads all of the frame's bytes, even if part of the frame can not be read. Returns the first error, or None
+    // if the whole frame could not be skipped over
+    fn read_frame_bytes(&mut self, frame: &mut [u8]) -> Option<Result<()>> {
+        let reader = self.open_wav.reader();
+        let mut first_error = None;
+        let mut bytes_read = 0;
+
+        while bytes_read < frame.len() {
+            match reader.read(&mut frame[bytes_read..]) {
+                Ok(0) => {
+                    return match first_error {
+                        Some(_) => None,
+                        None => Some(Err(Error::new(
+                            ErrorKind::UnexpectedEof,
+                            "The wav ended in the middle of a frame",
+                        ))),
+                    }
+                }
+                Ok(read) => bytes_read += read,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => match first_error {
+                    // The rest of the frame can not be skipped over
+                    Some(_) => return None,
+                    None => first_error = Some(err),
+                },
+            }
+        }
+
+        Some(match first_error {
+            Some(err) => Err(err),
+            None => Ok(()),
+        })
+    }
+
+    fn samples_from_frame(&self, sample: usize, frame: &[u8]) -> Result<SamplesByChannel<T>> {
+        let mut cursor = Cursor::new(frame);
+        let mut samples_by_channel = SamplesByChannel::new();
+        for position in self.open_wav.channels().positions() {
+            let value = (*self.read_sample_from_stream)(&mut cursor)
+                .map_err(|err| sample_error(err, sample, position))?;
+            samples_by_channel.set(position, value);
+        }
+
+        Ok(samples_by_channel)
+    }
+
+    fn silent_frame(&self) -> SamplesByChannel<T> {
+        let mut samples_by_channel = SamplesByChannel::new();
+        for position in self.open_wav.channels().positions() {
+            samples_by_channel.set(position, T::default());
+        }
+
+        samples_by_channel
+    }
+}
+
+impl<T: Copy + Default> Iterator for LenientStreamWavReaderIterator<T> {
+    type Item = Result<SamplesByChannel<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.stopped || self.current_sample >= self.open_wav.len_samples() {
+            return None;
+        }
+
+        self.current_sample += 1;
+
+        let mut frame = vec![0u8; self.open_wav.frame_bytes()];
+        let err = match self.read_frame_bytes(&mut frame) {
+            Some(Ok(())) => return Some(self.samples_from_frame(self.current_sample - 1, &frame)),
+            Some(Err(err)) => err,
+            None => {
+                // The reader is no longer aligned to a frame
+                self.stopped = true;
+                return Some(Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Sample {}: Could not skip over a frame that can not be read",
+                        self.current_sample - 1
+                    ),
+                )));
+            }
+        };
+
+        match self.on_error {
+            ErrorPolicy::SkipFrame => Some(Ok(self.silent_frame())),
+            ErrorPolicy::Stop => {
+                self.stopped = true;
+                None
+            }
+            ErrorPolicy::Propagate => Some(Err(frame_error(err, self.current_sample - 1))),
         }
     }
 }
@@ -0,0 +1,87 @@
+use std::io::{Read, Result};
+use std::iter::FusedIterator;
+
+use crate::open_wav::OpenWav;
+use crate::samples_by_channel::SamplesByChannel;
+use crate::wav_sample::WavSample;
+use crate::OpenWavReader;
+use crate::TypedStreamReader;
+
+use super::frame_error;
+
+impl<TReader: Read> OpenWavReader<TReader> {
+    /// Reads the wav as samples of type T, without trait objects. (IE, get_typed_stream_reader::<f32>()) Note that
+    /// downsampling is not supported
+    pub fn get_typed_stream_reader<T: WavSample>(self) -> Result<TypedStreamReader<TReader, T>> {
+        // Returns an error if the samples can't be read as T
+        T::decode_into(
+            self.header.sample_format,
+            self.rounding_mode,
+            &[],
+            &mut Vec::new(),
+        )?;
+
+        let positions = self.header.channels.positions().collect();
+        let frame = vec![0u8; self.frame_bytes()];
+
+        Ok(TypedStreamReader {
+            open_wav: self,
+            positions,
+            frame,
+            values: Vec::new(),
+            current_sample: 0,
+            stopped: false,
+        })
+    }
+}
+
+impl<TReader: Read, T: WavSample> TypedStreamReader<TReader, T> {
+    pub fn info(&self) -> &OpenWavReader<TReader> {
+        &self.open_wav
+    }
+
+    fn read_samples(&mut self) -> Result<SamplesByChannel<T>> {
+        let sample = self.current_sample;
+        self.open_wav
+            .reader
+            .read_exact(&mut self.frame)
+            .map_err(|err| frame_error(err, sample))?;
+        self.current_sample += 1;
+
+        T::decode_into(
+            self.open_wav.header.sample_format,
+            self.open_wav.rounding_mode,
+            &self.frame,
+            &mut self.values,
+        )
+        .map_err(|err| frame_error(err, sample))?;
+
+        let mut samples_by_channel = SamplesByChannel::new();
+        for (position, value) in self.positions.iter().zip(self.values.iter()) {
+            samples_by_channel.set(*position, *value);
+        }
+
+        Ok(samples_by_channel)
+    }
+}
+
+impl<TReader: Read, T: WavSample> Iterator for TypedStreamReader<TReader, T> {
+    type Item = Result<SamplesByChannel<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.stopped || self.current_sample >= self.open_wav.len_samples() {
+            return None;
+        }
+
+        let samples_result = self.read_samples();
+
+        // After an error, the reader is no longer aligned on a sample
+        if samples_result.is_err() {
+            self.stopped = true;
+        }
+
+        Some(samples_result)
+    }
+}
+
+impl<TReader: Read, T: WavSample> FusedIterator for TypedStreamReader<TReader, T> {}
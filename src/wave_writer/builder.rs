@@ -0,0 +1,365 @@
+use std::fs::File;
+use std::io::{BufWriter, Error, ErrorKind, Result, Seek, Write};
+use std::path::Path;
+
+use super::OpenWavWriter;
+use crate::wave_header::{FmtChunkStyle, SampleFormatSize, WavHeader};
+use crate::WriteEx;
+
+/// Configures how a wav is written. Use this instead of write_wav when the defaults need to be changed
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+///
+/// use wave_stream::wave_header::{Channels, FmtChunkStyle, SampleFormat, WavHeader};
+/// use wave_stream::wave_writer::WavWriterBuilder;
+///
+/// let header = WavHeader {
+///     sample_format: SampleFormat::Int16,
+///     channels: Channels::new().front_left().front_right(),
+///     sample_rate: 44100,
+/// };
+///
+/// let open_wav = WavWriterBuilder::new(header)
+///     .fmt_style(FmtChunkStyle::Classic)
+///     .buffer_capacity(64 * 1024)
+///     .build_to_path(Path::new("classic.wav"))
+///     .unwrap();
+/// ```
+pub struct WavWriterBuilder {
+    header: WavHeader,
+    fmt_style: FmtChunkStyle,
+    buffer_capacity: Option<usize>,
+    auto_flush: bool,
+    size_placeholder: Option<u32>,
+    fixed_data_size: Option<usize>,
+    raw_fmt_chunk: Option<Vec<u8>>,
+}
+
+/// The placeholder size that open-ended wavs are usually written with
+pub const OPEN_ENDED_SIZE: u32 = 0xFFFFFFFF;
+
+impl WavWriterBuilder {
+    /// Creates a builder with the default options
+    ///
+    /// # Arguments
+    ///
+    /// * 'header' - The header information in the wav. This specifies things like sampling rate, sample bit depth, ect
+    pub fn new(header: WavHeader) -> WavWriterBuilder {
+        WavWriterBuilder {
+            header,
+            fmt_style: FmtChunkStyle::Extensible,
+            buffer_capacity: None,
+            auto_flush: true,
+            size_placeholder: None,
+            fixed_data_size: None,
+            raw_fmt_chunk: None,
+        }
+    }
+
+    /// The layout of the fmt chunk. Defaults to FmtChunkStyle::Extensible
+    pub fn fmt_style(mut self, fmt_style: FmtChunkStyle) -> WavWriterBuilder {
+        self.fmt_style = fmt_style;
+
+        self
+    }
+
+    /// The capacity of the BufWriter that build_to_path creates. Defaults to BufWriter's default capacity
+    pub fn buffer_capacity(mut self, buffer_capacity: usize) -> WavWriterBuilder {
+        self.buffer_capacity = Some(buffer_capacity);
+
+        self
+    }
+
+    /// If the wav is flushed when the OpenWavWriter is dropped. Defaults to true. When false, flush must be called,
+    /// otherwise the wav's header will not have the correct length
+    pub fn auto_flush(mut self, auto_flush: bool) -> WavWriterBuilder {
+        self.auto_flush = auto_flush;
+
+        self
+    }
+
+    /// Writes 'size_placeholder' as the size of the RIFF and data chunks, and never replaces it with the actual
+    /// size. (Typically OPEN_ENDED_SIZE.) This is non-standard: It allows piping a wav to a player that starts
+    /// playing immediately and ignores the size, but readers that trust the size will read past the end of the wav
+    ///
+    /// # Arguments
+    ///
+    /// * 'size_placeholder' - The size to write in the RIFF and data chunks
+    pub fn open_ended(mut self, size_placeholder: u32) -> WavWriterBuilder {
+        self.size_placeholder = Some(size_placeholder);
+
+        self
+    }
+
+    /// Writes 'data_bytes' as the size of the data chunk, instead of the size of the samples that are written. This
+    /// supports systems that reserve space for the samples before they are written: The caller must write exactly
+    /// 'data_bytes' of samples, otherwise the wav will be corrupt. Can not be combined with open_ended
+    ///
+    /// # Arguments
+    ///
+    /// * 'data_bytes' - The size of the data chunk. It must be a multiple of the size of each sample, across all
+    ///   channels
+    pub fn fixed_data_size(mut self, data_bytes: usize) -> WavWriterBuilder {
+        self.fixed_data_size = Some(data_bytes);
+
+        self
+    }
+
+    /// Writes 'raw_fmt_chunk' as the fmt chunk, instead of generating a fmt chunk from the header. (IE, from
+    /// OpenWavReader::raw_fmt_chunk()) The fmt chunk must describe the same header that the builder was created with.
+    /// Overrides fmt_style
+    ///
+    /// # Arguments
+    ///
+    /// * 'raw_fmt_chunk' - The contents of the fmt chunk, excluding its id and length
+    pub fn raw_fmt_chunk(mut self, raw_fmt_chunk: Vec<u8>) -> WavWriterBuilder {
+        self.raw_fmt_chunk = Some(raw_fmt_chunk);
+
+        self
+    }
+
+    /// Starts writing a wav to a Path
+    ///
+    /// # Arguments
+    ///
+    /// * 'file_path' - The path to where the wav will be written
+    pub fn build_to_path(self, file_path: &Path) -> Result<OpenWavWriter> {
+        let file = File::create(file_path)?;
+        let writer = match self.buffer_capacity {
+            Some(buffer_capacity) => BufWriter::with_capacity(buffer_capacity, file),
+            None => BufWriter::new(file),
+        };
+
+        let mut open_wav = self.build(writer)?;
+        open_wav.path = Some(file_path.to_path_buf());
+
+        Ok(open_wav)
+    }
+
+    /// Starts writing a wav to a (Write + Seek) struct
+    ///
+    /// # Arguments
+    ///
+    /// * 'writer' - The (Write + Seek) struct to write the wav into. It is strongly recommended that this struct implement some form of buffering, such as via a BufWriter
+    pub fn build<TWriter: 'static + Write + Seek>(
+        self,
+        mut writer: TWriter,
+    ) -> Result<OpenWavWriter> {
+        if let Some(data_bytes) = self.fixed_data_size {
+            self.validate_fixed_data_size(data_bytes)?;
+        }
+
+        // Write RIFF header and format
+        match self.size_placeholder {
+            Some(size_placeholder) => {
+                writer.write_all(b"RIFF")?;
+                writer.write_u32(size_placeholder)?;
+                writer.write_all(b"WAVE")?;
+            }
+            None => writer.write_all(b"RIFF    WAVE")?,
+        }
+
+        match &self.raw_fmt_chunk {
+            Some(raw_fmt_chunk) => {
+                self.validate_raw_fmt_chunk(raw_fmt_chunk)?;
+
+                writer.write_all(b"fmt ")?;
+                writer.write_u32(raw_fmt_chunk.len() as u32)?;
+                writer.write_all(raw_fmt_chunk)?;
+            }
+            None => WavHeader::to_writer_with_style(&mut writer, &self.header, self.fmt_style)?,
+        }
+
+        let mut open_wav = OpenWavWriter::new(writer, self.header)?;
+        open_wav.flush_on_drop = self.auto_flush;
+        open_wav.size_placeholder = self.size_placeholder;
+        open_wav.fixed_data_size = self.fixed_data_size;
+
+        Ok(open_wav)
+    }
+
+    fn validate_raw_fmt_chunk(&self, raw_fmt_chunk: &[u8]) -> Result<()> {
+        let header = WavHeader::from_raw_fmt_chunk(raw_fmt_chunk)?;
+        if header != self.header {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "The fmt chunk is for {:?}, but the wav is {:?}",
+                    header, self.header
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn validate_fixed_data_size(&self, data_bytes: usize) -> Result<()> {
+        if self.size_placeholder.is_some() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "A wav can not be both open-ended and have a fixed data size",
+            ));
+        }
+
+        let frame_bytes = self.header.channels.count() as usize
+            * self.header.sample_format.bytes_per_sample() as usize;
+        if data_bytes % frame_bytes != 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "{} bytes is not a whole number of samples. Each sample is {} bytes",
+                    data_bytes, frame_bytes
+                ),
+            ));
+        }
+
+        if data_bytes / frame_bytes > self.header.max_samples() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Wav files can only go up to 4GB.",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::open_wav::OpenWav;
+    use crate::read_wav_from_file_path;
+    use crate::samples_by_channel::SamplesByChannel;
+    use crate::wave_header::{Channels, SampleFormat};
+    use crate::wave_reader::{ErrorPolicy, RandomAccessOpenWavReader, StreamOpenWavReader};
+
+    #[test]
+    fn build_classic_fmt_chunk() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("classic.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Int16,
+            channels: Channels::new().front_left().front_right(),
+            sample_rate: 44100,
+        };
+
+        let open_wav = WavWriterBuilder::new(header)
+            .fmt_style(FmtChunkStyle::Classic)
+            .buffer_capacity(16)
+            .build_to_path(&path)
+            .unwrap();
+
+        let samples = (0..100i16).map(|sample| {
+            Ok(SamplesByChannel::new()
+                .front_left(sample)
+                .front_right(-sample))
+        });
+        open_wav.write_all_i16(samples).unwrap();
+
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(b"fmt ", &bytes[12..16]);
+        assert_eq!(16, u32::from_le_bytes(bytes[16..20].try_into().unwrap()));
+        // wFormatTag: PCM
+        assert_eq!(1, u16::from_le_bytes(bytes[20..22].try_into().unwrap()));
+        assert_eq!(b"data", &bytes[36..40]);
+
+        let open_wav = read_wav_from_file_path(&path).unwrap();
+        assert_eq!(header.channels, *open_wav.channels());
+        assert_eq!(44100, open_wav.sample_rate());
+        assert_eq!(100, open_wav.len_samples());
+
+        let mut reader = open_wav.get_random_access_i16_reader().unwrap();
+        for sample in 0..100usize {
+            assert_eq!(
+                SamplesByChannel::new()
+                    .front_left(sample as i16)
+                    .front_right(-(sample as i16)),
+                reader.read_sample(sample).unwrap(),
+                "Wrong sample read at {sample}"
+            );
+        }
+    }
+
+    #[test]
+    fn build_classic_fmt_chunk_unsupported_channels() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("classic.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Float,
+            channels: Channels::new().front_left().front_center(),
+            sample_rate: 44100,
+        };
+
+        let err = WavWriterBuilder::new(header)
+            .fmt_style(FmtChunkStyle::Classic)
+            .build_to_path(&path)
+            .err()
+            .expect("front_left and front_center can't be written without a channel mask");
+        assert_eq!(std::io::ErrorKind::InvalidInput, err.kind());
+    }
+
+    #[test]
+    fn build_open_ended() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("open_ended.wav");
+
+        let header = WavHeader {
+            sample_format: SampleFormat::Int16,
+            channels: Channels::new().front_left().front_right(),
+            sample_rate: 44100,
+        };
+
+        let open_wav = WavWriterBuilder::new(header)
+            .open_ended(OPEN_ENDED_SIZE)
+            .build_to_path(&path)
+            .unwrap();
+
+        let samples = (0..100i16).map(|sample| {
+            Ok(SamplesByChannel::new()
+                .front_left(sample)
+                .front_right(-sample))
+        });
+        open_wav.write_all_i16(samples).unwrap();
+
+        let bytes = fs::read(&path).unwrap();
+        let data_start = bytes
+            .windows(4)
+            .position(|window| window == b"data")
+            .expect("data chunk missing")
+            + 8;
+        assert_eq!(
+            OPEN_ENDED_SIZE,
+            u32::from_le_bytes(bytes[4..8].try_into().unwrap()),
+            "Wrong RIFF size"
+        );
+        assert_eq!(
+            OPEN_ENDED_SIZE,
+            u32::from_le_bytes(bytes[data_start - 4..data_start].try_into().unwrap()),
+            "Wrong data size"
+        );
+        assert_eq!(data_start + 400, bytes.len());
+
+        // The stream ends when the file ends, instead of when the data chunk ends
+        let samples: Vec<SamplesByChannel<i16>> = read_wav_from_file_path(&path)
+            .unwrap()
+            .get_stream_i16_reader()
+            .unwrap()
+            .into_lenient_iter(ErrorPolicy::Stop)
+            .map(|samples_result| samples_result.unwrap())
+            .collect();
+        assert_eq!(100, samples.len());
+        assert_eq!(
+            SamplesByChannel::new().front_left(99).front_right(-99),
+            samples[99]
+        );
+    }
+}
@@ -1,7 +1,21 @@
-use std::io::{Result, Seek, SeekFrom, Write};
+use std::fs::File;
+use std::io::{BufReader, Error, ErrorKind, Result, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::byte_counter::ByteCounter;
+use crate::chunks::{
+    display_title_chunk, validate_origination_date, validate_origination_time, AcidChunk,
+    BroadcastExtension, CartChunk, ChannelPeak, PeakChunk,
+};
+use crate::offsets::{ByteOffset, SampleIndex};
 use crate::open_wav::OpenWav;
+use crate::read_wav_from_file_path;
+use crate::samples_by_channel::SamplesByChannel;
+use crate::wav_sample::WavSample;
 use crate::wave_header::Channels;
+use crate::wave_reader::OpenWavReader;
+use crate::ReadEx;
 use crate::SampleFormat;
 use crate::SampleFormatSize;
 use crate::WavHeader;
@@ -11,20 +25,59 @@ pub trait WriteSeek: Write + Seek {}
 
 impl<TWriteSeek: Write + Seek> WriteSeek for TWriteSeek {}
 
+/// The byte order that floating point samples are written in
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ByteOrder {
+    /// Little-endian, as defined by the wav format
+    #[default]
+    Little,
+    /// Big-endian. This is non-standard: Only use it for tools that read a wav's samples directly and require
+    /// big-endian floats. wave_stream reads them back with OpenWavReader::set_byte_order(ByteOrder::Big)
+    Big,
+}
+
 /// An open wav writer
 pub struct OpenWavWriter {
-    writer: Box<dyn WriteSeek>,
+    writer: ByteCounter<Box<dyn WriteSeek>>,
     header: WavHeader,
     data_start: usize,
     chunk_size_written: bool,
     samples_written: usize,
     max_samples: usize,
+    flush_on_drop: bool,
+    // The path that the wav is written to, if it's written to a file
+    path: Option<PathBuf>,
+    data_started: bool,
+    // Chunks that are written before the data chunk, by name
+    extra_chunks: Vec<([u8; 4], Vec<u8>)>,
+    // When set, written as the RIFF and data chunk sizes, which are never updated
+    size_placeholder: Option<u32>,
+    // When set, written as the data chunk size, regardless of how many samples are written
+    fixed_data_size: Option<usize>,
+    byte_order: ByteOrder,
+    // When set, the loudest sample written to each channel, which is written in a PEAK chunk
+    peaks: Option<Vec<ChannelPeak>>,
+    // The position of the PEAK chunk's contents
+    peak_chunk_start: usize,
+    // Reused for each write when peaks are tracked, so that writing doesn't allocate per sample
+    peak_buffer: Vec<u8>,
 }
 
+type WriteSampleToStream<T> = Box<dyn Fn(&mut dyn Write, T) -> Result<()>>;
+
+// Writes a sample that's borrowed, so that it can still be passed to OnWrite
+type WriteBorrowedSampleToStream<T> = Box<dyn Fn(&mut dyn Write, &T) -> Result<()>>;
+
+/// Called with the index of each sample that a RandomAccessWavWriter writes, and the sample. It must be Send,
+/// because the writer can be moved to another thread
+pub type OnWrite<T> = Box<dyn FnMut(usize, &SamplesByChannel<T>) + Send>;
+
 /// An open random access wav writer
 pub struct RandomAccessWavWriter<T> {
     open_wav: OpenWavWriter,
-    write_sample_to_stream: Box<dyn Fn(&mut dyn Write, T) -> Result<()>>,
+    write_sample_to_stream: WriteBorrowedSampleToStream<T>,
+    strict_channels: bool,
+    on_write: Option<OnWrite<T>>,
 }
 
 impl OpenWavWriter {
@@ -45,33 +98,357 @@ impl OpenWavWriter {
         header: WavHeader,
         max_samples: usize,
     ) -> Result<OpenWavWriter> {
-        writer.write_str("data")?;
-        writer.write_u32(0)?;
-
-        let data_start = writer.stream_position()? as usize;
+        // The RIFF header and fmt chunk are already written
+        let bytes_written = writer.stream_position()? as usize;
 
         Ok(OpenWavWriter {
-            writer: Box::new(writer),
+            writer: ByteCounter::new(Box::new(writer), bytes_written),
             header,
-            data_start,
+            data_start: 0,
             chunk_size_written: false,
             samples_written: 0,
             max_samples,
+            flush_on_drop: true,
+            path: None,
+            data_started: false,
+            extra_chunks: Vec::new(),
+            size_placeholder: None,
+            fixed_data_size: None,
+            byte_order: ByteOrder::Little,
+            peaks: None,
+            peak_chunk_start: 0,
+            peak_buffer: Vec::new(),
         })
     }
 
+    /// Sets the loop and tempo metadata. Must be called before any samples are written
+    ///
+    /// # Arguments
+    ///
+    /// * 'acid_chunk' - The loop and tempo metadata to write in an acid chunk
+    pub fn set_acid_chunk(&mut self, acid_chunk: AcidChunk) -> Result<()> {
+        let mut chunk = Vec::new();
+        acid_chunk.to_writer(&mut chunk)?;
+
+        self.set_chunk(b"acid", chunk)
+    }
+
+    /// Sets the radio automation metadata, in a cart chunk. Must be called before any samples are written
+    ///
+    /// # Arguments
+    ///
+    /// * 'cart_chunk' - The radio automation metadata
+    pub fn set_cart_chunk(&mut self, cart_chunk: &CartChunk) -> Result<()> {
+        let mut chunk = Vec::new();
+        cart_chunk.to_writer(&mut chunk)?;
+
+        self.set_chunk(b"cart", chunk)
+    }
+
+    /// Sets the title that Windows displays for the sound, in a DISP chunk. Must be called before any samples are
+    /// written
+    ///
+    /// # Arguments
+    ///
+    /// * 'title' - The title to display
+    pub fn set_display_title(&mut self, title: &str) -> Result<()> {
+        self.set_chunk(b"DISP", display_title_chunk(title))
+    }
+
+    /// Sets the date and time that the wav was created, in a bext chunk. Must be called before any samples are
+    /// written
+    ///
+    /// # Arguments
+    ///
+    /// * 'date' - The date, formatted as yyyy-mm-dd
+    /// * 'time' - The time, formatted as hh:mm:ss
+    pub fn set_origination(&mut self, date: &str, time: &str) -> Result<()> {
+        validate_origination_date(date)?;
+        validate_origination_time(time)?;
+
+        let broadcast_extension = BroadcastExtension {
+            origination_date: date.to_string(),
+            origination_time: time.to_string(),
+            ..Default::default()
+        };
+
+        let mut chunk = Vec::new();
+        broadcast_extension.to_writer(&mut chunk)?;
+
+        self.set_chunk(b"bext", chunk)
+    }
+
+    /// Sets the broadcast extension, in a bext chunk. This replaces the bext chunk that set_origination writes. Must
+    /// be called before any samples are written
+    ///
+    /// # Arguments
+    ///
+    /// * 'broadcast_extension' - The broadcast extension. The origination date and time must be empty, or formatted
+    ///   as yyyy-mm-dd and hh:mm:ss
+    pub fn set_broadcast_extension(
+        &mut self,
+        broadcast_extension: &BroadcastExtension,
+    ) -> Result<()> {
+        if !broadcast_extension.origination_date.is_empty() {
+            validate_origination_date(&broadcast_extension.origination_date)?;
+        }
+        if !broadcast_extension.origination_time.is_empty() {
+            validate_origination_time(&broadcast_extension.origination_time)?;
+        }
+
+        let mut chunk = Vec::new();
+        broadcast_extension.to_writer(&mut chunk)?;
+
+        self.set_chunk(b"bext", chunk)
+    }
+
+    /// Sets the byte order that floating point samples are written in. Defaults to ByteOrder::Little. Big-endian
+    /// wavs are non-standard, and most programs will not read them correctly. Must be called before any samples are
+    /// written
+    ///
+    /// # Arguments
+    ///
+    /// * 'byte_order' - The byte order of the samples
+    pub fn set_byte_order(&mut self, byte_order: ByteOrder) -> Result<()> {
+        if self.data_started {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "The byte order must be set before samples are written",
+            ));
+        }
+
+        if byte_order == ByteOrder::Big && self.header.sample_format != SampleFormat::Float {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Only floating point samples can be written big-endian",
+            ));
+        }
+
+        self.byte_order = byte_order;
+
+        Ok(())
+    }
+
+    /// Tracks the loudest sample in each channel while samples are written, and writes them in a PEAK chunk when the
+    /// wav is flushed. This avoids scanning the samples again after they are written. Only floating point wavs can
+    /// track peaks. Must be called before any samples are written
+    pub fn track_peaks(&mut self) -> Result<()> {
+        if self.data_started {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "Peaks must be tracked before samples are written",
+            ));
+        }
+
+        if self.header.sample_format != SampleFormat::Float {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Only floating point wavs can track peaks",
+            ));
+        }
+
+        self.peaks = Some(vec![
+            ChannelPeak {
+                value: 0.0,
+                position: 0,
+            };
+            self.num_channels() as usize
+        ]);
+
+        Ok(())
+    }
+
+    // Writes whole samples, across all channels, starting at 'first_sample'. When peaks are tracked, the samples are
+    // written into a buffer first, so that their peaks can be tracked
+    fn write_frames_with(
+        &mut self,
+        first_sample: usize,
+        write_frames: impl FnOnce(&mut dyn Write) -> Result<()>,
+    ) -> Result<()> {
+        if self.peaks.is_none() {
+            return write_frames(&mut self.writer);
+        }
+
+        let mut bytes = std::mem::take(&mut self.peak_buffer);
+        bytes.clear();
+
+        let result = write_frames(&mut bytes)
+            .and_then(|_| self.track_peaks_in(first_sample, &bytes))
+            .and_then(|_| self.writer.write_all(&bytes));
+
+        self.peak_buffer = bytes;
+        result
+    }
+
+    // Updates the peaks, if they are tracked, with samples that are about to be written. 'bytes' holds whole samples,
+    // across all channels, starting at 'first_sample'
+    fn track_peaks_in(&mut self, first_sample: usize, bytes: &[u8]) -> Result<()> {
+        let frame_bytes = self.frame_bytes();
+        for (sample, frame) in bytes.chunks(frame_bytes).enumerate() {
+            for (channel_index, channel_bytes) in frame.chunks(4).enumerate() {
+                self.track_channel_peak(first_sample + sample, channel_index, channel_bytes)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Updates a channel's peak, if peaks are tracked, with a single channel of a sample that is about to be written
+    fn track_channel_peak(
+        &mut self,
+        sample: usize,
+        channel_index: usize,
+        mut bytes: &[u8],
+    ) -> Result<()> {
+        let value = match self.byte_order {
+            ByteOrder::Little => bytes.read_f32()?,
+            ByteOrder::Big => bytes.read_f32_be()?,
+        };
+
+        if let Some(peaks) = self.peaks.as_mut() {
+            let peak = &mut peaks[channel_index];
+            if value.abs() > peak.value {
+                peak.value = value.abs();
+                peak.position = sample as u32;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Selects how samples of type T are written, in the wav's byte order
+    fn sample_writer<T: WavSample>(&self) -> Result<WriteSampleToStream<T>> {
+        let write_sample_to_stream = T::write_to(self.header.sample_format)?;
+
+        match self.byte_order {
+            ByteOrder::Little => Ok(Box::new(write_sample_to_stream)),
+            ByteOrder::Big => Ok(Box::new(move |writer: &mut dyn Write, value: T| {
+                // Samples are always converted to little-endian floats
+                let mut bytes = [0u8; 4];
+                write_sample_to_stream(&mut &mut bytes[..], value)?;
+                bytes.reverse();
+
+                writer.write_all(&bytes)
+            })),
+        }
+    }
+
+    /// Sets a chunk, by its id, that is written between the fmt and data chunks. Any chunk can be written, including
+    /// proprietary chunks that wave_stream doesn't otherwise understand. Setting a chunk again replaces it. A pad byte
+    /// is written after chunks with an odd length. Must be called before any samples are written
+    ///
+    /// # Arguments
+    ///
+    /// * 'id' - The chunk's 4-character id. (IE, b"LGWV")
+    /// * 'chunk' - The chunk's contents, excluding its id and length
+    pub fn set_chunk(&mut self, id: &[u8; 4], chunk: Vec<u8>) -> Result<()> {
+        if self.data_started {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "Chunks must be set before samples are written",
+            ));
+        }
+
+        if id == b"fmt " || id == b"data" {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "The fmt and data chunks are written by the writer",
+            ));
+        }
+
+        if chunk.len() > u32::MAX as usize {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Chunks can only go up to 4GB",
+            ));
+        }
+
+        self.extra_chunks.retain(|(chunk_id, _)| chunk_id != id);
+        self.extra_chunks.push((*id, chunk));
+
+        Ok(())
+    }
+
+    /// Writes the extra chunks and the start of the data chunk. Called before samples are written
+    fn start_data(&mut self) -> Result<()> {
+        if self.data_started {
+            return Ok(());
+        }
+
+        if let Some(peaks) = self.peaks.as_ref() {
+            // Space is reserved for the PEAK chunk, which is written when the wav is flushed
+            let chunk_size = PeakChunk::chunk_size(peaks.len());
+            self.writer.write_all(b"PEAK")?;
+            self.writer.write_u32(chunk_size as u32)?;
+            self.peak_chunk_start = self.writer.stream_position()? as usize;
+            self.writer.write_all(&vec![0u8; chunk_size])?;
+        }
+
+        for (chunk_id, chunk) in self.extra_chunks.iter() {
+            if chunk_id == b"PEAK" && self.peaks.is_some() {
+                continue;
+            }
+
+            self.writer.write_all(chunk_id)?;
+            self.writer.write_u32(chunk.len() as u32)?;
+            self.writer.write_all(chunk)?;
+
+            // Chunks are word-aligned
+            if chunk.len() % 2 == 1 {
+                self.writer.write_all(&[0u8])?;
+            }
+        }
+
+        self.writer.write_str("data")?;
+        let data_size = match (self.size_placeholder, self.fixed_data_size) {
+            (Some(size_placeholder), _) => size_placeholder,
+            (None, Some(fixed_data_size)) => fixed_data_size as u32,
+            (None, None) => 0,
+        };
+        self.writer.write_u32(data_size)?;
+
+        self.data_start = self.writer.stream_position()? as usize;
+        self.data_started = true;
+
+        Ok(())
+    }
+
     /// Flushes all buffered data to the stream
     pub fn flush(&mut self) -> Result<()> {
+        self.start_data()?;
+
+        if let Some(peaks) = self.peaks.as_ref() {
+            let peak_chunk = PeakChunk {
+                version: 1,
+                timestamp: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |since_epoch| since_epoch.as_secs() as u32),
+                peaks: peaks.clone(),
+            };
+
+            self.writer
+                .seek(SeekFrom::Start(self.peak_chunk_start as u64))?;
+            peak_chunk.to_writer(&mut self.writer)?;
+        }
+
+        if self.size_placeholder.is_some() {
+            self.chunk_size_written = true;
+            return self.writer.flush();
+        }
+
         // data chunk
-        let chunk_size =
-            self.samples_written * (self.num_channels() * self.bytes_per_sample()) as usize;
+        let chunk_size = match self.fixed_data_size {
+            Some(fixed_data_size) => fixed_data_size,
+            None => self.samples_written * (self.num_channels() * self.bytes_per_sample()) as usize,
+        };
         self.writer
             .seek(SeekFrom::Start(self.data_start as u64 - 4u64))?;
         self.writer.write_u32(chunk_size as u32)?;
 
         // RIFF header
         self.writer.seek(SeekFrom::Start(4))?;
-        self.writer.write_u32((chunk_size + 32 - 8) as u32)?;
+        self.writer
+            .write_u32((self.data_start + chunk_size - 8) as u32)?;
 
         self.chunk_size_written = true;
 
@@ -80,10 +457,113 @@ impl OpenWavWriter {
         Ok(())
     }
 
+    /// Appends raw, little-endian sample bytes, without converting them. The bytes must be in the wav's sample
+    /// format, and must contain whole samples across all channels. (IE, a multiple of frame_bytes())
+    ///
+    /// # Arguments
+    ///
+    /// * 'bytes' - The bytes to append
+    pub fn write_raw_frames(&mut self, bytes: &[u8]) -> Result<()> {
+        if bytes.len() % self.frame_bytes() != 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "{} bytes is not a whole number of samples. Each sample is {} bytes",
+                    bytes.len(),
+                    self.frame_bytes()
+                ),
+            ));
+        }
+
+        let samples = bytes.len() / self.frame_bytes();
+        if self.samples_written + samples > self.max_samples {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "Wav files can only go up to 4GB.",
+            ));
+        }
+
+        self.start_data()?;
+        if self.peaks.is_some() {
+            self.track_peaks_in(self.samples_written, bytes)?;
+        }
+
+        let position = ByteOffset(self.data_start)
+            + SampleIndex(self.samples_written).to_byte_offset(self.frame_bytes());
+        self.writer.seek(SeekFrom::Start(position.into()))?;
+        self.writer.write_all(bytes)?;
+
+        self.samples_written += samples;
+        self.chunk_size_written = false;
+
+        Ok(())
+    }
+
+    /// Appends raw, little-endian sample bytes, like write_raw_frames. If the bytes end with a partial sample, it is
+    /// padded with zeros. Returns the number of bytes of padding that were written
+    ///
+    /// # Arguments
+    ///
+    /// * 'bytes' - The bytes to append
+    pub fn write_raw_frames_lenient(&mut self, bytes: &[u8]) -> Result<usize> {
+        let partial_frame_bytes = bytes.len() % self.frame_bytes();
+        if partial_frame_bytes == 0 {
+            self.write_raw_frames(bytes)?;
+            return Ok(0);
+        }
+
+        let padding = self.frame_bytes() - partial_frame_bytes;
+        let mut padded_bytes = Vec::with_capacity(bytes.len() + padding);
+        padded_bytes.extend_from_slice(bytes);
+        padded_bytes.resize(bytes.len() + padding, 0);
+
+        self.write_raw_frames(&padded_bytes)?;
+
+        Ok(padding)
+    }
+
+    /// Finishes writing the wav, and then opens it for reading. Only wavs that are written to a path can be reopened
+    pub fn reopen_as_reader(mut self) -> Result<OpenWavReader<BufReader<File>>> {
+        let path = match self.path.take() {
+            Some(path) => path,
+            None => {
+                return Err(Error::new(
+                    ErrorKind::Unsupported,
+                    "Only wavs that are written to a path can be reopened",
+                ))
+            }
+        };
+
+        self.flush()?;
+
+        // Closes the file
+        drop(self);
+
+        read_wav_from_file_path(&path)
+    }
+
+    /// Discards the writer without finishing the wav. Use this after an error, when flushing would fail: Otherwise,
+    /// dropping the writer flushes it, and panics if flushing fails. The wav is left partially written
+    pub fn abandon(mut self) {
+        self.flush_on_drop = false;
+    }
+
+    /// The header that the wav is written with
+    pub fn header(&self) -> &WavHeader {
+        &self.header
+    }
+
     /// The maximum number of samples that can be written without exceeding the 4GB limit
     pub fn max_samples(&self) -> usize {
         self.max_samples
     }
+
+    /// The number of bytes that have been written to the underlying stream, including the headers. (IE, for showing
+    /// progress while a wav is sent over a network) Headers that are updated when the wav is flushed are counted
+    /// again
+    pub fn bytes_written(&self) -> usize {
+        self.writer.count()
+    }
 }
 
 impl OpenWav for OpenWavWriter {
@@ -114,15 +594,22 @@ impl OpenWav for OpenWavWriter {
     fn len_samples(&self) -> usize {
         self.samples_written
     }
+
+    fn len_samples_u64(&self) -> u64 {
+        self.samples_written as u64
+    }
 }
 
 impl Drop for OpenWavWriter {
     fn drop(&mut self) {
-        if !self.chunk_size_written {
+        if self.flush_on_drop && !self.chunk_size_written {
             self.flush().unwrap();
         }
     }
 }
 
+mod builder;
 mod random;
 mod stream;
+
+pub use builder::{WavWriterBuilder, OPEN_ENDED_SIZE};
@@ -1,103 +1,45 @@
 use std::io::{Error, ErrorKind, Result, Seek, SeekFrom, Write};
 
+use super::OnWrite;
 use super::OpenWavWriter;
 use super::RandomAccessWavWriter;
-use super::SampleFormat;
-use super::WriteEx;
+use crate::offsets::{ByteOffset, SampleIndex};
 use crate::open_wav::OpenWav;
 use crate::samples_by_channel::SamplesByChannel;
+use crate::wav_sample::WavSample;
+use crate::wave_header::ChannelPosition;
 
 impl OpenWavWriter {
-    pub fn get_random_access_i8_writer(self) -> Result<RandomAccessWavWriter<i8>> {
-        match self.header.sample_format {
-            SampleFormat::Int8 => Ok(RandomAccessWavWriter {
-                open_wav: self,
-                write_sample_to_stream: Box::new(|mut writer: &mut dyn Write, value: i8| {
-                    writer.write_i8(value)
-                }),
-            }),
-            SampleFormat::Int16 => Ok(RandomAccessWavWriter {
-                open_wav: self,
-                write_sample_to_stream: Box::new(|mut writer: &mut dyn Write, value: i8| {
-                    writer.write_i8_as_i16(value)
-                }),
-            }),
-            SampleFormat::Int24 => Ok(RandomAccessWavWriter {
-                open_wav: self,
-                write_sample_to_stream: Box::new(|mut writer: &mut dyn Write, value: i8| {
-                    writer.write_i8_as_i24(value)
-                }),
-            }),
-            SampleFormat::Float => Ok(RandomAccessWavWriter {
-                open_wav: self,
-                write_sample_to_stream: Box::new(|mut writer: &mut dyn Write, value: i8| {
-                    writer.write_i8_as_f32(value)
-                }),
+    /// Writes samples of type T. (IE, get_random_access_writer::<f32>()) Note that downsampling is not supported
+    pub fn get_random_access_writer<T: WavSample>(mut self) -> Result<RandomAccessWavWriter<T>> {
+        let write_sample_to_stream = self.sample_writer::<T>()?;
+
+        self.start_data()?;
+
+        Ok(RandomAccessWavWriter {
+            open_wav: self,
+            write_sample_to_stream: Box::new(move |writer: &mut dyn Write, value: &T| {
+                write_sample_to_stream(writer, *value)
             }),
-        }
+            strict_channels: false,
+            on_write: None,
+        })
+    }
+
+    pub fn get_random_access_i8_writer(self) -> Result<RandomAccessWavWriter<i8>> {
+        self.get_random_access_writer()
     }
 
     pub fn get_random_access_i16_writer(self) -> Result<RandomAccessWavWriter<i16>> {
-        match self.header.sample_format {
-            SampleFormat::Int16 => Ok(RandomAccessWavWriter {
-                open_wav: self,
-                write_sample_to_stream: Box::new(|mut writer: &mut dyn Write, value: i16| {
-                    writer.write_i16(value)
-                }),
-            }),
-            SampleFormat::Int24 => Ok(RandomAccessWavWriter {
-                open_wav: self,
-                write_sample_to_stream: Box::new(|mut writer: &mut dyn Write, value: i16| {
-                    writer.write_i16_as_i24(value)
-                }),
-            }),
-            SampleFormat::Float => Ok(RandomAccessWavWriter {
-                open_wav: self,
-                write_sample_to_stream: Box::new(|mut writer: &mut dyn Write, value: i16| {
-                    writer.write_i16_as_f32(value)
-                }),
-            }),
-            _ => Err(Error::new(
-                ErrorKind::InvalidData,
-                "Converting to 16-bit int unsupported",
-            )),
-        }
+        self.get_random_access_writer()
     }
 
     pub fn get_random_access_i24_writer(self) -> Result<RandomAccessWavWriter<i32>> {
-        match self.header.sample_format {
-            SampleFormat::Int24 => Ok(RandomAccessWavWriter {
-                open_wav: self,
-                write_sample_to_stream: Box::new(|mut writer: &mut dyn Write, value: i32| {
-                    writer.write_i24(value)
-                }),
-            }),
-            SampleFormat::Float => Ok(RandomAccessWavWriter {
-                open_wav: self,
-                write_sample_to_stream: Box::new(|mut writer: &mut dyn Write, value: i32| {
-                    writer.write_i24_as_f32(value)
-                }),
-            }),
-            _ => Err(Error::new(
-                ErrorKind::InvalidData,
-                "Converting to 24-bit int unsupported",
-            )),
-        }
+        self.get_random_access_writer()
     }
 
     pub fn get_random_access_f32_writer(self) -> Result<RandomAccessWavWriter<f32>> {
-        match self.header.sample_format {
-            SampleFormat::Float => Ok(RandomAccessWavWriter {
-                open_wav: self,
-                write_sample_to_stream: Box::new(|mut writer: &mut dyn Write, value: f32| {
-                    writer.write_f32(value)
-                }),
-            }),
-            _ => Err(Error::new(
-                ErrorKind::InvalidData,
-                "Converting to 32-bit float unsupported",
-            )),
-        }
+        self.get_random_access_writer()
     }
 }
 
@@ -106,185 +48,193 @@ impl<T> RandomAccessWavWriter<T> {
         &(self.open_wav)
     }
 
+    /// When true, write_samples returns an error if a sample is missing a channel that's in the wav, or has a
+    /// channel that isn't in the wav. Otherwise, channels that aren't in the wav are ignored. Defaults to false.
+    /// (write_all always checks the channels)
+    ///
+    /// # Arguments
+    ///
+    /// * 'strict_channels' - If the channels in each sample must match the wav's channels exactly
+    pub fn set_strict_channels(&mut self, strict_channels: bool) {
+        self.strict_channels = strict_channels;
+    }
+
+    /// Sets a callback that's called after each call to write_samples() succeeds, (IE, to update a level meter,) with
+    /// the index of the sample and the sample. There is no callback by default
+    ///
+    /// # Arguments
+    ///
+    /// * 'on_write' - Called with the index of each sample that's written, and the sample
+    pub fn set_on_write(&mut self, on_write: OnWrite<T>) {
+        self.on_write = Some(on_write);
+    }
+
     pub fn write_samples(
         &mut self,
         sample: usize,
         samples_by_channel: SamplesByChannel<T>,
     ) -> Result<()> {
-        if sample >= self.open_wav.max_samples {
-            return Err(Error::new(
-                ErrorKind::Unsupported,
-                "Wav files can only go up to 4GB.",
-            ));
+        if self.strict_channels {
+            samples_by_channel.check_channels(self.open_wav.channels(), sample)?;
         }
 
-        // Pad the file if needed
-        if sample >= self.open_wav.samples_written {
-            self.open_wav.writer.seek(SeekFrom::End(0))?;
+        self.pad_to_sample(sample)?;
 
-            let samples_to_pad = (sample + 1) - self.open_wav.samples_written;
-            let padding_size = samples_to_pad
-                * (self.open_wav.num_channels() * self.open_wav.bytes_per_sample()) as usize;
-            let padding = vec![0u8; 1];
-            for _ in 0..padding_size {
-                self.open_wav.writer.write(&padding)?;
+        let position = ByteOffset(self.open_wav.data_start)
+            + SampleIndex(sample).to_byte_offset(self.open_wav.frame_bytes());
+
+        self.open_wav
+            .writer
+            .seek(SeekFrom::Start(position.into()))?;
+
+        self.open_wav.chunk_size_written = false;
+
+        let channels = self.open_wav.channels().clone();
+        let write_sample_to_stream = &self.write_sample_to_stream;
+        self.open_wav.write_frames_with(sample, |writer| {
+            for position in channels.positions() {
+                let sample = samples_by_channel
+                    .get(position)
+                    .unwrap_or_else(|| panic!("{:?} channel missing", position));
+                write_sample_to_stream(writer, sample)?;
             }
-            self.open_wav.samples_written = sample + 1;
+
+            Ok(())
+        })?;
+
+        if let Some(on_write) = self.on_write.as_mut() {
+            on_write(sample, &samples_by_channel);
         }
 
-        let sample_in_channels = sample * self.open_wav.num_channels() as usize;
-        let sample_in_bytes =
-            (sample_in_channels as u64) * (self.open_wav.bytes_per_sample() as u64);
-        let position = (self.open_wav.data_start as u64) + sample_in_bytes;
+        Ok(())
+    }
+
+    /// Writes a single channel's value, without touching the other channels in the sample
+    ///
+    /// # Arguments
+    ///
+    /// * 'sample' - The index of the sample to write
+    /// * 'channel' - The channel to write. It must be present in the wav's header
+    /// * 'value' - The value to write
+    pub fn write_channel_sample(
+        &mut self,
+        sample: usize,
+        channel: ChannelPosition,
+        value: T,
+    ) -> Result<()> {
+        let channel_index = match self
+            .open_wav
+            .channels()
+            .positions()
+            .position(|position| position == channel)
+        {
+            Some(channel_index) => channel_index,
+            None => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("{:?} is not in the wav", channel),
+                ))
+            }
+        };
+
+        self.pad_to_sample(sample)?;
+
+        let position = ByteOffset(self.open_wav.data_start)
+            + SampleIndex(sample).to_byte_offset(self.open_wav.frame_bytes())
+            + ByteOffset(channel_index * self.open_wav.bytes_per_sample() as usize);
 
         self.open_wav
             .writer
-            .seek(SeekFrom::Start(position as u64))?;
+            .seek(SeekFrom::Start(position.into()))?;
 
         self.open_wav.chunk_size_written = false;
 
-        let channels = self.open_wav.channels().clone();
-        if channels.front_left {
-            (*self.write_sample_to_stream)(
-                &mut self.open_wav.writer,
-                samples_by_channel.front_left.expect("Left channel missing"),
-            )?;
-        }
-        if channels.front_right {
-            (*self.write_sample_to_stream)(
-                &mut self.open_wav.writer,
-                samples_by_channel
-                    .front_right
-                    .expect("Right channel missing"),
-            )?;
-        }
-        if channels.front_center {
-            (*self.write_sample_to_stream)(
-                &mut self.open_wav.writer,
-                samples_by_channel
-                    .front_center
-                    .expect("Center channel missing"),
-            )?;
-        }
-        if channels.low_frequency {
-            (*self.write_sample_to_stream)(
-                &mut self.open_wav.writer,
-                samples_by_channel
-                    .low_frequency
-                    .expect("Low frequency channel missing"),
-            )?;
-        }
-        if channels.back_left {
-            (*self.write_sample_to_stream)(
-                &mut self.open_wav.writer,
-                samples_by_channel
-                    .back_left
-                    .expect("Back left channel missing"),
-            )?;
-        }
-        if channels.back_right {
-            (*self.write_sample_to_stream)(
-                &mut self.open_wav.writer,
-                samples_by_channel
-                    .back_right
-                    .expect("Back right channel missing"),
-            )?;
-        }
-        if channels.front_left_of_center {
-            (*self.write_sample_to_stream)(
-                &mut self.open_wav.writer,
-                samples_by_channel
-                    .front_left_of_center
-                    .expect("Front left of center channel missing"),
-            )?;
-        }
-        if channels.front_right_of_center {
-            (*self.write_sample_to_stream)(
-                &mut self.open_wav.writer,
-                samples_by_channel
-                    .front_right_of_center
-                    .expect("Front right of center channel missing"),
-            )?;
-        }
-        if channels.back_center {
-            (*self.write_sample_to_stream)(
-                &mut self.open_wav.writer,
-                samples_by_channel
-                    .back_center
-                    .expect("Back center channel missing"),
-            )?;
-        }
-        if channels.side_left {
-            (*self.write_sample_to_stream)(
-                &mut self.open_wav.writer,
-                samples_by_channel
-                    .side_left
-                    .expect("Side left channel missing"),
-            )?;
-        }
-        if channels.side_right {
-            (*self.write_sample_to_stream)(
-                &mut self.open_wav.writer,
-                samples_by_channel
-                    .side_right
-                    .expect("Side right channel missing"),
-            )?;
-        }
-        if channels.top_center {
-            (*self.write_sample_to_stream)(
-                &mut self.open_wav.writer,
-                samples_by_channel
-                    .top_center
-                    .expect("Top center channel missing"),
-            )?;
-        }
-        if channels.top_front_left {
-            (*self.write_sample_to_stream)(
-                &mut self.open_wav.writer,
-                samples_by_channel
-                    .top_front_left
-                    .expect("Top front left channel missing"),
-            )?;
+        if self.open_wav.peaks.is_none() {
+            return (*self.write_sample_to_stream)(&mut self.open_wav.writer, &value);
         }
-        if channels.top_front_center {
-            (*self.write_sample_to_stream)(
-                &mut self.open_wav.writer,
-                samples_by_channel
-                    .top_front_center
-                    .expect("Top front center channel missing"),
-            )?;
-        }
-        if channels.top_front_right {
-            (*self.write_sample_to_stream)(
-                &mut self.open_wav.writer,
-                samples_by_channel
-                    .top_front_right
-                    .expect("Top front right channel missing"),
-            )?;
+
+        let mut bytes = Vec::with_capacity(self.open_wav.bytes_per_sample() as usize);
+        (*self.write_sample_to_stream)(&mut bytes, &value)?;
+        self.open_wav
+            .track_channel_peak(sample, channel_index, &bytes)?;
+
+        self.open_wav.writer.write_all(&bytes)
+    }
+
+    /// Appends interleaved samples after the last sample written, as an audio capture callback delivers them.
+    /// Returns the number of samples written
+    ///
+    /// # Arguments
+    ///
+    /// * 'buf' - The samples, interleaved in the order of the wav's channels. Its length must be a multiple of the
+    ///   number of channels
+    pub fn push_interleaved(&mut self, buf: &[T]) -> Result<usize>
+    where
+        T: Copy,
+    {
+        let num_channels = self.open_wav.num_channels() as usize;
+        if buf.len() % num_channels != 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "{} values is not a whole number of samples. Each sample has {} channels",
+                    buf.len(),
+                    num_channels
+                ),
+            ));
         }
-        if channels.top_back_left {
-            (*self.write_sample_to_stream)(
-                &mut self.open_wav.writer,
-                samples_by_channel
-                    .top_back_left
-                    .expect("Top back left channel missing"),
-            )?;
+
+        let samples = buf.len() / num_channels;
+        if self.open_wav.samples_written + samples > self.open_wav.max_samples {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "Wav files can only go up to 4GB.",
+            ));
         }
-        if channels.top_back_center {
-            (*self.write_sample_to_stream)(
-                &mut self.open_wav.writer,
-                samples_by_channel
-                    .top_back_center
-                    .expect("Top back center channel missing"),
-            )?;
+
+        let position = ByteOffset(self.open_wav.data_start)
+            + SampleIndex(self.open_wav.samples_written)
+                .to_byte_offset(self.open_wav.frame_bytes());
+        self.open_wav
+            .writer
+            .seek(SeekFrom::Start(position.into()))?;
+
+        let write_sample_to_stream = &self.write_sample_to_stream;
+        self.open_wav
+            .write_frames_with(self.open_wav.samples_written, |writer| {
+                for value in buf {
+                    write_sample_to_stream(writer, value)?;
+                }
+
+                Ok(())
+            })?;
+
+        self.open_wav.samples_written += samples;
+        self.open_wav.chunk_size_written = false;
+
+        Ok(samples)
+    }
+
+    // Pads the file with silence so that the sample can be written
+    fn pad_to_sample(&mut self, sample: usize) -> Result<()> {
+        if sample >= self.open_wav.max_samples {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "Wav files can only go up to 4GB.",
+            ));
         }
-        if channels.top_back_right {
-            (*self.write_sample_to_stream)(
-                &mut self.open_wav.writer,
-                samples_by_channel
-                    .top_back_right
-                    .expect("Top back right channel missing"),
-            )?;
+
+        if sample >= self.open_wav.samples_written {
+            self.open_wav.writer.seek(SeekFrom::End(0))?;
+
+            let frame_bytes = self.open_wav.frame_bytes();
+            let padding_size = SampleIndex(sample + 1).to_byte_offset(frame_bytes)
+                - SampleIndex(self.open_wav.samples_written).to_byte_offset(frame_bytes);
+            let padding = vec![0u8; 1];
+            for _ in 0..padding_size.0 {
+                self.open_wav.writer.write(&padding)?;
+            }
+            self.open_wav.samples_written = sample + 1;
         }
 
         Ok(())
@@ -300,4 +250,6 @@ impl<T> RandomAccessWavWriter<T> {
     }
 }
 
+// SAFETY: The on_write callback is required to be Send, and the sample writers only wrap WavSample::write_to, which
+// doesn't capture anything. The underlying stream is trusted to be Send, as it was before the callback was added
 unsafe impl<T> Send for RandomAccessWavWriter<T> {}
@@ -1,103 +1,219 @@
 use std::io::{Error, ErrorKind, Result, Seek, SeekFrom, Write};
+use std::iter;
 
-use super::OpenWavWriter;
-use super::SampleFormat;
-use super::WriteEx;
+use super::{OpenWavWriter, WriteSampleToStream};
+use crate::open_wav::OpenWav;
 use crate::samples_by_channel::SamplesByChannel;
+use crate::wav_sample::WavSample;
 
 impl OpenWavWriter {
+    /// Writes all samples of type T. (IE, write_all_samples::<f32>()) Note that downsampling is not supported
+    pub fn write_all_samples<T, TIterator>(self, samples_itr: TIterator) -> Result<()>
+    where
+        T: WavSample,
+        TIterator: Iterator<Item = Result<SamplesByChannel<T>>>,
+    {
+        let write_sample_to_stream = self.sample_writer()?;
+        self.write_all(samples_itr, write_sample_to_stream)
+    }
+
     pub fn write_all_i8<TIterator>(self, samples_itr: TIterator) -> Result<()>
     where
         TIterator: Iterator<Item = Result<SamplesByChannel<i8>>>,
     {
-        match self.header.sample_format {
-            SampleFormat::Int8 => self.write_all(
-                samples_itr,
-                Box::new(|mut writer: &mut dyn Write, value: i8| writer.write_i8(value)),
-            ),
-            SampleFormat::Int16 => self.write_all(
-                samples_itr,
-                Box::new(|mut writer: &mut dyn Write, value: i8| writer.write_i8_as_i16(value)),
-            ),
-            SampleFormat::Int24 => self.write_all(
-                samples_itr,
-                Box::new(|mut writer: &mut dyn Write, value: i8| writer.write_i8_as_i24(value)),
-            ),
-            SampleFormat::Float => self.write_all(
-                samples_itr,
-                Box::new(|mut writer: &mut dyn Write, value: i8| writer.write_i8_as_f32(value)),
-            ),
-        }
+        self.write_all_samples(samples_itr)
     }
 
     pub fn write_all_i16<TIterator>(self, samples_itr: TIterator) -> Result<()>
     where
         TIterator: Iterator<Item = Result<SamplesByChannel<i16>>>,
     {
-        match self.header.sample_format {
-            SampleFormat::Int16 => self.write_all(
-                samples_itr,
-                Box::new(|mut writer: &mut dyn Write, value: i16| writer.write_i16(value)),
-            ),
-            SampleFormat::Int24 => self.write_all(
-                samples_itr,
-                Box::new(|mut writer: &mut dyn Write, value: i16| writer.write_i16_as_i24(value)),
-            ),
-            SampleFormat::Float => self.write_all(
-                samples_itr,
-                Box::new(|mut writer: &mut dyn Write, value: i16| writer.write_i16_as_f32(value)),
-            ),
-            _ => Err(Error::new(
-                ErrorKind::InvalidData,
-                "Converting to 16-bit int unsupported",
-            )),
-        }
+        self.write_all_samples(samples_itr)
     }
 
     pub fn write_all_i24<TIterator>(self, samples_itr: TIterator) -> Result<()>
     where
         TIterator: Iterator<Item = Result<SamplesByChannel<i32>>>,
     {
-        match self.header.sample_format {
-            SampleFormat::Int24 => self.write_all(
-                samples_itr,
-                Box::new(|mut writer: &mut dyn Write, value: i32| writer.write_i24(value)),
-            ),
-            SampleFormat::Float => self.write_all(
-                samples_itr,
-                Box::new(|mut writer: &mut dyn Write, value: i32| writer.write_i24_as_f32(value)),
-            ),
-            _ => Err(Error::new(
-                ErrorKind::InvalidData,
-                "Converting to 24-bit int unsupported",
-            )),
-        }
+        self.write_all_samples(samples_itr)
     }
 
     pub fn write_all_f32<TIterator>(self, samples_itr: TIterator) -> Result<()>
     where
         TIterator: Iterator<Item = Result<SamplesByChannel<f32>>>,
     {
-        match self.header.sample_format {
-            SampleFormat::Float => self.write_all(
-                samples_itr,
-                Box::new(|mut writer: &mut dyn Write, value: f32| writer.write_f32(value)),
-            ),
-            _ => Err(Error::new(
-                ErrorKind::InvalidData,
-                "Converting to float int unsupported",
-            )),
+        self.write_all_samples(samples_itr)
+    }
+
+    /// Writes a mono wav from individual samples, without SamplesByChannel
+    ///
+    /// # Arguments
+    ///
+    /// * 'samples_itr' - The samples. The wav must have exactly one channel
+    pub fn write_all_mono_f32<TIterator>(self, samples_itr: TIterator) -> Result<()>
+    where
+        TIterator: Iterator<Item = Result<f32>>,
+    {
+        self.write_all_frames(samples_itr.map(|sample_result| sample_result.map(|sample| [sample])))
+    }
+
+    /// Writes a stereo wav from [left, right] frames, without SamplesByChannel
+    ///
+    /// # Arguments
+    ///
+    /// * 'frames_itr' - The frames, with each channel in the order that the wav's channels are written. The wav must
+    ///   have exactly two channels
+    pub fn write_all_stereo_f32<TIterator>(self, frames_itr: TIterator) -> Result<()>
+    where
+        TIterator: Iterator<Item = Result<[f32; 2]>>,
+    {
+        self.write_all_frames(frames_itr)
+    }
+
+    /// Writes a wav from one iterator per channel. (IE, when each channel is synthesized separately) Each sample
+    /// takes one value from every channel's iterator, and writing stops when any iterator ends
+    ///
+    /// # Arguments
+    ///
+    /// * 'iters' - An iterator for each of the wav's channels. There must be exactly one iterator for each channel
+    pub fn write_all_per_channel_f32(
+        self,
+        mut iters: SamplesByChannel<Box<dyn Iterator<Item = Result<f32>>>>,
+    ) -> Result<()> {
+        // Each of the wav's channels needs an iterator
+        let channels = *self.channels();
+        iters.check_channels(&channels, 0)?;
+
+        let samples_itr = iter::from_fn(move || {
+            let mut samples_by_channel = SamplesByChannel::new();
+            for position in channels.positions() {
+                match iters.get_mut(position)?.next()? {
+                    Ok(sample) => samples_by_channel.set(position, sample),
+                    Err(err) => return Some(Err(err)),
+                }
+            }
+
+            Some(Ok(samples_by_channel))
+        });
+
+        self.write_all_f32(samples_itr)
+    }
+
+    /// Writes a wav from a 2D array, where each row is a sample and each column is a channel. (IE, audio from
+    /// scientific tools.) Columns are in the order that the wav's channels are written. Requires the ndarray feature
+    ///
+    /// # Arguments
+    ///
+    /// * 'data' - The samples, as samples × channels. There must be exactly one column for each of the wav's channels
+    #[cfg(feature = "ndarray")]
+    pub fn write_all_ndarray_f32(self, data: ndarray::ArrayView2<f32>) -> Result<()> {
+        if data.ncols() != self.num_channels() as usize {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "The array has {} channels, but the wav has {} channels",
+                    data.ncols(),
+                    self.num_channels()
+                ),
+            ));
+        }
+
+        let channels = *self.channels();
+        let samples_itr = data.rows().into_iter().map(move |row| {
+            let mut samples_by_channel = SamplesByChannel::new();
+            for (position, sample) in channels.positions().zip(row.iter()) {
+                samples_by_channel.set(position, *sample);
+            }
+
+            Ok(samples_by_channel)
+        });
+
+        self.write_all_f32(samples_itr)
+    }
+
+    // Writes frames of exactly N channels
+    fn write_all_frames<const N: usize, TIterator>(self, frames_itr: TIterator) -> Result<()>
+    where
+        TIterator: Iterator<Item = Result<[f32; N]>>,
+    {
+        if self.num_channels() as usize != N {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Frames have {} channels, but the wav has {} channels",
+                    N,
+                    self.num_channels()
+                ),
+            ));
         }
+
+        let channels = *self.channels();
+        let samples_itr = frames_itr.map(move |frame_result| {
+            let frame = frame_result?;
+            let mut samples_by_channel = SamplesByChannel::new();
+            for (position, sample) in channels.positions().zip(frame) {
+                samples_by_channel.set(position, sample);
+            }
+
+            Ok(samples_by_channel)
+        });
+
+        self.write_all_f32(samples_itr)
+    }
+
+    /// Writes all samples, like write_all_f32, but flushes the underlying writer's buffer every 'every' samples.
+    /// This bounds how much is buffered in memory during very long writes, (IE, a multi-hour capture,) and makes
+    /// the samples visible to other processes sooner. The header's sizes are only updated once all samples are
+    /// written
+    ///
+    /// # Arguments
+    ///
+    /// * 'samples_itr' - The samples to write
+    /// * 'every' - The number of samples to write between flushes. Must be at least 1
+    pub fn write_all_f32_flush_every<TIterator>(
+        self,
+        samples_itr: TIterator,
+        every: usize,
+    ) -> Result<()>
+    where
+        TIterator: Iterator<Item = Result<SamplesByChannel<f32>>>,
+    {
+        if every == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Must write at least 1 sample between flushes",
+            ));
+        }
+
+        let write_sample_to_stream = self.sample_writer()?;
+        self.write_all_flushing(samples_itr, write_sample_to_stream, Some(every))
     }
 
     pub fn write_all<T, TIterator>(
+        self,
+        samples_itr: TIterator,
+        write_sample_to_stream: WriteSampleToStream<T>,
+    ) -> Result<()>
+    where
+        T: Copy,
+        TIterator: Iterator<Item = Result<SamplesByChannel<T>>>,
+    {
+        self.write_all_flushing(samples_itr, write_sample_to_stream, None)
+    }
+
+    // Writes all samples, and optionally flushes the writer's buffer every 'flush_every' samples
+    fn write_all_flushing<T, TIterator>(
         mut self,
         samples_itr: TIterator,
-        write_sample_to_stream: Box<dyn Fn(&mut dyn Write, T) -> Result<()>>,
+        write_sample_to_stream: WriteSampleToStream<T>,
+        flush_every: Option<usize>,
     ) -> Result<()>
     where
+        T: Copy,
         TIterator: Iterator<Item = Result<SamplesByChannel<T>>>,
     {
+        self.start_data()?;
+
         let position = self.data_start as u64;
 
         self.writer.seek(SeekFrom::Start(position as u64))?;
@@ -115,151 +231,26 @@ impl OpenWavWriter {
             }
 
             let samples_by_channel = samples_result?;
+            samples_by_channel.check_channels(&channels, self.samples_written)?;
 
-            if channels.front_left {
-                write_sample_to_stream(
-                    &mut self.writer,
-                    samples_by_channel.front_left.expect("Left channel missing"),
-                )?;
-            }
-            if channels.front_right {
-                write_sample_to_stream(
-                    &mut self.writer,
-                    samples_by_channel
-                        .front_right
-                        .expect("Right channel missing"),
-                )?;
-            }
-            if channels.front_center {
-                write_sample_to_stream(
-                    &mut self.writer,
-                    samples_by_channel
-                        .front_center
-                        .expect("Center channel missing"),
-                )?;
-            }
-            if channels.low_frequency {
-                write_sample_to_stream(
-                    &mut self.writer,
-                    samples_by_channel
-                        .low_frequency
-                        .expect("Low frequency channel missing"),
-                )?;
-            }
-            if channels.back_left {
-                write_sample_to_stream(
-                    &mut self.writer,
-                    samples_by_channel
-                        .back_left
-                        .expect("Back left channel missing"),
-                )?;
-            }
-            if channels.back_right {
-                write_sample_to_stream(
-                    &mut self.writer,
-                    samples_by_channel
-                        .back_right
-                        .expect("Back right channel missing"),
-                )?;
-            }
-            if channels.front_left_of_center {
-                write_sample_to_stream(
-                    &mut self.writer,
-                    samples_by_channel
-                        .front_left_of_center
-                        .expect("Front left of center channel missing"),
-                )?;
-            }
-            if channels.front_right_of_center {
-                write_sample_to_stream(
-                    &mut self.writer,
-                    samples_by_channel
-                        .front_right_of_center
-                        .expect("Front right of center channel missing"),
-                )?;
-            }
-            if channels.back_center {
-                write_sample_to_stream(
-                    &mut self.writer,
-                    samples_by_channel
-                        .back_center
-                        .expect("Back center channel missing"),
-                )?;
-            }
-            if channels.side_left {
-                write_sample_to_stream(
-                    &mut self.writer,
-                    samples_by_channel
-                        .side_left
-                        .expect("Side left channel missing"),
-                )?;
-            }
-            if channels.side_right {
-                write_sample_to_stream(
-                    &mut self.writer,
-                    samples_by_channel
-                        .side_right
-                        .expect("Side right channel missing"),
-                )?;
-            }
-            if channels.top_center {
-                write_sample_to_stream(
-                    &mut self.writer,
-                    samples_by_channel
-                        .top_center
-                        .expect("Top center channel missing"),
-                )?;
-            }
-            if channels.top_front_left {
-                write_sample_to_stream(
-                    &mut self.writer,
-                    samples_by_channel
-                        .top_front_left
-                        .expect("Top front left channel missing"),
-                )?;
-            }
-            if channels.top_front_center {
-                write_sample_to_stream(
-                    &mut self.writer,
-                    samples_by_channel
-                        .top_front_center
-                        .expect("Top front center channel missing"),
-                )?;
-            }
-            if channels.top_front_right {
-                write_sample_to_stream(
-                    &mut self.writer,
-                    samples_by_channel
-                        .top_front_right
-                        .expect("Top front right channel missing"),
-                )?;
-            }
-            if channels.top_back_left {
-                write_sample_to_stream(
-                    &mut self.writer,
-                    samples_by_channel
-                        .top_back_left
-                        .expect("Top back left channel missing"),
-                )?;
-            }
-            if channels.top_back_center {
-                write_sample_to_stream(
-                    &mut self.writer,
-                    samples_by_channel
-                        .top_back_center
-                        .expect("Top back center channel missing"),
-                )?;
-            }
-            if channels.top_back_right {
-                write_sample_to_stream(
-                    &mut self.writer,
-                    samples_by_channel
-                        .top_back_right
-                        .expect("Top back right channel missing"),
-                )?;
-            }
+            self.write_frames_with(self.samples_written, |writer| {
+                for position in channels.positions() {
+                    let sample = samples_by_channel
+                        .get(position)
+                        .unwrap_or_else(|| panic!("{:?} channel missing", position));
+                    write_sample_to_stream(writer, *sample)?;
+                }
+
+                Ok(())
+            })?;
 
             self.samples_written += 1;
+
+            if let Some(every) = flush_every {
+                if self.samples_written % every == 0 {
+                    self.writer.flush()?;
+                }
+            }
         }
 
         self.flush()?;
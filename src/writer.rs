@@ -1,7 +1,9 @@
 use std::io::{Result, Write};
 
 use crate::assertions::assert_int_24;
-use crate::upconvert::{i16_to_f32, i16_to_i24, i24_to_f32, i8_to_f32, i8_to_i16, i8_to_i24};
+use crate::upconvert::{
+    i16_to_f32, i16_to_i24, i24_to_f32, i24_to_i32, i8_to_f32, i8_to_i16, i8_to_i24,
+};
 
 /// Convenience methods for writing to a stream
 pub trait WriteEx: Write {
@@ -12,6 +14,7 @@ pub trait WriteEx: Write {
     fn write_i16_as_i24(&mut self, v: i16) -> Result<()>;
     fn write_u16(&mut self, v: u16) -> Result<()>;
     fn write_f32(&mut self, v: f32) -> Result<()>;
+    fn write_f32_be(&mut self, v: f32) -> Result<()>;
     fn write_i8(&mut self, v: i8) -> Result<()>;
     fn write_i8_as_i24(&mut self, v: i8) -> Result<()>;
     fn write_i8_as_i16(&mut self, v: i8) -> Result<()>;
@@ -19,6 +22,7 @@ pub trait WriteEx: Write {
     fn write_i24_as_f32(&mut self, v: i32) -> Result<()>;
     fn write_i16_as_f32(&mut self, v: i16) -> Result<()>;
     fn write_i8_as_f32(&mut self, v: i8) -> Result<()>;
+    fn write_i64(&mut self, v: i64) -> Result<()>;
 }
 
 impl<T> WriteEx for T
@@ -88,6 +92,13 @@ where
         Ok(())
     }
 
+    fn write_f32_be(&mut self, v: f32) -> Result<()> {
+        let bytes = v.to_be_bytes();
+        self.write_all(&bytes)?;
+
+        Ok(())
+    }
+
     fn write_i8(&mut self, v: i8) -> Result<()> {
         let bytes = v.to_le_bytes();
         self.write(&bytes)?;
@@ -98,7 +109,7 @@ where
     fn write_i24(&mut self, v: i32) -> Result<()> {
         assert_int_24(v)?;
 
-        let bytes = (v << 8).to_le_bytes();
+        let bytes = i24_to_i32(v).to_le_bytes();
 
         let bytes = [bytes[1], bytes[2], bytes[3]];
         self.write(&bytes)?;
@@ -120,4 +131,8 @@ where
         let sample_float = i8_to_f32(v)?;
         return self.write_f32(sample_float);
     }
+
+    fn write_i64(&mut self, v: i64) -> Result<()> {
+        self.write_all(&v.to_le_bytes())
+    }
 }